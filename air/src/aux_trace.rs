@@ -0,0 +1,90 @@
+use crypto::rescue::{CYCLE_LENGTH, NUM_ROUNDS};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    matrix::ColMatrix,
+    EvaluationFrame,
+};
+
+use crate::layout::{
+    AUX_TRACE_WIDTH, BITWISE_PERM_COL, FHE_PERM_OFFSET, HPERM_PERM_COL, MEM_PERM_COL, NUM_OVERFLOW_SLOTS,
+    NUM_RANGE_PERM_COLS, NUM_U32_LIMBS, OVERFLOW_PERM_OFFSET, RANGE_PERM_OFFSET,
+};
+use crate::{constrains, fhe_cycle_length, stack_ct_width};
+
+// the auxiliary trace is built from the committed main trace and the drawn random elements
+// memory: running product of the memory requests over the memory table responses
+// overflow: partial products of the running product with the overflow slot requests of the row
+// fhe: partial products with the chiplet row accesses over the pass response, the b read and the pass request
+// range: partial products with the u32 limb requests, the first one over the range chiplet response
+// bitwise: partial product with the byte requests over the bitwise chiplet response
+// hperm: partial product with the permutation request over the hash chiplet response
+pub fn build_aux_trace<E>(main_trace: &ColMatrix<BaseElement>, ct_width: usize, rand: &[E]) -> ColMatrix<E>
+where
+    E: FieldElement<BaseField = BaseElement>,
+{
+    let trace_length = main_trace.num_rows();
+    let cycle = fhe_cycle_length(ct_width);
+
+    let mut frame = EvaluationFrame::new(main_trace.num_cols());
+
+    let mut columns = vec![vec![E::ONE; trace_length]; AUX_TRACE_WIDTH];
+
+    // the partial products of the last row wrap to the first one, it keeps their degree on the random row
+    for step in 0..trace_length {
+        main_trace.read_row_into(step, frame.current_mut());
+        main_trace.read_row_into((step + 1) % trace_length, frame.next_mut());
+
+        let mut product = columns[MEM_PERM_COL][step];
+
+        for i in 0..(NUM_OVERFLOW_SLOTS / 2) {
+            product *= constrains::overflow_request(&frame, 2 * i, rand)
+                * constrains::overflow_request(&frame, 2 * i + 1, rand);
+            columns[OVERFLOW_PERM_OFFSET + i][step] = product;
+        }
+
+        // periodic values of the fhe pass row
+        let last = BaseElement::from((step % cycle == cycle - 1) as u8);
+        let element = BaseElement::from((step % cycle < ct_width) as u8);
+
+        let [write_c, read_a, read_b] = constrains::fhe_memory_requests(&frame, ct_width, element, rand);
+
+        product *= write_c * read_a / constrains::fhe_pass_response(&frame, last, rand);
+        columns[FHE_PERM_OFFSET][step] = product;
+
+        product *= read_b;
+        columns[FHE_PERM_OFFSET + 1][step] = product;
+
+        product *= constrains::fhe_pass_request(&frame, rand);
+        columns[FHE_PERM_OFFSET + 2][step] = product;
+
+        product /= constrains::range_response(&frame, rand);
+
+        for i in 0..NUM_RANGE_PERM_COLS {
+            for j in 0..NUM_U32_LIMBS {
+                product *= constrains::range_request(&frame, NUM_U32_LIMBS * i + j, rand);
+            }
+            columns[RANGE_PERM_OFFSET + i][step] = product;
+        }
+
+        for i in 0..NUM_U32_LIMBS {
+            product *= constrains::bitwise_request(&frame, i, rand);
+        }
+        product /= constrains::bitwise_response(&frame, rand);
+        columns[BITWISE_PERM_COL][step] = product;
+
+        // periodic value of the row after the rounds of a hash cycle
+        let output = BaseElement::from((step % CYCLE_LENGTH == NUM_ROUNDS) as u8);
+
+        product *= constrains::hperm_request(&frame, rand) / constrains::hperm_response(&frame, output, rand);
+        columns[HPERM_PERM_COL][step] = product;
+
+        if step + 1 < trace_length {
+            let request = constrains::memory_request(&frame, stack_ct_width(ct_width), rand);
+            let response = constrains::memory_response(&frame, rand);
+
+            columns[MEM_PERM_COL][step + 1] = product * request / response;
+        }
+    }
+
+    ColMatrix::new(columns)
+}
@@ -1,13 +1,30 @@
 use crypto::rescue::{self, STATE_WIDTH};
-use fhe::{FheElement, ServerKey};
+use fhe::{FheElement, HomomorphicScheme};
 use winterfell::{
-    math::{fields::f128::BaseElement, FieldElement},
+    math::{fields::f128::BaseElement, ExtensionOf, FieldElement},
     EvaluationFrame,
 };
 
 use crate::flags::{
-    is_add, is_add2, is_mul, is_noop, is_push, is_read, is_read2, is_sadd, is_shl, is_shr, is_smul, not_,
-    opcode_to_element,
+    is_add, is_add2, is_and, is_assert, is_assert_eq, is_assertz, is_call, is_csel, is_csel2, is_cswap, is_cswap2,
+    is_ct_add, is_ct_mac, is_ct_read, is_ct_smul, is_drop, is_drop2, is_dup, is_dup2, is_dup_any, is_eq, is_false,
+    is_fend, is_hperm, is_inv, is_loop, is_lt, is_lte, is_mem_load, is_mem_load2, is_mem_store, is_mem_store2,
+    is_movdn, is_movup, is_mul, is_neg, is_neq, is_noop, is_or, is_push, is_push2, is_read, is_read2, is_repeat,
+    is_sadd, is_shl, is_shl_op, is_shr, is_shr_op, is_smul, is_sub, is_swap, is_swap2, is_tend, is_true, is_u32add,
+    is_u32div, is_u32mod, is_u32mul, is_u32sub, is_wend, is_xor, not_, opcode_to_element, EvaluationFrameExtBits, CALL,
+    FEND, LOOP, REPEAT, TEND, WEND,
+};
+use crate::layout::{
+    BITWISE_A_BITS_OFFSET, BITWISE_B_BITS_OFFSET, BITWISE_FLAG_COL, BITWISE_LOOKUP_COL, BITWISE_PERM_COL,
+    BITWISE_RAND_OFFSET, CLK_COL, FHE_ACTIVE_COL, FHE_ADDR, FHE_HANDLE_A_COL, FHE_HANDLE_B_COL, FHE_HANDLE_COL,
+    FHE_INDEX_COL, FHE_MAC_COL, FHE_PERM_OFFSET, FHE_RAND_OFFSET, FHE_SCALAR_COL, FHE_USE_B_COL, FHE_VALUE_OFFSET,
+    HASHER_OP_COL, HASH_STATE_OFFSET, HPERM_FLAG_COL, HPERM_INPUT_OFFSET, HPERM_PERM_COL, HPERM_RAND_OFFSET,
+    HPERM_STATE_OFFSET, IMM_COL, LOOP_CTX_OFFSET, LOOP_CTX_WIDTH, MAX_STACK_DEPTH, MEM_ACCESS_COL, MEM_ADDR_COL,
+    MEM_CLK_COL, MEM_DELTA_OFFSET, MEM_ELEMENT_COL, MEM_NEW_ADDR_COL, MEM_PERM_COL, MEM_WORD_OFFSET, MEM_WORD_WIDTH,
+    MEM_WRITE_COL, NUM_BITWISE_BITS, NUM_MEM_DELTA_LIMBS, NUM_OP_BITS, NUM_OVERFLOW_SLOTS, NUM_RANGE_PERM_COLS,
+    NUM_STACK_FREE_LIMBS, NUM_STACK_ROOM_LIMBS, NUM_U32_LIMBS, OVERFLOW_ADDR, OVERFLOW_PERM_OFFSET,
+    OVERFLOW_SLOTS_OFFSET, RANGE_LOOKUP_COL, RANGE_PERM_OFFSET, RANGE_RAND_OFFSET, RANGE_VALUE_COL, STACK_DEPTH_COL,
+    STACK_FREE_OFFSET, STACK_OFFSET, STACK_ROOM_OFFSET, STACK_WIDTH, U32_FLAG_COL, U32_INV_COL, U32_LIMBS_OFFSET,
 };
 
 trait EvaluationFrameExt<E: FieldElement> {
@@ -21,74 +38,228 @@ trait EvaluationFrameExt<E: FieldElement> {
 
     fn hash(&self) -> &[E];
 
-    fn hash_item(&self, index: usize) -> E;
-
     fn hash_next(&self) -> &[E];
 
     fn hash_item_next(&self, index: usize) -> E;
 
+    fn loop_ctx(&self) -> &[E];
+
+    fn loop_ctx_next(&self) -> &[E];
+
+    fn mem_item(&self, col: usize) -> E;
+
+    fn mem_item_next(&self, col: usize) -> E;
+
+    fn mem_word(&self) -> &[E];
+
+    fn mem_word_next(&self) -> &[E];
+
     fn stack_depth(&self) -> E;
 
     fn stack_depth_next(&self) -> E;
 
+    fn stack_free(&self) -> E;
+
+    fn stack_free_next(&self) -> E;
+
+    fn overflow_slot(&self, index: usize) -> E;
+
+    fn fhe_item(&self, col: usize) -> E;
+
+    fn fhe_item_next(&self, col: usize) -> E;
+
+    fn fhe_values(&self) -> [E; 3];
+
+    fn range_item(&self, col: usize) -> E;
+
+    fn range_item_next(&self, col: usize) -> E;
+
+    fn u32_flag(&self) -> E;
+
+    fn u32_limb(&self, index: usize) -> E;
+
+    fn u32_value(&self, index: usize) -> E;
+
+    fn u32_inv(&self) -> E;
+
+    fn bitwise_flag(&self) -> E;
+
+    fn bitwise_lookup(&self) -> E;
+
+    fn bitwise_bit(&self, offset: usize, index: usize) -> E;
+
+    fn hperm_item(&self, col: usize) -> E;
+
+    fn hperm_item_next(&self, col: usize) -> E;
+
+    fn hperm_state(&self) -> &[E];
+
+    fn hperm_state_next(&self) -> &[E];
+
     fn clk(&self) -> E;
 
     fn clk_next(&self) -> E;
 
+    fn imm(&self) -> E;
+
     fn h0(&self) -> E;
 }
 
 impl<E: FieldElement> EvaluationFrameExt<E> for &EvaluationFrame<E> {
     fn stack_item(&self, index: usize) -> E {
-        self.current()[12 + index]
+        self.current()[STACK_OFFSET + index]
     }
 
     fn stack_items(&self, index: usize, size: usize) -> &[E] {
-        &self.current()[(12 + index)..(12 + index + size)]
+        &self.current()[(STACK_OFFSET + index)..(STACK_OFFSET + index + size)]
     }
 
     fn stack_item_next(&self, index: usize) -> E {
-        self.next()[12 + index]
+        self.next()[STACK_OFFSET + index]
     }
 
     fn stack_items_next(&self, index: usize, size: usize) -> &[E] {
-        &self.next()[(12 + index)..(12 + index + size)]
+        &self.next()[(STACK_OFFSET + index)..(STACK_OFFSET + index + size)]
     }
 
     fn hash(&self) -> &[E] {
-        &self.current()[7..11]
-    }
-
-    fn hash_item(&self, index: usize) -> E {
-        self.current()[7 + index]
+        &self.current()[HASH_STATE_OFFSET..(HASH_STATE_OFFSET + STATE_WIDTH)]
     }
 
     fn hash_next(&self) -> &[E] {
-        &self.next()[7..11]
+        &self.next()[HASH_STATE_OFFSET..(HASH_STATE_OFFSET + STATE_WIDTH)]
     }
 
     fn hash_item_next(&self, index: usize) -> E {
-        self.next()[7 + index]
+        self.next()[HASH_STATE_OFFSET + index]
+    }
+
+    fn loop_ctx(&self) -> &[E] {
+        &self.current()[LOOP_CTX_OFFSET..(LOOP_CTX_OFFSET + LOOP_CTX_WIDTH)]
+    }
+
+    fn loop_ctx_next(&self) -> &[E] {
+        &self.next()[LOOP_CTX_OFFSET..(LOOP_CTX_OFFSET + LOOP_CTX_WIDTH)]
+    }
+
+    fn mem_item(&self, col: usize) -> E {
+        self.current()[col]
+    }
+
+    fn mem_item_next(&self, col: usize) -> E {
+        self.next()[col]
+    }
+
+    fn mem_word(&self) -> &[E] {
+        &self.current()[MEM_WORD_OFFSET..(MEM_WORD_OFFSET + MEM_WORD_WIDTH)]
+    }
+
+    fn mem_word_next(&self) -> &[E] {
+        &self.next()[MEM_WORD_OFFSET..(MEM_WORD_OFFSET + MEM_WORD_WIDTH)]
     }
 
     fn stack_depth(&self) -> E {
-        self.current()[11]
+        self.current()[STACK_DEPTH_COL]
     }
 
     fn stack_depth_next(&self) -> E {
-        self.next()[11]
+        self.next()[STACK_DEPTH_COL]
+    }
+
+    fn stack_free(&self) -> E {
+        free_registers(&self.current()[STACK_FREE_OFFSET..(STACK_FREE_OFFSET + NUM_STACK_FREE_LIMBS)])
+    }
+
+    fn stack_free_next(&self) -> E {
+        free_registers(&self.next()[STACK_FREE_OFFSET..(STACK_FREE_OFFSET + NUM_STACK_FREE_LIMBS)])
+    }
+
+    fn overflow_slot(&self, index: usize) -> E {
+        self.current()[OVERFLOW_SLOTS_OFFSET + index]
+    }
+
+    fn fhe_item(&self, col: usize) -> E {
+        self.current()[col]
+    }
+
+    fn fhe_item_next(&self, col: usize) -> E {
+        self.next()[col]
+    }
+
+    // [a, b, c] of the fhe chiplet row
+    fn fhe_values(&self) -> [E; 3] {
+        [0, 1, 2].map(|i| self.current()[FHE_VALUE_OFFSET + i])
+    }
+
+    fn range_item(&self, col: usize) -> E {
+        self.current()[col]
+    }
+
+    fn range_item_next(&self, col: usize) -> E {
+        self.next()[col]
+    }
+
+    fn u32_flag(&self) -> E {
+        self.current()[U32_FLAG_COL]
+    }
+
+    fn u32_limb(&self, index: usize) -> E {
+        self.current()[U32_LIMBS_OFFSET + index]
+    }
+
+    // u32 value from its little endian byte limbs
+    fn u32_value(&self, index: usize) -> E {
+        (0..NUM_U32_LIMBS).rev().fold(E::ZERO, |acc, j| {
+            acc * E::from(256u32) + self.u32_limb(NUM_U32_LIMBS * index + j)
+        })
+    }
+
+    fn u32_inv(&self) -> E {
+        self.current()[U32_INV_COL]
+    }
+
+    fn bitwise_flag(&self) -> E {
+        self.current()[BITWISE_FLAG_COL]
+    }
+
+    fn bitwise_lookup(&self) -> E {
+        self.current()[BITWISE_LOOKUP_COL]
+    }
+
+    fn bitwise_bit(&self, offset: usize, index: usize) -> E {
+        self.current()[offset + index]
+    }
+
+    fn hperm_item(&self, col: usize) -> E {
+        self.current()[col]
+    }
+
+    fn hperm_item_next(&self, col: usize) -> E {
+        self.next()[col]
+    }
+
+    fn hperm_state(&self) -> &[E] {
+        &self.current()[HPERM_STATE_OFFSET..(HPERM_STATE_OFFSET + STATE_WIDTH)]
+    }
+
+    fn hperm_state_next(&self) -> &[E] {
+        &self.next()[HPERM_STATE_OFFSET..(HPERM_STATE_OFFSET + STATE_WIDTH)]
     }
 
     fn clk(&self) -> E {
-        self.current()[0]
+        self.current()[CLK_COL]
     }
 
     fn clk_next(&self) -> E {
-        self.next()[0]
+        self.next()[CLK_COL]
+    }
+
+    fn imm(&self) -> E {
+        self.current()[IMM_COL]
     }
 
     fn h0(&self) -> E {
-        self.current()[6]
+        self.current()[HASHER_OP_COL]
     }
 }
 
@@ -96,87 +267,399 @@ pub fn enforce_clock_increase<E: FieldElement>(frame: &EvaluationFrame<E>) -> E
     frame.clk_next() - (frame.clk() + E::ONE)
 }
 
-pub fn enforce_stack_shift<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    is_shr(frame) * is_shl(frame)
+pub fn enforce_op_bits<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    for (i, value) in result.iter_mut().enumerate().take(NUM_OP_BITS) {
+        *value = frame.op_bit(i) * not_(frame.op_bit(i));
+    }
 }
 
-pub fn enforce_stack_depth<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    (frame.stack_depth_next() - frame.stack_depth() - is_shr(frame) + is_shl(frame)) - is_read2(frame) * E::from(4u8)
-        + is_add2(frame) * E::from(4u8)
+pub fn enforce_stack_depth<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize) -> E {
+    // ciphertext operations shift the stack by the ciphertext width
+    let ct_shift = E::from(ct_width as u32 - 1);
+
+    (frame.stack_depth_next() - frame.stack_depth() - is_shr(frame) + is_shl(frame))
+        - (is_read2(frame) + is_dup2(frame) + is_push2(frame) + is_mem_load2(frame)) * ct_shift
+        + (is_add2(frame) + is_drop2(frame) + is_mem_store2(frame)) * ct_shift
+        + is_csel(frame)
+        + is_ct_mac(frame)
+        + is_assert_eq(frame)
+        + is_csel2(frame) * E::from(ct_width as u32)
 }
 
 pub fn enforce_add<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
     is_add(frame) * (frame.stack_item_next(0) - (frame.stack_item(0) + frame.stack_item(1)))
 }
 
-pub fn enforce_sadd<E: FieldElement + From<BaseElement>>(frame: &EvaluationFrame<E>, server_key: &ServerKey) -> E {
-    let stack_ct = frame.stack_items(1, server_key.lwe_size());
-    let stack_ct_next = frame.stack_items_next(0, server_key.lwe_size());
+// the ciphertext ops add their element-wise constraints to the ones of the two top ciphertexts of the next row
+// result holds 2w constraints, the op flags are exclusive so the ops share them
+pub fn enforce_ct_ops<E: FieldElement + From<BaseElement>, S: HomomorphicScheme>(
+    frame: &EvaluationFrame<E>,
+    scheme: &S,
+    ct_width: usize,
+    result: &mut [E],
+) {
+    result.fill(E::ZERO);
 
-    let value = FheElement::new(stack_ct);
+    enforce_sadd(frame, scheme, ct_width, result);
+    enforce_add2(frame, scheme, ct_width, result);
+    enforce_smul(frame, scheme, ct_width, result);
+    enforce_push2(frame, scheme, ct_width, result);
+    enforce_dup2(frame, ct_width, result);
+    enforce_swap2(frame, ct_width, result);
+    enforce_drop2(frame, ct_width, result);
+    enforce_cswap2(frame, ct_width, result);
+    enforce_csel2(frame, ct_width, result);
+}
 
-    let output = server_key.scalar_add(&frame.stack_item(0), &value);
+pub fn enforce_sadd<E: FieldElement + From<BaseElement>, S: HomomorphicScheme>(
+    frame: &EvaluationFrame<E>,
+    scheme: &S,
+    ct_width: usize,
+    result: &mut [E],
+) {
+    let value = FheElement::new(frame.stack_items(1, ct_width));
 
-    is_sadd(frame)
-        * stack_ct_next
-            .iter()
-            .zip(output.ciphertext().iter())
-            .map(|(&a, &b)| a - b)
-            .fold(E::ZERO, |acc, sum| acc + sum)
+    let output = scheme.scalar_add(&frame.stack_item(0), &value);
+
+    add_ct_diff(
+        result,
+        is_sadd(frame),
+        frame.stack_items_next(0, ct_width),
+        output.ciphertext(),
+    );
 }
 
-pub fn enforce_add2<E: FieldElement + From<BaseElement>>(frame: &EvaluationFrame<E>, server_key: &ServerKey) -> E {
-    let stack_ct0 = frame.stack_items(0, server_key.lwe_size());
-    let stack_ct1 = frame.stack_items(server_key.lwe_size(), server_key.lwe_size() * 2);
-    let stack_ct_next = frame.stack_items_next(0, server_key.lwe_size());
+pub fn enforce_add2<E: FieldElement + From<BaseElement>, S: HomomorphicScheme>(
+    frame: &EvaluationFrame<E>,
+    scheme: &S,
+    ct_width: usize,
+    result: &mut [E],
+) {
+    let value0 = FheElement::new(frame.stack_items(0, ct_width));
+    let value1 = FheElement::new(frame.stack_items(ct_width, ct_width));
+
+    let output = scheme.add(&value0, &value1);
 
-    let value0 = FheElement::new(stack_ct0);
-    let value1 = FheElement::new(stack_ct1);
+    add_ct_diff(
+        result,
+        is_add2(frame),
+        frame.stack_items_next(0, ct_width),
+        output.ciphertext(),
+    );
+}
+
+pub fn enforce_sub<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    is_sub(frame) * (frame.stack_item_next(0) - (frame.stack_item(1) - frame.stack_item(0)))
+}
 
-    let output = server_key.add(&value0, &value1);
+pub fn enforce_neg<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    is_neg(frame) * (frame.stack_item_next(0) + frame.stack_item(0))
+}
 
-    is_add2(frame)
-        * stack_ct_next
-            .iter()
-            .zip(output.ciphertext().iter())
-            .map(|(&a, &b)| a - b)
-            .fold(E::ZERO, |acc, sum| acc + sum)
+pub fn enforce_inv<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    is_inv(frame) * (frame.stack_item_next(0) * frame.stack_item(0) - E::ONE)
 }
 
 pub fn enforce_mul<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
     is_mul(frame) * (frame.stack_item_next(0) - (frame.stack_item(0) * frame.stack_item(1)))
 }
 
-pub fn enforce_smul<E: FieldElement + From<BaseElement>>(frame: &EvaluationFrame<E>, server_key: &ServerKey) -> E {
-    let stack_ct = frame.stack_items(1, server_key.lwe_size());
-    let stack_ct_next = frame.stack_items_next(0, server_key.lwe_size());
+pub fn enforce_smul<E: FieldElement + From<BaseElement>, S: HomomorphicScheme>(
+    frame: &EvaluationFrame<E>,
+    scheme: &S,
+    ct_width: usize,
+    result: &mut [E],
+) {
+    let value = FheElement::new(frame.stack_items(1, ct_width));
 
-    let value = FheElement::new(stack_ct);
+    let output = scheme.scalar_mul(&frame.stack_item(0), &value);
 
-    let output = server_key.scalar_mul(&frame.stack_item(0), &value);
+    add_ct_diff(
+        result,
+        is_smul(frame),
+        frame.stack_items_next(0, ct_width),
+        output.ciphertext(),
+    );
+}
 
-    is_smul(frame)
-        * stack_ct_next
-            .iter()
-            .zip(output.ciphertext().iter())
-            .map(|(&a, &b)| a - b)
-            .fold(E::ZERO, |acc, sum| acc + sum)
+pub fn enforce_push<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    result[0] = is_push(frame) * (frame.stack_item_next(0) - frame.imm());
+    result[1] = is_push(frame) * (frame.stack_item_next(1) - frame.stack_item(0));
 }
 
-pub fn enforce_push<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    is_push(frame) * (frame.stack_item_next(1) - frame.stack_item(0))
+pub fn enforce_push2<E: FieldElement + From<BaseElement>, S: HomomorphicScheme>(
+    frame: &EvaluationFrame<E>,
+    scheme: &S,
+    ct_width: usize,
+    result: &mut [E],
+) {
+    let output = scheme.encrypt_trivial(&frame.imm());
+
+    add_ct_diff(
+        result,
+        is_push2(frame),
+        frame.stack_items_next(0, ct_width),
+        output.ciphertext(),
+    );
 }
 
 pub fn enforce_read<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    is_read(frame) * (frame.stack_item_next(1) - frame.stack_item(0))
+    (is_read(frame) + is_mem_load(frame) + is_ct_read(frame)) * (frame.stack_item_next(1) - frame.stack_item(0))
 }
 
-pub fn enforce_read2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    is_read2(frame) * (frame.stack_item_next(5) - frame.stack_item(0))
+// read2, mem_load2 and push2 push a ciphertext above the top element
+pub fn enforce_read2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize) -> E {
+    (is_read2(frame) + is_mem_load2(frame) + is_push2(frame)) * (frame.stack_item_next(ct_width) - frame.stack_item(0))
 }
 
 pub fn enforce_noop<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    is_noop(frame) * (frame.stack_item_next(0) - frame.stack_item(0))
+    let flag = is_noop(frame)
+        + is_tend(frame)
+        + is_fend(frame)
+        + is_loop(frame)
+        + is_call(frame)
+        + is_repeat(frame)
+        + is_wend(frame);
+
+    flag * (frame.stack_item_next(0) - frame.stack_item(0))
+}
+
+pub fn enforce_dup<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    result[0] = (0..STACK_WIDTH)
+        .map(|n| is_dup(frame, n) * (frame.stack_item_next(0) - frame.stack_item(n)))
+        .fold(E::ZERO, |acc, sum| acc + sum);
+
+    result[1] = is_dup_any(frame) * (frame.stack_item_next(1) - frame.stack_item(0));
+}
+
+pub fn enforce_swap<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    result[0] = is_swap(frame) * (frame.stack_item_next(0) - frame.stack_item(1));
+    result[1] = is_swap(frame) * (frame.stack_item_next(1) - frame.stack_item(0));
+}
+
+pub fn enforce_drop<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    (is_drop(frame) + is_mem_store(frame)) * (frame.stack_item_next(0) - frame.stack_item(1))
+}
+
+pub fn enforce_movup<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    (2..STACK_WIDTH)
+        .map(|n| is_movup(frame, n) * (frame.stack_item_next(0) - frame.stack_item(n)))
+        .fold(E::ZERO, |acc, sum| acc + sum)
+}
+
+pub fn enforce_movdn<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    (2..STACK_WIDTH)
+        .map(|n| is_movdn(frame, n) * (frame.stack_item_next(n) - frame.stack_item(0)))
+        .fold(E::ZERO, |acc, sum| acc + sum)
+}
+
+pub fn enforce_dup2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    let (ct0, ct1) = result.split_at_mut(ct_width);
+    let stack_ct = frame.stack_items(0, ct_width);
+
+    add_ct_diff(ct0, is_dup2(frame), frame.stack_items_next(0, ct_width), stack_ct);
+    add_ct_diff(
+        ct1,
+        is_dup2(frame),
+        frame.stack_items_next(ct_width, ct_width),
+        stack_ct,
+    );
+}
+
+pub fn enforce_swap2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    let (ct0, ct1) = result.split_at_mut(ct_width);
+    let stack_ct0 = frame.stack_items(0, ct_width);
+    let stack_ct1 = frame.stack_items(ct_width, ct_width);
+
+    add_ct_diff(ct0, is_swap2(frame), frame.stack_items_next(0, ct_width), stack_ct1);
+    add_ct_diff(
+        ct1,
+        is_swap2(frame),
+        frame.stack_items_next(ct_width, ct_width),
+        stack_ct0,
+    );
+}
+
+pub fn enforce_drop2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    add_ct_diff(
+        result,
+        is_drop2(frame) + is_mem_store2(frame),
+        frame.stack_items_next(0, ct_width),
+        frame.stack_items(ct_width, ct_width),
+    );
+}
+
+// the condition of a conditional operation is binary
+pub fn enforce_condition<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    let c = frame.stack_item(0);
+    (is_cswap(frame) + is_csel(frame) + is_cswap2(frame) + is_csel2(frame)) * c * not_(c)
+}
+
+pub fn enforce_cswap<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let c = frame.stack_item(0);
+    let (b, a) = (frame.stack_item(1), frame.stack_item(2));
+
+    result[0] = is_cswap(frame) * (frame.stack_item_next(0) - (c * a + not_(c) * b));
+    result[1] = is_cswap(frame) * (frame.stack_item_next(1) - (c * b + not_(c) * a));
+}
+
+pub fn enforce_csel<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    let c = frame.stack_item(0);
+    let (b, a) = (frame.stack_item(1), frame.stack_item(2));
+
+    is_csel(frame) * (frame.stack_item_next(0) - (c * b + not_(c) * a))
+}
+
+pub fn enforce_cswap2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    let c = frame.stack_item(0);
+    let stack_ct0 = select(
+        c,
+        frame.stack_items(ct_width + 1, ct_width),
+        frame.stack_items(1, ct_width),
+    );
+    let stack_ct1 = select(
+        c,
+        frame.stack_items(1, ct_width),
+        frame.stack_items(ct_width + 1, ct_width),
+    );
+
+    let (ct0, ct1) = result.split_at_mut(ct_width);
+
+    add_ct_diff(ct0, is_cswap2(frame), frame.stack_items_next(0, ct_width), &stack_ct0);
+    add_ct_diff(
+        ct1,
+        is_cswap2(frame),
+        frame.stack_items_next(ct_width, ct_width),
+        &stack_ct1,
+    );
+}
+
+pub fn enforce_csel2<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    let c = frame.stack_item(0);
+    let stack_ct = select(
+        c,
+        frame.stack_items(1, ct_width),
+        frame.stack_items(ct_width + 1, ct_width),
+    );
+
+    add_ct_diff(result, is_csel2(frame), frame.stack_items_next(0, ct_width), &stack_ct);
+}
+
+// the branch guard pops the condition of the executed branch
+pub fn enforce_branch<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    result[0] = is_true(frame) * (frame.stack_item(0) - E::ONE);
+    result[1] = is_false(frame) * frame.stack_item(0);
+}
+
+// the assertions pop the checked elements
+pub fn enforce_assert<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    result[0] = is_assert(frame) * (frame.stack_item(0) - E::ONE);
+    result[1] = is_assertz(frame) * frame.stack_item(0);
+    result[2] = is_assert_eq(frame) * (frame.stack_item(0) - frame.stack_item(1));
+}
+
+// the u32 operations flag their row for the range chiplet lookups
+pub fn enforce_u32_flag<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    let flag = is_u32add(frame) + is_u32sub(frame) + is_u32mul(frame) + is_u32div(frame) + is_u32mod(frame);
+    let bitwise = is_and(frame) + is_or(frame) + is_xor(frame) + is_shl_op(frame) + is_shr_op(frame);
+    let compare = is_lt(frame) + is_lte(frame);
+
+    frame.u32_flag() - flag - bitwise - compare
+}
+
+// the operands a and b are the first two u32 values
+// shl and shr shift a on top of the stack by the power of two of the op value
+pub fn enforce_u32_operands<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let shift = is_shl_op(frame) + is_shr_op(frame);
+    let flag = frame.u32_flag() - shift;
+
+    result[0] = flag * (frame.stack_item(1) - frame.u32_value(0)) + shift * (frame.stack_item(0) - frame.u32_value(0));
+    result[1] = flag * (frame.stack_item(0) - frame.u32_value(1)) + shift * (frame.imm() - frame.u32_value(1));
+}
+
+// u32add and u32sub push the binary carry or borrow over the u32 value c
+pub fn enforce_u32add<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (b, a) = (frame.stack_item(0), frame.stack_item(1));
+    let (carry, c) = (frame.stack_item_next(0), frame.stack_item_next(1));
+    let modulus = u32_modulus();
+    let flag = is_u32add(frame) + is_u32sub(frame);
+
+    result[0] = is_u32add(frame) * (a + b - c - carry * modulus);
+    result[1] = is_u32sub(frame) * (a - b - c + carry * modulus);
+    result[2] = flag * carry * not_(carry);
+    result[3] = flag * (c - frame.u32_value(2));
+}
+
+// u32mul pushes the overflow flag over the low word, the flag is 1 when the high word is not 0
+pub fn enforce_u32mul<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (b, a) = (frame.stack_item(0), frame.stack_item(1));
+    let (overflow, lo) = (frame.stack_item_next(0), frame.stack_item_next(1));
+    let hi = frame.u32_value(3);
+    let flag = is_u32mul(frame);
+
+    result[0] = flag * (a * b - frame.u32_value(2) - hi * u32_modulus());
+    result[1] = flag * (lo - frame.u32_value(2));
+    result[2] = flag * (overflow - hi * frame.u32_inv());
+    result[3] = flag * hi * not_(overflow);
+}
+
+// the division takes q and r with a = q * b + r, b - r - 1 is a u32 value so r < b
+pub fn enforce_u32div<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (b, a) = (frame.stack_item(0), frame.stack_item(1));
+    let (q, r) = (frame.u32_value(2), frame.u32_value(3));
+    let flag = is_u32div(frame) + is_u32mod(frame);
+
+    result[0] = flag * (a - q * b - r);
+    result[1] = flag * (frame.u32_value(4) - (b - r - E::ONE));
+    result[2] = is_u32div(frame) * (frame.stack_item_next(0) - q);
+    result[3] = is_u32mod(frame) * (frame.stack_item_next(0) - r);
+}
+
+// shl splits a * 2^n in a low and a high word, shr divides a by 2^n with a remainder below 2^n
+pub fn enforce_shift<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (a, power) = (frame.u32_value(0), frame.u32_value(1));
+    let (x, y) = (frame.u32_value(2), frame.u32_value(3));
+    let (shl, shr) = (is_shl_op(frame), is_shr_op(frame));
+
+    result[0] = shl * (a * power - x - y * u32_modulus());
+    result[1] = shl * (frame.stack_item_next(0) - x);
+    result[2] = shr * (a - x * power - y);
+    result[3] = shr * (frame.u32_value(4) - (power - y - E::ONE));
+    result[4] = shr * (frame.stack_item_next(0) - x);
+}
+
+// the bitwise operations flag their row for the bitwise chiplet lookups
+// and pushes a and b, xor a xor b and or their sum
+pub fn enforce_bitwise<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (and, xor) = (frame.u32_value(2), frame.u32_value(3));
+    let next = frame.stack_item_next(0);
+
+    result[0] = frame.bitwise_flag() - (is_and(frame) + is_or(frame) + is_xor(frame));
+    result[1] = is_and(frame) * (next - and);
+    result[2] = is_or(frame) * (next - and - xor);
+    result[3] = is_xor(frame) * (next - xor);
+}
+
+// lt pushes the borrow of a - b and lte the negated borrow of b - a, the difference c is a u32 value
+pub fn enforce_compare<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let (a, b, c) = (frame.u32_value(0), frame.u32_value(1), frame.u32_value(2));
+    let flag = frame.stack_item_next(0);
+    let modulus = u32_modulus();
+
+    result[0] = is_lt(frame) * (a - b - c + flag * modulus);
+    result[1] = is_lte(frame) * (b - a - c + not_(flag) * modulus);
+    result[2] = (is_lt(frame) + is_lte(frame)) * flag * not_(flag);
+}
+
+// eq pushes 1 - d * inv and neq d * inv, where d = a - b and inv its inverse or 0
+// the result times d is 0 for eq and d for neq, so inv cannot turn a difference into equality
+pub fn enforce_eq<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let diff = frame.stack_item(1) - frame.stack_item(0);
+    let flag = frame.stack_item_next(0);
+    let (eq, neq) = (is_eq(frame), is_neq(frame));
+
+    result[0] = eq * (flag + diff * frame.u32_inv() - E::ONE) + neq * (flag - diff * frame.u32_inv());
+    result[1] = eq * diff * flag + neq * diff * not_(flag);
 }
 
 pub fn enforce_hash_round<E: FieldElement + From<BaseElement>>(
@@ -185,32 +668,752 @@ pub fn enforce_hash_round<E: FieldElement + From<BaseElement>>(
     ark: &[E],
     result: &mut [E],
 ) {
-    let mut step0 = frame.hash().to_vec();
+    let injected = [opcode_to_element(frame), frame.imm()];
+    let (step0, step1) = round_halves(frame.hash(), frame.hash_next(), ark, injected);
+
+    result[0] = (step1[0] - step0[0]) * hash_flag * frame.h0();
+    result[1] = (step1[1] - step0[1]) * hash_flag * frame.h0();
+    result[2] = (step1[2] - step0[2]) * hash_flag * frame.h0();
+    result[3] = (step1[3] - step0[3]) * hash_flag * frame.h0();
+}
+
+// the state after the first half of the round with the injected values and the next state before the second half
+fn round_halves<E: FieldElement + From<BaseElement>>(
+    state: &[E],
+    next: &[E],
+    ark: &[E],
+    injected: [E; 2],
+) -> (Vec<E>, Vec<E>) {
+    let mut step0 = state.to_vec();
     rescue::apply_sbox(&mut step0);
     rescue::apply_mds(&mut step0);
     for i in 0..STATE_WIDTH {
         step0[i] += ark[i];
     }
 
-    step0[0] += opcode_to_element(frame);
-    step0[1] += frame.stack_item_next(0) * is_push(frame);
+    step0[0] += injected[0];
+    step0[1] += injected[1];
 
-    let mut step1 = frame.hash_next().to_vec();
+    let mut step1 = next.to_vec();
     for i in 0..STATE_WIDTH {
         step1[i] -= ark[STATE_WIDTH + i];
     }
     rescue::apply_inv_mds(&mut step1);
     rescue::apply_sbox(&mut step1);
 
-    result[0] = (step1[0] - step0[0]) * hash_flag * frame.h0();
-    result[1] = (step1[1] - step0[1]) * hash_flag * frame.h0();
-    result[2] = (step1[2] - step0[2]) * hash_flag * frame.h0();
-    result[3] = (step1[3] - step0[3]) * hash_flag * frame.h0();
+    (step0, step1)
 }
 
+// the flag is binary, the flag and the input state are kept through the rounds of a cycle
+// the first row holds the input state, a flagged cycle applies the rounds without injected values
+pub fn enforce_hperm_table<E: FieldElement + From<BaseElement>>(
+    frame: &EvaluationFrame<E>,
+    hash_flag: E,
+    first: E,
+    ark: &[E],
+    result: &mut [E],
+) {
+    let flag = frame.hperm_item(HPERM_FLAG_COL);
+
+    result[0] = flag * not_(flag);
+    result[1] = (frame.hperm_item_next(HPERM_FLAG_COL) - flag) * hash_flag;
+
+    for i in 0..STATE_WIDTH {
+        let input = frame.hperm_item(HPERM_INPUT_OFFSET + i);
+        result[2 + i] = (frame.hperm_item_next(HPERM_INPUT_OFFSET + i) - input) * hash_flag;
+        result[2 + STATE_WIDTH + i] = (input - frame.hperm_state()[i]) * first;
+    }
+
+    let (step0, step1) = round_halves(frame.hperm_state(), frame.hperm_state_next(), ark, [E::ZERO; 2]);
+
+    for i in 0..STATE_WIDTH {
+        result[2 + 2 * STATE_WIDTH + i] = (step1[i] - step0[i]) * hash_flag * flag;
+    }
+}
+
+// operations on copy steps are not absorbed, only noop, tend, fend, loop, call, repeat and wend are allowed
+// the trace past the program end is padded with noop
+pub fn enforce_copy_ops<E: FieldElement>(frame: &EvaluationFrame<E>, hash_flag: E) -> E {
+    let op = opcode_to_element(frame);
+
+    [TEND, FEND, LOOP, CALL, REPEAT, WEND]
+        .iter()
+        .fold(op, |acc, &code| acc * (op - E::from(code)))
+        * not_(hash_flag)
+}
+
+// selectors of the copy step operations, valid only on copy steps
+// bit 0 selects tend, bit 1 selects fend, bits 3 and 4 select loop or call, repeat and wend
+// bit 2 tells call from loop
+fn copy_op_flags<E: FieldElement>(frame: &EvaluationFrame<E>) -> [E; 6] {
+    let (bit2, bit3, bit4) = (frame.op_bit(2), frame.op_bit(3), frame.op_bit(4));
+
+    [
+        frame.op_bit(0),
+        frame.op_bit(1),
+        bit3 * not_(bit4),
+        bit2 * bit3,
+        bit4 * not_(bit3),
+        bit3 * bit4,
+    ]
+}
+
+// tend merges into [s0, s1, s3, imm], fend merges into [s1, imm, s3, s0]
+// call clears the state, repeat restores the loop entry [p0, p1, 0, 0], wend merges into [s0, s1, d0, d1]
 pub fn enforce_hash_copy<E: FieldElement>(frame: &EvaluationFrame<E>, hash_flag: E, result: &mut [E]) {
-    result[0] = (frame.hash_item_next(0) - frame.hash_item(0)) * not_(hash_flag) * frame.h0();
-    result[1] = (frame.hash_item_next(1) - frame.hash_item(1)) * not_(hash_flag) * frame.h0();
-    result[2] = frame.hash_item_next(2) * not_(hash_flag) * frame.h0();
-    result[3] = frame.hash_item_next(3) * not_(hash_flag) * frame.h0();
+    let [tend, fend, _, call, repeat, wend] = copy_op_flags(frame);
+    let hash = frame.hash();
+    let ctx = frame.loop_ctx();
+
+    let next = [
+        hash[0] + fend * (hash[1] - hash[0]) + repeat * (ctx[0] - hash[0]) - call * hash[0],
+        hash[1] + fend * (frame.imm() - hash[1]) + repeat * (ctx[1] - hash[1]) - call * hash[1],
+        (tend + fend) * hash[3] + wend * ctx[2],
+        tend * frame.imm() + fend * hash[0] + wend * ctx[3],
+    ];
+
+    for i in 0..STATE_WIDTH {
+        result[i] = (frame.hash_item_next(i) - next[i]) * not_(hash_flag) * frame.h0();
+    }
+}
+
+// an iteration ends with the loop body digest
+pub fn enforce_loop_body<E: FieldElement>(frame: &EvaluationFrame<E>, hash_flag: E, result: &mut [E]) {
+    let [_, _, _, _, repeat, _] = copy_op_flags(frame);
+    let hash = frame.hash();
+    let ctx = frame.loop_ctx();
+
+    result[0] = (hash[0] - ctx[2]) * repeat * not_(hash_flag);
+    result[1] = (hash[1] - ctx[3]) * repeat * not_(hash_flag);
+}
+
+// loop and call shift [s0, s1, c3, imm] into the loop ctx, wend clears it, any other step keeps it
+pub fn enforce_loop_ctx<E: FieldElement>(frame: &EvaluationFrame<E>, hash_flag: E, result: &mut [E]) {
+    let [_, _, enter, _, _, wend] = copy_op_flags(frame);
+    let hash = frame.hash();
+    let ctx = frame.loop_ctx();
+    let ctx_next = frame.loop_ctx_next();
+
+    let entry = [hash[0], hash[1], ctx[3], frame.imm()];
+
+    for i in 0..LOOP_CTX_WIDTH {
+        let update = enter * (entry[i] - ctx[i]) - wend * ctx[i];
+        result[i] = ctx_next[i] - ctx[i] - update * not_(hash_flag);
+    }
+}
+
+// the flags of the next memory table row are binary, padding rows are not writes
+pub fn enforce_memory_flags<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let [write, element, access, new_addr] =
+        [MEM_WRITE_COL, MEM_ELEMENT_COL, MEM_ACCESS_COL, MEM_NEW_ADDR_COL].map(|col| frame.mem_item_next(col));
+
+    result[0] = write * not_(write);
+    result[1] = element * not_(element);
+    result[2] = access * not_(access);
+    result[3] = new_addr * not_(new_addr);
+    result[4] = write * not_(access);
+}
+
+// the memory table is sorted by address then clock
+// the next row keeps the address with a greater clock or moves to a greater address, the delta is in the limbs
+pub fn enforce_memory_order<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let new_addr = frame.mem_item_next(MEM_NEW_ADDR_COL);
+    let addr_delta = frame.mem_item_next(MEM_ADDR_COL) - frame.mem_item(MEM_ADDR_COL);
+    let clk_delta = frame.mem_item_next(MEM_CLK_COL) - frame.mem_item(MEM_CLK_COL);
+
+    let delta = (0..NUM_MEM_DELTA_LIMBS).rev().fold(E::ZERO, |acc, i| {
+        acc * E::from(4u8) + frame.mem_item(MEM_DELTA_OFFSET + i)
+    });
+
+    result[0] = not_(new_addr) * addr_delta;
+    result[1] = delta - (new_addr * (addr_delta - E::ONE) + not_(new_addr) * (clk_delta - E::ONE));
+}
+
+// the delta limbs are in [0, 4)
+pub fn enforce_memory_delta<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    for (i, value) in result.iter_mut().enumerate().take(NUM_MEM_DELTA_LIMBS) {
+        *value = limb_range(frame.mem_item(MEM_DELTA_OFFSET + i));
+    }
+}
+
+// reads and padding rows keep the word of the address, a new address starts from 0
+// scalar writes clear the word but the first element
+pub fn enforce_memory_word<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let write = frame.mem_item_next(MEM_WRITE_COL);
+    let element = frame.mem_item_next(MEM_ELEMENT_COL);
+    let new_addr = frame.mem_item_next(MEM_NEW_ADDR_COL);
+
+    let word = frame.mem_word();
+    let word_next = frame.mem_word_next();
+
+    for i in 0..MEM_WORD_WIDTH {
+        result[i] = not_(write) * (word_next[i] - not_(new_addr) * word[i]);
+        if i > 0 {
+            result[i] += element * write * word_next[i];
+        }
+    }
+}
+
+// memory access of the executed operation, 1 for any other operation
+// loads take the word from the next stack state, stores from the current one
+pub fn memory_request<F, E>(frame: &EvaluationFrame<F>, ct_width: usize, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let (clk, addr) = (frame.clk_next(), frame.imm());
+
+    let requests = [
+        (
+            is_mem_load(frame),
+            memory_tuple(rand, [clk, addr, F::ZERO, F::ONE], frame.stack_items_next(0, 1)),
+        ),
+        (
+            is_mem_store(frame),
+            memory_tuple(rand, [clk, addr, F::ONE, F::ONE], frame.stack_items(0, 1)),
+        ),
+        (
+            is_mem_load2(frame),
+            memory_tuple(rand, [clk, addr, F::ZERO, F::ZERO], frame.stack_items_next(0, ct_width)),
+        ),
+        (
+            is_mem_store2(frame),
+            memory_tuple(rand, [clk, addr, F::ONE, F::ZERO], frame.stack_items(0, ct_width)),
+        ),
+    ];
+
+    requests
+        .into_iter()
+        .fold(E::ONE, |acc, (flag, tuple)| acc + (tuple - E::ONE).mul_base(flag))
+}
+
+// memory access of the next table row, 1 for padding rows
+// scalar accesses only commit to the first element of the word
+pub fn memory_response<F, E>(frame: &EvaluationFrame<F>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let element = frame.mem_item_next(MEM_ELEMENT_COL);
+
+    let word: Vec<F> = frame
+        .mem_word_next()
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| if i == 0 { value } else { not_(element) * value })
+        .collect();
+
+    let access = [
+        frame.mem_item_next(MEM_CLK_COL),
+        frame.mem_item_next(MEM_ADDR_COL),
+        frame.mem_item_next(MEM_WRITE_COL),
+        element,
+    ];
+
+    E::ONE + (memory_tuple(rand, access, &word) - E::ONE).mul_base(frame.mem_item_next(MEM_ACCESS_COL))
+}
+
+// the running product of the requests over the table responses ends at 1 when they are a permutation
+pub fn enforce_memory_perm<F, E>(
+    frame: &EvaluationFrame<F>,
+    aux_frame: &EvaluationFrame<E>,
+    ct_width: usize,
+    rand: &[E],
+) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let product = aux_frame.current()[HPERM_PERM_COL];
+
+    aux_frame.next()[MEM_PERM_COL] * memory_response(frame, rand) - product * memory_request(frame, ct_width, rand)
+}
+
+// the free register limbs are in [0, 4)
+pub fn enforce_stack_free<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    for (i, value) in result.iter_mut().enumerate().take(NUM_STACK_FREE_LIMBS) {
+        *value = limb_range(frame.current()[STACK_FREE_OFFSET + i]);
+    }
+}
+
+// the room limbs are in [0, 4) and make up the maximum depth minus the depth, the depth is at most the maximum
+pub fn enforce_stack_room<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let limbs = &frame.current()[STACK_ROOM_OFFSET..(STACK_ROOM_OFFSET + NUM_STACK_ROOM_LIMBS)];
+
+    for (value, &limb) in result.iter_mut().zip(limbs) {
+        *value = limb_range(limb);
+    }
+
+    result[NUM_STACK_ROOM_LIMBS] = E::from(MAX_STACK_DEPTH) - frame.stack_depth() - free_registers(limbs);
+}
+
+// the overflow slot flags are binary
+// a shift flags its slots unless the deeper state has no overflow, d - 16 + free = 0 for 16 elements or less
+pub fn enforce_overflow_slots<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, result: &mut [E]) {
+    let stack_width = E::from(STACK_WIDTH as u32);
+    let overflow = frame.stack_depth() - stack_width + frame.stack_free();
+    let overflow_next = frame.stack_depth_next() - stack_width + frame.stack_free_next();
+
+    for (m, value) in result.iter_mut().enumerate().take(NUM_OVERFLOW_SLOTS) {
+        let slot = frame.overflow_slot(m);
+        *value = slot * not_(slot);
+    }
+
+    for m in 0..=ct_width {
+        let (shr, shl) = shift_slot_flags(frame, ct_width, m);
+        result[NUM_OVERFLOW_SLOTS + m] = not_(frame.overflow_slot(m)) * (shr * overflow_next + shl * overflow);
+    }
+}
+
+// overflow slot m moves register 15 - m, 1 when the slot is not flagged
+// right shifts write it from the current state, left shifts read it into the next state
+// the address is OVERFLOW_ADDR + m + the depth of the shallower state
+pub fn overflow_request<F, E>(frame: &EvaluationFrame<F>, m: usize, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let (shr, shl) = (is_shr(frame), is_shl(frame));
+    let register = STACK_WIDTH - 1 - m;
+
+    let depth = shr * frame.stack_depth() + shl * frame.stack_depth_next();
+    let addr = F::from(OVERFLOW_ADDR) + F::from(m as u32) + depth;
+    let value = shr * frame.stack_item(register) + shl * frame.stack_item_next(register);
+
+    let tuple = memory_tuple(rand, [frame.clk_next(), addr, shr, F::ONE], &[value]);
+
+    E::ONE + (tuple - E::ONE).mul_base(frame.overflow_slot(m))
+}
+
+// the partial products multiply the running product by two overflow slot requests each
+pub fn enforce_overflow_perm<F, E>(
+    frame: &EvaluationFrame<F>,
+    aux_frame: &EvaluationFrame<E>,
+    rand: &[E],
+    result: &mut [E],
+) where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let aux = aux_frame.current();
+
+    for i in 0..(NUM_OVERFLOW_SLOTS / 2) {
+        let product = if i == 0 {
+            aux[MEM_PERM_COL]
+        } else {
+            aux[OVERFLOW_PERM_OFFSET + i - 1]
+        };
+        let requests = overflow_request(frame, 2 * i, rand) * overflow_request(frame, 2 * i + 1, rand);
+
+        result[i] = aux[OVERFLOW_PERM_OFFSET + i] - product * requests;
+    }
+}
+
+// the fhe flags are binary, a pass is a read, a mac or padding
+pub fn enforce_fhe_flags<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let [active, mac, use_b] = [FHE_ACTIVE_COL, FHE_MAC_COL, FHE_USE_B_COL].map(|col| frame.fhe_item(col));
+
+    result[0] = active * not_(active);
+    result[1] = mac * not_(mac);
+    result[2] = use_b * not_(use_b);
+    result[3] = (active - mac) * not_(active - mac);
+}
+
+// a pass keeps its flags, operands and scalar up to its last row, the next pass takes the next handle
+// the element index counts the rows of the pass
+pub fn enforce_fhe_pass<E: FieldElement>(frame: &EvaluationFrame<E>, last: E, result: &mut [E]) {
+    let header = [
+        FHE_ACTIVE_COL,
+        FHE_MAC_COL,
+        FHE_USE_B_COL,
+        FHE_HANDLE_A_COL,
+        FHE_HANDLE_B_COL,
+        FHE_SCALAR_COL,
+    ];
+
+    for (value, col) in result.iter_mut().zip(header) {
+        *value = not_(last) * (frame.fhe_item_next(col) - frame.fhe_item(col));
+    }
+
+    result[6] = frame.fhe_item_next(FHE_HANDLE_COL) - frame.fhe_item(FHE_HANDLE_COL) - last;
+    result[7] = frame.fhe_item_next(FHE_INDEX_COL) - not_(last) * (frame.fhe_item(FHE_INDEX_COL) + E::ONE);
+}
+
+// mac passes compute c = use_b * b + scalar * a, read passes take the input
+pub fn enforce_fhe_mac<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    let [a, b, c] = frame.fhe_values();
+    let (use_b, scalar) = (frame.fhe_item(FHE_USE_B_COL), frame.fhe_item(FHE_SCALAR_COL));
+
+    frame.fhe_item(FHE_MAC_COL) * (c - use_b * b - scalar * a)
+}
+
+// memory accesses of the fhe chiplet row [write c, read a, read b], 1 when not flagged
+// rows past the ciphertext width and padding passes are idle, reads pass only write c and smul skips b
+// the pass of handle h reads a at 3h, b at 3h + 1 and writes c at 3h + 2, element i is at FHE_ADDR + h * w + i
+pub fn fhe_memory_requests<F, E>(frame: &EvaluationFrame<F>, ct_width: usize, element: F, rand: &[E]) -> [E; 3]
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let [a, b, c] = frame.fhe_values();
+    let [active, mac, use_b] = [FHE_ACTIVE_COL, FHE_MAC_COL, FHE_USE_B_COL].map(|col| frame.fhe_item(col));
+    let [handle, handle_a, handle_b] =
+        [FHE_HANDLE_COL, FHE_HANDLE_A_COL, FHE_HANDLE_B_COL].map(|col| frame.fhe_item(col));
+
+    let index = frame.fhe_item(FHE_INDEX_COL);
+    let addr = |handle: F| F::from(FHE_ADDR) + handle * F::from(ct_width as u32) + index;
+    let time = handle * F::from(3u8);
+
+    let accesses = [
+        (
+            active * element,
+            memory_tuple(rand, [time + F::from(2u8), addr(handle), F::ONE, F::ONE], &[c]),
+        ),
+        (
+            mac * element,
+            memory_tuple(rand, [time, addr(handle_a), F::ZERO, F::ONE], &[a]),
+        ),
+        (
+            mac * use_b * element,
+            memory_tuple(rand, [time + F::ONE, addr(handle_b), F::ZERO, F::ONE], &[b]),
+        ),
+    ];
+
+    accesses.map(|(flag, tuple)| E::ONE + (tuple - E::ONE).mul_base(flag))
+}
+
+// fhe pass of the executed ciphertext op, 1 for any other operation
+// the op pushes the handle of the pass, ct_add adds s0 to s1, ct_smul multiplies s1 by s0 and ct_mac adds it to s2
+pub fn fhe_pass_request<F, E>(frame: &EvaluationFrame<F>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let handle = frame.stack_item_next(0);
+    let [s0, s1, s2] = [0, 1, 2].map(|i| frame.stack_item(i));
+    let (zero, one) = (F::ZERO, F::ONE);
+
+    let requests = [
+        (is_ct_read(frame), [handle, zero, zero, zero, one, zero]),
+        (is_ct_add(frame), [handle, s0, s1, one, zero, one]),
+        (is_ct_smul(frame), [handle, s1, zero, s0, zero, zero]),
+        (is_ct_mac(frame), [handle, s1, s2, s0, zero, one]),
+    ];
+
+    requests.into_iter().fold(E::ONE, |acc, (flag, pass)| {
+        acc + (fhe_pass_tuple(rand, pass) - E::ONE).mul_base(flag)
+    })
+}
+
+// the pass of the fhe chiplet row on its last row, 1 for any other row and padding passes
+pub fn fhe_pass_response<F, E>(frame: &EvaluationFrame<F>, last: F, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let [handle, handle_a, handle_b, scalar, active, mac, use_b] = [
+        FHE_HANDLE_COL,
+        FHE_HANDLE_A_COL,
+        FHE_HANDLE_B_COL,
+        FHE_SCALAR_COL,
+        FHE_ACTIVE_COL,
+        FHE_MAC_COL,
+        FHE_USE_B_COL,
+    ]
+    .map(|col| frame.fhe_item(col));
+
+    let pass = [handle, handle_a, handle_b, scalar, active - mac, use_b];
+
+    E::ONE + (fhe_pass_tuple(rand, pass) - E::ONE).mul_base(active * last)
+}
+
+// the partial products multiply the last overflow partial product by the row write and a read over the pass response
+// then by the b read and by the pass request of the stack
+pub fn enforce_fhe_perm<F, E>(
+    frame: &EvaluationFrame<F>,
+    aux_frame: &EvaluationFrame<E>,
+    ct_width: usize,
+    [last, element]: [F; 2],
+    rand: &[E],
+    result: &mut [E],
+) where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let aux = aux_frame.current();
+    let product = aux[OVERFLOW_PERM_OFFSET + NUM_OVERFLOW_SLOTS / 2 - 1];
+    let [write_c, read_a, read_b] = fhe_memory_requests(frame, ct_width, element, rand);
+
+    result[0] = aux[FHE_PERM_OFFSET] * fhe_pass_response(frame, last, rand) - product * write_c * read_a;
+    result[1] = aux[FHE_PERM_OFFSET + 1] - aux[FHE_PERM_OFFSET] * read_b;
+    result[2] = aux[FHE_PERM_OFFSET + 2] - aux[FHE_PERM_OFFSET + 1] * fhe_pass_request(frame, rand);
+}
+
+// the lookup flag is binary, the sorted values step by 0 or 1
+pub fn enforce_range_table<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let lookup = frame.range_item(RANGE_LOOKUP_COL);
+    let delta = frame.range_item_next(RANGE_VALUE_COL) - frame.range_item(RANGE_VALUE_COL);
+
+    result[0] = lookup * not_(lookup);
+    result[1] = delta * (delta - E::ONE);
+}
+
+// the lookup flag and the bits are binary
+pub fn enforce_bitwise_table<E: FieldElement>(frame: &EvaluationFrame<E>, result: &mut [E]) {
+    let lookup = frame.bitwise_lookup();
+
+    result[0] = lookup * not_(lookup);
+
+    for i in 0..NUM_BITWISE_BITS {
+        let (a, b) = (
+            frame.bitwise_bit(BITWISE_A_BITS_OFFSET, i),
+            frame.bitwise_bit(BITWISE_B_BITS_OFFSET, i),
+        );
+        result[1 + i] = a * not_(a);
+        result[1 + NUM_BITWISE_BITS + i] = b * not_(b);
+    }
+}
+
+// range check of a u32 helper limb, 1 when the row runs no u32 operation
+pub fn range_request<F, E>(frame: &EvaluationFrame<F>, limb: usize, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    E::ONE + (range_tuple(rand, frame.u32_limb(limb)) - E::ONE).mul_base(frame.u32_flag())
+}
+
+// range check answered by the range chiplet row, 1 for filler rows
+pub fn range_response<F, E>(frame: &EvaluationFrame<F>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let value = frame.range_item(RANGE_VALUE_COL);
+
+    E::ONE + (range_tuple(rand, value) - E::ONE).mul_base(frame.range_item(RANGE_LOOKUP_COL))
+}
+
+// the partial products multiply the last fhe partial product by four limb requests each
+// the first one divides by the range chiplet response
+pub fn enforce_range_perm<F, E>(
+    frame: &EvaluationFrame<F>,
+    aux_frame: &EvaluationFrame<E>,
+    rand: &[E],
+    result: &mut [E],
+) where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let aux = aux_frame.current();
+
+    for i in 0..NUM_RANGE_PERM_COLS {
+        let requests =
+            (0..NUM_U32_LIMBS).fold(E::ONE, |acc, j| acc * range_request(frame, NUM_U32_LIMBS * i + j, rand));
+
+        result[i] = match i {
+            0 => aux[RANGE_PERM_OFFSET] * range_response(frame, rand) - aux[FHE_PERM_OFFSET + 2] * requests,
+            _ => aux[RANGE_PERM_OFFSET + i] - aux[RANGE_PERM_OFFSET + i - 1] * requests,
+        };
+    }
+}
+
+// bitwise lookup of the byte i of [a, b, a and b, a xor b], 1 when the row runs no bitwise operation
+pub fn bitwise_request<F, E>(frame: &EvaluationFrame<F>, byte: usize, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let bytes = [0, 1, 2, 3].map(|i| frame.u32_limb(NUM_U32_LIMBS * i + byte));
+
+    E::ONE + (bitwise_tuple(rand, bytes) - E::ONE).mul_base(frame.bitwise_flag())
+}
+
+// bitwise lookup answered by the bitwise chiplet row from the bits of a and b, 1 for padding rows
+pub fn bitwise_response<F, E>(frame: &EvaluationFrame<F>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let mut bytes = [F::ZERO; 4];
+
+    for i in (0..NUM_BITWISE_BITS).rev() {
+        let (a, b) = (
+            frame.bitwise_bit(BITWISE_A_BITS_OFFSET, i),
+            frame.bitwise_bit(BITWISE_B_BITS_OFFSET, i),
+        );
+        let and = a * b;
+        for (byte, bit) in bytes.iter_mut().zip([a, b, and, a + b - and.double()]) {
+            *byte = byte.double() + bit;
+        }
+    }
+
+    E::ONE + (bitwise_tuple(rand, bytes) - E::ONE).mul_base(frame.bitwise_lookup())
+}
+
+// the partial product multiplies the last range partial product by the four byte requests
+// and divides it by the bitwise chiplet response
+pub fn enforce_bitwise_perm<F, E>(frame: &EvaluationFrame<F>, aux_frame: &EvaluationFrame<E>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let aux = aux_frame.current();
+
+    let requests = (0..NUM_U32_LIMBS).fold(E::ONE, |acc, i| acc * bitwise_request(frame, i, rand));
+
+    aux[BITWISE_PERM_COL] * bitwise_response(frame, rand) - aux[RANGE_PERM_OFFSET + NUM_RANGE_PERM_COLS - 1] * requests
+}
+
+// permutation lookup of the top four stack elements before and after hperm, 1 when the row runs no hperm
+pub fn hperm_request<F, E>(frame: &EvaluationFrame<F>, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let tuple = hperm_tuple(
+        rand,
+        frame.stack_items(0, STATE_WIDTH),
+        frame.stack_items_next(0, STATE_WIDTH),
+    );
+
+    E::ONE + (tuple - E::ONE).mul_base(is_hperm(frame))
+}
+
+// permutation lookup answered by the row after the rounds of a flagged cycle, 1 for the other rows
+pub fn hperm_response<F, E>(frame: &EvaluationFrame<F>, output: F, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let input = &frame.current()[HPERM_INPUT_OFFSET..(HPERM_INPUT_OFFSET + STATE_WIDTH)];
+    let tuple = hperm_tuple(rand, input, frame.hperm_state());
+
+    E::ONE + (tuple - E::ONE).mul_base(frame.hperm_item(HPERM_FLAG_COL) * output)
+}
+
+// the partial product multiplies the bitwise partial product by the permutation request
+// and divides it by the hash chiplet response
+pub fn enforce_hperm_perm<F, E>(frame: &EvaluationFrame<F>, aux_frame: &EvaluationFrame<E>, output: F, rand: &[E]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let aux = aux_frame.current();
+
+    aux[HPERM_PERM_COL] * hperm_response(frame, output, rand) - aux[BITWISE_PERM_COL] * hperm_request(frame, rand)
+}
+
+// flags of the right and left shifts moving more than m registers
+// ciphertext shifts move w registers, csel, ct_mac and assert_eq 2 and csel2 w + 1
+fn shift_slot_flags<E: FieldElement>(frame: &EvaluationFrame<E>, ct_width: usize, m: usize) -> (E, E) {
+    if m == 0 {
+        return (is_shr(frame), is_shl(frame));
+    }
+
+    let (mut shr, mut shl) = (E::ZERO, E::ZERO);
+
+    if m < ct_width {
+        shr += is_read2(frame) + is_dup2(frame) + is_push2(frame) + is_mem_load2(frame);
+        shl += is_add2(frame) + is_drop2(frame) + is_mem_store2(frame);
+    }
+    if m < 2 {
+        shl += is_csel(frame) + is_ct_mac(frame) + is_assert_eq(frame);
+    }
+    if m <= ct_width {
+        shl += is_csel2(frame);
+    }
+
+    (shr, shl)
+}
+
+// free registers from their base 4 limbs
+fn free_registers<E: FieldElement>(limbs: &[E]) -> E {
+    limbs.iter().rev().fold(E::ZERO, |acc, &limb| acc * E::from(4u8) + limb)
+}
+
+// l * (l - 1) * (l - 2) * (l - 3)
+fn limb_range<E: FieldElement>(limb: E) -> E {
+    (0..4u8).fold(E::ONE, |acc, j| acc * (limb - E::from(j)))
+}
+
+// random linear combination of [clk, addr, write, element, word], rand[0] keeps it away from 0
+fn memory_tuple<F, E>(rand: &[E], access: [F; 4], word: &[F]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    access
+        .iter()
+        .chain(word)
+        .zip(&rand[1..])
+        .fold(rand[0], |acc, (&value, &r)| acc + r.mul_base(value))
+}
+
+// 2^32 from its square root, field elements only convert from u32
+fn u32_modulus<E: FieldElement>() -> E {
+    E::from(1u32 << 16).square()
+}
+
+// random linear combination of a range checked value with the range random elements
+fn range_tuple<F, E>(rand: &[E], value: F) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    rand[RANGE_RAND_OFFSET] + rand[RANGE_RAND_OFFSET + 1].mul_base(value)
+}
+
+// random linear combination of [a, b, a and b, a xor b] with the bitwise random elements
+fn bitwise_tuple<F, E>(rand: &[E], bytes: [F; 4]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let rand = &rand[BITWISE_RAND_OFFSET..];
+
+    bytes
+        .iter()
+        .zip(&rand[1..])
+        .fold(rand[0], |acc, (&value, &r)| acc + r.mul_base(value))
+}
+
+// random linear combination of the input and output states with the permutation random elements
+fn hperm_tuple<F, E>(rand: &[E], input: &[F], output: &[F]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let rand = &rand[HPERM_RAND_OFFSET..];
+
+    input
+        .iter()
+        .chain(output)
+        .zip(&rand[1..])
+        .fold(rand[0], |acc, (&value, &r)| acc + r.mul_base(value))
+}
+
+// random linear combination of [handle, a, b, scalar, read, use_b] with the fhe random elements
+fn fhe_pass_tuple<F, E>(rand: &[E], pass: [F; 6]) -> E
+where
+    F: FieldElement,
+    E: FieldElement + ExtensionOf<F>,
+{
+    let rand = &rand[FHE_RAND_OFFSET..];
+
+    pass.iter()
+        .zip(&rand[1..])
+        .fold(rand[0], |acc, (&value, &r)| acc + r.mul_base(value))
+}
+
+// c * a + (1 - c) * b element-wise
+fn select<E: FieldElement>(c: E, a: &[E], b: &[E]) -> Vec<E> {
+    a.iter().zip(b.iter()).map(|(&a, &b)| c * a + not_(c) * b).collect()
+}
+
+// adds flag * (a - b) element-wise to the constraints of a ciphertext
+fn add_ct_diff<E: FieldElement>(result: &mut [E], flag: E, a: &[E], b: &[E]) {
+    for ((value, &a), &b) in result.iter_mut().zip(a.iter()).zip(b.iter()) {
+        *value += flag * (a - b);
+    }
 }
@@ -1,89 +1,357 @@
 use winterfell::{math::FieldElement, EvaluationFrame};
 
-pub trait EvaluationFrameExtBits<E: FieldElement> {
-    fn b0(&self) -> E;
+use crate::layout::{NUM_OP_BITS, OP_BITS_OFFSET};
 
-    fn b1(&self) -> E;
+// op codes, must match vm::processor::opcodes
+// the two upper bits select the stack shift: 10 right, 01 left, 00 and 11 none
+pub const NOOP: u8 = 0b00_00000;
+pub const SWAP: u8 = 0b00_00001;
+pub const SWAP2: u8 = 0b00_00010;
+pub const NEG: u8 = 0b00_00011;
+pub const INV: u8 = 0b00_00100;
+pub const TEND: u8 = 0b00_00101;
+pub const FEND: u8 = 0b00_00110;
+pub const LOOP: u8 = 0b00_01000;
+pub const CALL: u8 = 0b00_01100;
+pub const REPEAT: u8 = 0b00_10000;
+pub const WEND: u8 = 0b00_11000;
+pub const U32ADD: u8 = 0b00_10001;
+pub const U32SUB: u8 = 0b00_10010;
+pub const U32MUL: u8 = 0b00_10100;
+pub const SHL: u8 = 0b00_10111;
+pub const SHR: u8 = 0b00_11110;
+pub const HPERM: u8 = 0b00_01001;
+
+pub const PUSH: u8 = 0b10_00000;
+pub const READ: u8 = 0b10_00001;
+pub const READ2: u8 = 0b10_00010;
+pub const DUP2: u8 = 0b10_00011;
+pub const PUSH2: u8 = 0b10_00100;
+pub const MEM_LOAD: u8 = 0b10_00111;
+pub const MEM_LOAD2: u8 = 0b10_01110;
+pub const CT_READ: u8 = 0b10_00101;
+pub const DUP: u8 = 0b10_10000;
 
-    fn b2(&self) -> E;
+pub const ADD: u8 = 0b01_00000;
+pub const MUL: u8 = 0b01_00001;
+pub const SADD: u8 = 0b01_00010;
+pub const ADD2: u8 = 0b01_00011;
+pub const SMUL: u8 = 0b01_00100;
+pub const DROP: u8 = 0b01_00101;
+pub const DROP2: u8 = 0b01_00110;
+pub const SUB: u8 = 0b01_00111;
+pub const CSWAP: u8 = 0b01_01000;
+pub const CSEL: u8 = 0b01_01001;
+pub const CSWAP2: u8 = 0b01_01010;
+pub const CSEL2: u8 = 0b01_01011;
+pub const TRUE: u8 = 0b01_01100;
+pub const FALSE: u8 = 0b01_01101;
+pub const MEM_STORE: u8 = 0b01_01111;
+pub const MEM_STORE2: u8 = 0b01_10111;
+pub const CT_ADD: u8 = 0b01_01110;
+pub const CT_SMUL: u8 = 0b01_10000;
+pub const CT_MAC: u8 = 0b01_10001;
+pub const ASSERT: u8 = 0b01_10010;
+pub const ASSERTZ: u8 = 0b01_10011;
+pub const ASSERT_EQ: u8 = 0b01_10100;
+pub const U32DIV: u8 = 0b01_11000;
+pub const U32MOD: u8 = 0b01_11011;
+pub const AND: u8 = 0b01_10101;
+pub const OR: u8 = 0b01_10110;
+pub const XOR: u8 = 0b01_11101;
+pub const LT: u8 = 0b01_11001;
+pub const LTE: u8 = 0b01_11010;
+pub const EQ: u8 = 0b01_11100;
+pub const NEQ: u8 = 0b01_11110;
 
-    fn b3(&self) -> E;
+pub const MOVUP: u8 = 0b11_00000;
+pub const MOVDN: u8 = 0b11_10000;
+
+pub trait EvaluationFrameExtBits<E: FieldElement> {
+    // bit i of the op code
+    fn op_bit(&self, i: usize) -> E;
+
+    fn b0(&self) -> E;
 
-    fn b4(&self) -> E;
+    fn b1(&self) -> E;
 }
 
 impl<E: FieldElement> EvaluationFrameExtBits<E> for &EvaluationFrame<E> {
-    fn b0(&self) -> E {
-        self.current()[5]
+    fn op_bit(&self, i: usize) -> E {
+        self.current()[OP_BITS_OFFSET + i]
     }
 
-    fn b1(&self) -> E {
-        self.current()[4]
-    }
-
-    fn b2(&self) -> E {
-        self.current()[3]
+    fn b0(&self) -> E {
+        self.op_bit(NUM_OP_BITS - 1)
     }
 
-    fn b3(&self) -> E {
-        self.current()[2]
+    fn b1(&self) -> E {
+        self.op_bit(NUM_OP_BITS - 2)
     }
+}
 
-    fn b4(&self) -> E {
-        self.current()[1]
-    }
+// product of the op bits matching the op code, degree NUM_OP_BITS
+pub fn op_flag<E: FieldElement>(frame: &EvaluationFrame<E>, code: u8) -> E {
+    (0..NUM_OP_BITS)
+        .map(|i| match code >> i & 1 {
+            1 => frame.op_bit(i),
+            _ => not_(frame.op_bit(i)),
+        })
+        .fold(E::ONE, |acc, bit| acc * bit)
 }
 
 pub fn is_shr<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b0()
+    frame.b0() * not_(frame.b1())
 }
 
 pub fn is_shl<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b1()
+    not_(frame.b0()) * frame.b1()
 }
 
 pub fn is_add<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * frame.b1() * not_(frame.b2()) * not_(frame.b3()) * not_(frame.b4())
+    op_flag(frame, ADD)
 }
 
 pub fn is_sadd<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * frame.b1() * not_(frame.b2()) * frame.b3() * not_(frame.b4())
+    op_flag(frame, SADD)
 }
 
 pub fn is_add2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * frame.b1() * not_(frame.b2()) * frame.b3() * frame.b4()
+    op_flag(frame, ADD2)
+}
+
+pub fn is_sub<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, SUB)
+}
+
+pub fn is_neg<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, NEG)
+}
+
+pub fn is_inv<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, INV)
+}
+
+pub fn is_cswap<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CSWAP)
+}
+
+pub fn is_csel<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CSEL)
+}
+
+pub fn is_cswap2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CSWAP2)
+}
+
+pub fn is_csel2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CSEL2)
+}
+
+pub fn is_true<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, TRUE)
+}
+
+pub fn is_false<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, FALSE)
+}
+
+pub fn is_assert<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, ASSERT)
+}
+
+pub fn is_assertz<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, ASSERTZ)
+}
+
+pub fn is_assert_eq<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, ASSERT_EQ)
+}
+
+pub fn is_u32add<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, U32ADD)
+}
+
+pub fn is_u32sub<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, U32SUB)
+}
+
+pub fn is_u32mul<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, U32MUL)
+}
+
+pub fn is_u32div<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, U32DIV)
+}
+
+pub fn is_u32mod<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, U32MOD)
+}
+
+pub fn is_and<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, AND)
+}
+
+pub fn is_or<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, OR)
+}
+
+pub fn is_xor<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, XOR)
+}
+
+pub fn is_eq<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, EQ)
+}
+
+pub fn is_neq<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, NEQ)
+}
+
+pub fn is_lt<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, LT)
+}
+
+pub fn is_lte<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, LTE)
+}
+
+// the shl and shr operations, is_shl and is_shr flag the stack shifts
+pub fn is_shl_op<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, SHL)
+}
+
+pub fn is_shr_op<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, SHR)
+}
+
+pub fn is_hperm<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, HPERM)
+}
+
+pub fn is_tend<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, TEND)
+}
+
+pub fn is_fend<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, FEND)
+}
+
+pub fn is_loop<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, LOOP)
+}
+
+pub fn is_call<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CALL)
+}
+
+pub fn is_repeat<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, REPEAT)
+}
+
+pub fn is_wend<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, WEND)
 }
 
 pub fn is_mul<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * frame.b1() * not_(frame.b2()) * not_(frame.b3()) * frame.b4()
+    op_flag(frame, MUL)
 }
 
 pub fn is_smul<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * frame.b1() * frame.b2() * not_(frame.b3()) * not_(frame.b4())
+    op_flag(frame, SMUL)
 }
 
 pub fn is_push<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b0() * not_(frame.b1()) * not_(frame.b2()) * not_(frame.b3()) * not_(frame.b4())
+    op_flag(frame, PUSH)
+}
+
+pub fn is_push2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, PUSH2)
 }
 
 pub fn is_read<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b0() * not_(frame.b1()) * not_(frame.b2()) * not_(frame.b3()) * frame.b4()
+    op_flag(frame, READ)
 }
 
 pub fn is_read2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b0() * not_(frame.b1()) * not_(frame.b2()) * frame.b3() * not_(frame.b4())
+    op_flag(frame, READ2)
+}
+
+pub fn is_mem_load<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, MEM_LOAD)
+}
+
+pub fn is_mem_load2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, MEM_LOAD2)
+}
+
+pub fn is_mem_store<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, MEM_STORE)
+}
+
+pub fn is_mem_store2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, MEM_STORE2)
+}
+
+pub fn is_ct_read<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CT_READ)
+}
+
+pub fn is_ct_add<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CT_ADD)
+}
+
+pub fn is_ct_smul<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CT_SMUL)
+}
+
+pub fn is_ct_mac<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, CT_MAC)
 }
 
 pub fn is_noop<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    not_(frame.b0()) * not_(frame.b1()) * not_(frame.b2()) * not_(frame.b3()) * not_(frame.b4())
+    op_flag(frame, NOOP)
+}
+
+pub fn is_dup<E: FieldElement>(frame: &EvaluationFrame<E>, n: usize) -> E {
+    op_flag(frame, DUP + n as u8)
+}
+
+// any dup.n, all op codes 0b10_1xxxx
+pub fn is_dup_any<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    is_shr(frame) * frame.op_bit(4)
+}
+
+pub fn is_dup2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, DUP2)
+}
+
+pub fn is_swap<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, SWAP)
+}
+
+pub fn is_swap2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, SWAP2)
+}
+
+pub fn is_drop<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, DROP)
+}
+
+pub fn is_drop2<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
+    op_flag(frame, DROP2)
+}
+
+pub fn is_movup<E: FieldElement>(frame: &EvaluationFrame<E>, n: usize) -> E {
+    op_flag(frame, MOVUP + n as u8)
+}
+
+pub fn is_movdn<E: FieldElement>(frame: &EvaluationFrame<E>, n: usize) -> E {
+    op_flag(frame, MOVDN + n as u8)
 }
 
 pub fn opcode_to_element<E: FieldElement>(frame: &EvaluationFrame<E>) -> E {
-    frame.b0() * E::from(16u8)
-        + frame.b1() * E::from(8u8)
-        + frame.b2() * E::from(4u8)
-        + frame.b3() * E::from(2u8)
-        + frame.b4()
+    (0..NUM_OP_BITS).fold(E::ZERO, |acc, i| acc + frame.op_bit(i) * E::from(1u8 << i))
 }
 
 pub fn not_<E: FieldElement>(bit: E) -> E {
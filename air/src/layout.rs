@@ -0,0 +1,145 @@
+use crypto::rescue::STATE_WIDTH;
+
+// execution trace columns
+// system: clk
+// decoder: op bits (bit i of the op code at OP_BITS_OFFSET + i) | op value
+// chiplets: hasher op bit | sponge state | loop ctx (entry rate, body digest) | permutation table | memory table
+//           | fhe passes | range checks | bitwise lookups
+// stack: depth | registers | free register limbs | room limbs | overflow slot flags | u32 helpers
+pub const CLK_COL: usize = 0;
+
+pub const OP_BITS_OFFSET: usize = 1;
+pub const NUM_OP_BITS: usize = 7;
+pub const IMM_COL: usize = OP_BITS_OFFSET + NUM_OP_BITS;
+
+pub const HASHER_OP_COL: usize = IMM_COL + 1;
+pub const HASH_STATE_OFFSET: usize = HASHER_OP_COL + 1;
+
+pub const LOOP_CTX_OFFSET: usize = HASH_STATE_OFFSET + STATE_WIDTH;
+pub const LOOP_CTX_WIDTH: usize = 4;
+
+// hash chiplet permutations of hperm, one per hash cycle and zeros for padding cycles
+// the rounds run on the first 14 rows and the row after them answers the lookup of the stack
+// flag | state | input state, kept through the cycle
+pub const HPERM_FLAG_COL: usize = LOOP_CTX_OFFSET + LOOP_CTX_WIDTH;
+pub const HPERM_STATE_OFFSET: usize = HPERM_FLAG_COL + 1;
+pub const HPERM_INPUT_OFFSET: usize = HPERM_STATE_OFFSET + STATE_WIDTH;
+
+// memory table rows sorted by address then clock
+// addr | clk | write flag | element flag | access flag | new address flag | delta limbs | word
+pub const MEM_ADDR_COL: usize = HPERM_INPUT_OFFSET + STATE_WIDTH;
+pub const MEM_CLK_COL: usize = MEM_ADDR_COL + 1;
+pub const MEM_WRITE_COL: usize = MEM_CLK_COL + 1;
+pub const MEM_ELEMENT_COL: usize = MEM_WRITE_COL + 1;
+pub const MEM_ACCESS_COL: usize = MEM_ELEMENT_COL + 1;
+pub const MEM_NEW_ADDR_COL: usize = MEM_ACCESS_COL + 1;
+
+// the address or clock delta to the next row in base 4 limbs, deltas are below 2^18
+pub const MEM_DELTA_OFFSET: usize = MEM_NEW_ADDR_COL + 1;
+pub const NUM_MEM_DELTA_LIMBS: usize = 9;
+
+// a word holds a scalar in its first element or a ciphertext
+pub const MEM_WORD_OFFSET: usize = MEM_DELTA_OFFSET + NUM_MEM_DELTA_LIMBS;
+pub const MEM_WORD_WIDTH: usize = 16;
+
+// fhe chiplet rows grouped in passes of a power of two rows above the ciphertext width
+// a pass computes c = use_b * b + scalar * a element-wise into the ciphertext of its handle, reads copy an input
+// executed passes are active, a read pass is an active pass without the mac flag
+// active flag | mac flag | use b flag | element index | handle | handle a | handle b | scalar | a | b | c
+pub const FHE_ACTIVE_COL: usize = MEM_WORD_OFFSET + MEM_WORD_WIDTH;
+pub const FHE_MAC_COL: usize = FHE_ACTIVE_COL + 1;
+pub const FHE_USE_B_COL: usize = FHE_MAC_COL + 1;
+pub const FHE_INDEX_COL: usize = FHE_USE_B_COL + 1;
+pub const FHE_HANDLE_COL: usize = FHE_INDEX_COL + 1;
+pub const FHE_HANDLE_A_COL: usize = FHE_HANDLE_COL + 1;
+pub const FHE_HANDLE_B_COL: usize = FHE_HANDLE_A_COL + 1;
+pub const FHE_SCALAR_COL: usize = FHE_HANDLE_B_COL + 1;
+pub const FHE_VALUE_OFFSET: usize = FHE_SCALAR_COL + 1;
+
+// the ciphertext elements live in the memory table above the overflow table
+// element i of handle h is at FHE_ADDR + h * w + i
+pub const FHE_ADDR: u32 = 1 << 17;
+
+// range chiplet rows sorted by value, from 0 to 255 with steps of 0 or 1
+// value | lookup flag, a lookup row answers a limb of the u32 helpers
+pub const RANGE_VALUE_COL: usize = FHE_VALUE_OFFSET + 3;
+pub const RANGE_LOOKUP_COL: usize = RANGE_VALUE_COL + 1;
+
+// the range chiplet ends at 255 once the trace is longer than its 256 values
+pub const RANGE_MAX: u32 = 255;
+
+// bitwise chiplet rows answer a byte lookup each, from the bits of the two bytes
+// lookup flag | bits of a | bits of b, least significant bit first
+pub const BITWISE_LOOKUP_COL: usize = RANGE_LOOKUP_COL + 1;
+pub const BITWISE_A_BITS_OFFSET: usize = BITWISE_LOOKUP_COL + 1;
+pub const BITWISE_B_BITS_OFFSET: usize = BITWISE_A_BITS_OFFSET + NUM_BITWISE_BITS;
+pub const NUM_BITWISE_BITS: usize = 8;
+
+pub const STACK_DEPTH_COL: usize = BITWISE_B_BITS_OFFSET + NUM_BITWISE_BITS;
+pub const STACK_OFFSET: usize = STACK_DEPTH_COL + 1;
+pub const STACK_WIDTH: usize = 16;
+
+// empty registers of a stack up to 16 elements in base 4 limbs, 0 once elements overflow
+pub const STACK_FREE_OFFSET: usize = STACK_OFFSET + STACK_WIDTH;
+pub const NUM_STACK_FREE_LIMBS: usize = 2;
+
+// the maximum depth minus the depth in base 4 limbs, the overflow addresses stay below FHE_ADDR
+pub const STACK_ROOM_OFFSET: usize = STACK_FREE_OFFSET + NUM_STACK_FREE_LIMBS;
+pub const NUM_STACK_ROOM_LIMBS: usize = 8;
+pub const MAX_STACK_DEPTH: u32 = (1 << 16) - 16;
+
+// slot m flags register 15 - m moving through the overflow table, a shift moves at most 8 registers
+pub const OVERFLOW_SLOTS_OFFSET: usize = STACK_ROOM_OFFSET + NUM_STACK_ROOM_LIMBS;
+pub const NUM_OVERFLOW_SLOTS: usize = 8;
+
+// csel2 moves w + 1 registers, wider ciphertexts only live in the fhe chiplet
+pub const MAX_STACK_CT_WIDTH: usize = NUM_OVERFLOW_SLOTS - 1;
+
+// the overflow table lives in the memory table above the u16 addresses
+// the element p positions above the stack bottom is at OVERFLOW_ADDR + 16 + p, the 16 addresses below hold zeros
+pub const OVERFLOW_ADDR: u32 = 1 << 16;
+
+// u32 operations check their operands and results through the range chiplet
+// u32 flag | byte limbs of the u32 values, little endian | inverse of the product high word | bitwise flag
+// eq and neq only take the inverse column for the inverse of the difference of their operands
+// the values are [a, b, c] for u32add and u32sub, [a, b, lo, hi] for u32mul and [a, b, q, r, b - r - 1] for the division
+// [a, b, a and b, a xor b] for the bitwise operations, [a, 2^n, lo, hi] for shl and [a, 2^n, q, r, 2^n - r - 1] for shr
+// [a, b, c] for lt with c = a - b mod 2^32 and for lte with c = b - a mod 2^32
+pub const U32_FLAG_COL: usize = OVERFLOW_SLOTS_OFFSET + NUM_OVERFLOW_SLOTS;
+pub const U32_LIMBS_OFFSET: usize = U32_FLAG_COL + 1;
+pub const NUM_U32_VALUES: usize = 5;
+pub const NUM_U32_LIMBS: usize = 4;
+pub const U32_INV_COL: usize = U32_LIMBS_OFFSET + NUM_U32_VALUES * NUM_U32_LIMBS;
+
+pub const BITWISE_FLAG_COL: usize = U32_INV_COL + 1;
+
+pub const TRACE_WIDTH: usize = BITWISE_FLAG_COL + 1;
+
+// auxiliary trace columns
+// memory: running product of the memory permutation check | partial products of the overflow slots, two each
+// fhe: partial products of the chiplet row accesses and pass response | b read | pass request of the stack
+// range: partial products of the u32 limb requests over the range chiplet response, four limbs each
+// bitwise: partial product of the byte requests over the bitwise chiplet response
+// hperm: partial product of the permutation request over the hash chiplet response
+pub const MEM_PERM_COL: usize = 0;
+pub const OVERFLOW_PERM_OFFSET: usize = MEM_PERM_COL + 1;
+pub const FHE_PERM_OFFSET: usize = OVERFLOW_PERM_OFFSET + NUM_OVERFLOW_SLOTS / 2;
+pub const RANGE_PERM_OFFSET: usize = FHE_PERM_OFFSET + 3;
+pub const NUM_RANGE_PERM_COLS: usize = NUM_U32_VALUES;
+
+pub const BITWISE_PERM_COL: usize = RANGE_PERM_OFFSET + NUM_RANGE_PERM_COLS;
+
+pub const HPERM_PERM_COL: usize = BITWISE_PERM_COL + 1;
+
+pub const AUX_TRACE_WIDTH: usize = HPERM_PERM_COL + 1;
+
+// memory tuples are compressed with an offset and one element per [clk, addr, write, element, word]
+// fhe pass tuples with their own offset and one element per [handle, a, b, scalar, read, use b]
+// range tuples with their own offset and one element for the value
+// bitwise tuples with their own offset and one element per [a, b, a and b, a xor b]
+// permutation tuples with their own offset and one element per element of the input and output states
+pub const FHE_RAND_OFFSET: usize = 5 + MEM_WORD_WIDTH;
+pub const RANGE_RAND_OFFSET: usize = FHE_RAND_OFFSET + 7;
+pub const BITWISE_RAND_OFFSET: usize = RANGE_RAND_OFFSET + 2;
+pub const HPERM_RAND_OFFSET: usize = BITWISE_RAND_OFFSET + 5;
+pub const NUM_AUX_RAND_ELEMENTS: usize = HPERM_RAND_OFFSET + 1 + 2 * STATE_WIDTH;
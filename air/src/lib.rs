@@ -1,103 +1,312 @@
+mod aux_trace;
 mod constrains;
 mod flags;
+mod layout;
 
-use fhe::ServerKey;
+pub use aux_trace::build_aux_trace;
+pub use layout::{AUX_TRACE_WIDTH, NUM_AUX_RAND_ELEMENTS};
+
+use fhe::{HomomorphicScheme, ServerKey};
 use winterfell::{
-    math::{fields::f128::BaseElement, FieldElement, ToElements},
+    math::{fields::f128::BaseElement, ExtensionOf, FieldElement, StarkField, ToElements},
     Air, AirContext, Assertion, EvaluationFrame, ProofOptions, TraceInfo, TransitionConstraintDegree,
 };
 
 use crypto::{
     rescue,
-    rescue::{CYCLE_LENGTH, DIGEST_SIZE},
+    rescue::{CYCLE_LENGTH, DIGEST_SIZE, NUM_ROUNDS, STATE_WIDTH},
+};
+
+use layout::{
+    CLK_COL, FHE_ACTIVE_COL, FHE_HANDLE_COL, FHE_INDEX_COL, FHE_VALUE_OFFSET, HASH_STATE_OFFSET, LOOP_CTX_OFFSET,
+    LOOP_CTX_WIDTH, MAX_STACK_CT_WIDTH, MEM_PERM_COL, MEM_WORD_OFFSET, MEM_WORD_WIDTH, MEM_WRITE_COL, NUM_BITWISE_BITS,
+    NUM_MEM_DELTA_LIMBS, NUM_OP_BITS, NUM_OVERFLOW_SLOTS, NUM_RANGE_PERM_COLS, NUM_STACK_FREE_LIMBS,
+    NUM_STACK_ROOM_LIMBS, RANGE_MAX, RANGE_VALUE_COL, STACK_DEPTH_COL, STACK_OFFSET, TRACE_WIDTH,
 };
 
 #[cfg(test)]
 mod tests;
 
-pub struct PublicInputs {
+pub struct PublicInputs<S: HomomorphicScheme = ServerKey> {
     program_hash: [BaseElement; DIGEST_SIZE],
+    policy_digest: [BaseElement; DIGEST_SIZE],
     stack_outputs: [BaseElement; 16],
-    server_key: ServerKey,
+    ciphertext_output: Vec<BaseElement>,
+    scheme: S,
 }
 
-impl PublicInputs {
+impl<S: HomomorphicScheme> PublicInputs<S> {
     pub fn new(
         program_hash: [BaseElement; DIGEST_SIZE],
         stack_outputs: [BaseElement; 16],
-        server_key: ServerKey,
-    ) -> PublicInputs {
+        scheme: S,
+    ) -> PublicInputs<S> {
         PublicInputs {
             program_hash,
+            policy_digest: [BaseElement::ZERO; DIGEST_SIZE],
             stack_outputs,
-            server_key,
+            ciphertext_output: Vec::new(),
+            scheme,
         }
     }
+
+    // digest of the input policy, it seeds the proof transcript
+    // a proof only verifies against the policy it was generated with
+    pub fn with_policy_digest(mut self, policy_digest: [BaseElement; DIGEST_SIZE]) -> PublicInputs<S> {
+        self.policy_digest = policy_digest;
+        self
+    }
+
+    // fhe chiplet ciphertext of the handle on top of the output stack
+    // it is asserted on the rows of the pass computing it, an empty output asserts nothing
+    pub fn with_ciphertext_output(mut self, ciphertext: &[BaseElement]) -> PublicInputs<S> {
+        self.ciphertext_output = ciphertext.to_vec();
+        self
+    }
 }
 
-impl ToElements<BaseElement> for PublicInputs {
+impl<S: HomomorphicScheme> ToElements<BaseElement> for PublicInputs<S> {
     fn to_elements(&self) -> Vec<BaseElement> {
         let mut elements = Vec::new();
 
         elements.extend(&self.program_hash);
+        elements.extend(&self.policy_digest);
         elements.extend(&self.stack_outputs);
+        elements.extend(&self.ciphertext_output);
 
         elements
     }
 }
 
-pub struct ProcessorAir {
+pub struct ProcessorAir<S: HomomorphicScheme = ServerKey> {
     context: AirContext<BaseElement>,
     program_hash: [BaseElement; DIGEST_SIZE],
     stack_outputs: [BaseElement; 16],
-    server_key: ServerKey,
+    ciphertext_output: Vec<BaseElement>,
+    output_pass: usize,
+    scheme: S,
 }
 
-impl ProcessorAir {
+impl<S: HomomorphicScheme> ProcessorAir<S> {
     pub fn last_step(&self) -> usize {
         self.trace_length() - self.context().num_transition_exemptions()
     }
 }
 
-impl Air for ProcessorAir {
+// ciphertext ops on the stack are bound to the overflow slots, wider ciphertexts never reach the registers
+pub(crate) fn stack_ct_width(ct_width: usize) -> usize {
+    ct_width.min(MAX_STACK_CT_WIDTH)
+}
+
+// rows of a fhe pass, a power of two above the ciphertext width keeps an idle row per pass
+pub(crate) fn fhe_cycle_length(ct_width: usize) -> usize {
+    (ct_width + 1).next_power_of_two()
+}
+
+// range values step by at most 1 from 0, traces up to 256 rows cannot go past 255
+fn range_max_assertions(trace_length: usize) -> usize {
+    (trace_length > RANGE_MAX as usize + 1) as usize
+}
+
+impl<S: HomomorphicScheme> Air for ProcessorAir<S> {
     type BaseField = BaseElement;
-    type PublicInputs = PublicInputs;
+    type PublicInputs = PublicInputs<S>;
     type GkrProof = ();
     type GkrVerifier = ();
 
-    fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: ProofOptions) -> Self {
-        let degrees = vec![
-            TransitionConstraintDegree::new(1),                             // clk
-            TransitionConstraintDegree::new(5),                             // stack depth
-            TransitionConstraintDegree::new(2),                             // shr or shl
-            TransitionConstraintDegree::new(6),                             // add
-            TransitionConstraintDegree::new(6),                             // sadd
-            TransitionConstraintDegree::new(6),                             // add2
-            TransitionConstraintDegree::new(7),                             // mul
-            TransitionConstraintDegree::new(7),                             // smul
-            TransitionConstraintDegree::new(6),                             // push
-            TransitionConstraintDegree::new(6),                             // read
-            TransitionConstraintDegree::new(6),                             // read2
-            TransitionConstraintDegree::new(6),                             // noop
+    fn new(trace_info: TraceInfo, pub_inputs: PublicInputs<S>, options: ProofOptions) -> Self {
+        assert_eq!(TRACE_WIDTH, trace_info.main_trace_width());
+
+        let mut degrees = vec![
+            TransitionConstraintDegree::new(1), // clk
+            TransitionConstraintDegree::new(7), // stack depth
+        ];
+
+        // op bits are binary
+        degrees.extend((0..NUM_OP_BITS).map(|_| TransitionConstraintDegree::new(2)));
+
+        degrees.extend([
+            TransitionConstraintDegree::new(8),                             // add
+            TransitionConstraintDegree::new(9),                             // mul
+            TransitionConstraintDegree::new(8),                             // push value
+            TransitionConstraintDegree::new(8),                             // push shift
+            TransitionConstraintDegree::new(8),                             // read
+            TransitionConstraintDegree::new(8),                             // read2
+            TransitionConstraintDegree::new(8),                             // noop
+            TransitionConstraintDegree::new(8),                             // dup value
+            TransitionConstraintDegree::new(4),                             // dup shift
+            TransitionConstraintDegree::new(8),                             // swap s0
+            TransitionConstraintDegree::new(8),                             // swap s1
+            TransitionConstraintDegree::new(8),                             // drop
+            TransitionConstraintDegree::new(8),                             // movup
+            TransitionConstraintDegree::new(8),                             // movdn
+            TransitionConstraintDegree::new(8),                             // sub
+            TransitionConstraintDegree::new(8),                             // neg
+            TransitionConstraintDegree::new(9),                             // inv
+            TransitionConstraintDegree::new(7),                             // condition
+            TransitionConstraintDegree::new(9),                             // cswap s0
+            TransitionConstraintDegree::new(9),                             // cswap s1
+            TransitionConstraintDegree::new(9),                             // csel
+            TransitionConstraintDegree::new(8),                             // true branch
+            TransitionConstraintDegree::new(8),                             // false branch
+            TransitionConstraintDegree::new(8),                             // assert
+            TransitionConstraintDegree::new(8),                             // assertz
+            TransitionConstraintDegree::new(8),                             // assert_eq
+            TransitionConstraintDegree::new(7),                             // u32 flag
+            TransitionConstraintDegree::new(8),                             // u32 operand a
+            TransitionConstraintDegree::new(8),                             // u32 operand b
+            TransitionConstraintDegree::new(8),                             // u32add
+            TransitionConstraintDegree::new(8),                             // u32sub
+            TransitionConstraintDegree::new(9),                             // u32add carry
+            TransitionConstraintDegree::new(8),                             // u32add c
+            TransitionConstraintDegree::new(9),                             // u32mul
+            TransitionConstraintDegree::new(8),                             // u32mul lo
+            TransitionConstraintDegree::new(9),                             // u32mul overflow
+            TransitionConstraintDegree::new(9),                             // u32mul high word
+            TransitionConstraintDegree::new(9),                             // u32div
+            TransitionConstraintDegree::new(8),                             // u32div remainder
+            TransitionConstraintDegree::new(8),                             // u32div q
+            TransitionConstraintDegree::new(8),                             // u32mod r
+            TransitionConstraintDegree::new(9),                             // shl
+            TransitionConstraintDegree::new(8),                             // shl lo
+            TransitionConstraintDegree::new(9),                             // shr
+            TransitionConstraintDegree::new(8),                             // shr remainder
+            TransitionConstraintDegree::new(8),                             // shr q
+            TransitionConstraintDegree::new(7),                             // bitwise flag
+            TransitionConstraintDegree::new(8),                             // and
+            TransitionConstraintDegree::new(8),                             // or
+            TransitionConstraintDegree::new(8),                             // xor
+            TransitionConstraintDegree::new(8),                             // lt
+            TransitionConstraintDegree::new(8),                             // lte
+            TransitionConstraintDegree::new(9),                             // lt flag
+            TransitionConstraintDegree::new(9),                             // eq inverse
+            TransitionConstraintDegree::new(9),                             // eq difference
             TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[0] round 0-14
-            TransitionConstraintDegree::with_cycles(7, vec![CYCLE_LENGTH]), // hash[1] round 0-14
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[1] round 0-14
             TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[2] round 0-14
             TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[3] round 0-14
-            TransitionConstraintDegree::with_cycles(2, vec![CYCLE_LENGTH]), // hash[0] round 14-16
-            TransitionConstraintDegree::with_cycles(2, vec![CYCLE_LENGTH]), // hash[1] round 14-16
-            TransitionConstraintDegree::with_cycles(2, vec![CYCLE_LENGTH]), // hash[2] round 14-16
-            TransitionConstraintDegree::with_cycles(2, vec![CYCLE_LENGTH]), // hash[3] round 14-16
-        ];
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[0] round 14-16
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[1] round 14-16
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[2] round 14-16
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hash[3] round 14-16
+            TransitionConstraintDegree::with_cycles(7, vec![CYCLE_LENGTH]), // ops round 14-16
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop body digest[0]
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop body digest[1]
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop ctx[0]
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop ctx[1]
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop ctx[2]
+            TransitionConstraintDegree::with_cycles(3, vec![CYCLE_LENGTH]), // loop ctx[3]
+            TransitionConstraintDegree::new(2),                             // hperm flag
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm flag copy
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[0] copy
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[1] copy
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[2] copy
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[3] copy
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[0] first row
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[1] first row
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[2] first row
+            TransitionConstraintDegree::with_cycles(1, vec![CYCLE_LENGTH]), // hperm input[3] first row
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hperm[0] round 0-14
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hperm[1] round 0-14
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hperm[2] round 0-14
+            TransitionConstraintDegree::with_cycles(4, vec![CYCLE_LENGTH]), // hperm[3] round 0-14
+            TransitionConstraintDegree::new(2),                             // memory write flag
+            TransitionConstraintDegree::new(2),                             // memory element flag
+            TransitionConstraintDegree::new(2),                             // memory access flag
+            TransitionConstraintDegree::new(2),                             // memory new address flag
+            TransitionConstraintDegree::new(2),                             // memory padding write
+            TransitionConstraintDegree::new(2),                             // memory address
+            TransitionConstraintDegree::new(2),                             // memory delta
+        ]);
+
+        // memory delta limbs
+        degrees.extend((0..NUM_MEM_DELTA_LIMBS).map(|_| TransitionConstraintDegree::new(4)));
+
+        // memory word
+        degrees.extend((0..MEM_WORD_WIDTH).map(|_| TransitionConstraintDegree::new(3)));
+
+        let ct_width = pub_inputs.scheme.ciphertext_width();
+        let cycle = fhe_cycle_length(ct_width);
+
+        // fhe flags are binary
+        degrees.extend((0..4).map(|_| TransitionConstraintDegree::new(2)));
+
+        // fhe pass flags, operands and scalar, handle, element index
+        degrees.extend((0..6).map(|_| TransitionConstraintDegree::with_cycles(1, vec![cycle])));
+        degrees.push(TransitionConstraintDegree::new(1));
+        degrees.push(TransitionConstraintDegree::with_cycles(1, vec![cycle]));
+
+        // fhe mac
+        degrees.push(TransitionConstraintDegree::new(3));
+
+        // range lookup flag is binary, range values step by 0 or 1
+        degrees.extend((0..2).map(|_| TransitionConstraintDegree::new(2)));
+
+        // bitwise lookup flag and bits are binary
+        degrees.extend((0..1 + 2 * NUM_BITWISE_BITS).map(|_| TransitionConstraintDegree::new(2)));
+
+        // free register limbs
+        degrees.extend((0..NUM_STACK_FREE_LIMBS).map(|_| TransitionConstraintDegree::new(4)));
+
+        // room limbs and the room of the stack depth
+        degrees.extend((0..NUM_STACK_ROOM_LIMBS).map(|_| TransitionConstraintDegree::new(4)));
+        degrees.push(TransitionConstraintDegree::new(1));
+
+        // overflow slot flags are binary
+        degrees.extend((0..NUM_OVERFLOW_SLOTS).map(|_| TransitionConstraintDegree::new(2)));
+
+        // overflow slots of the shifted registers, the first one is flagged by any shift
+        degrees.push(TransitionConstraintDegree::new(4));
+        degrees.extend((0..stack_ct_width(ct_width)).map(|_| TransitionConstraintDegree::new(9)));
+
+        // elements of the two top ciphertexts of the next row
+        degrees.extend((0..2 * stack_ct_width(ct_width)).map(|_| TransitionConstraintDegree::new(9)));
+
+        // memory permutation and overflow slot partial products
+        let mut aux_degrees = vec![TransitionConstraintDegree::new(9); 1 + NUM_OVERFLOW_SLOTS / 2];
+
+        // fhe row accesses over the pass response, b read, pass request
+        aux_degrees.extend([
+            TransitionConstraintDegree::with_cycles(5, vec![cycle, cycle]),
+            TransitionConstraintDegree::with_cycles(4, vec![cycle]),
+            TransitionConstraintDegree::new(9),
+        ]);
+
+        // range limb requests over the range chiplet response
+        aux_degrees.extend((0..NUM_RANGE_PERM_COLS).map(|_| TransitionConstraintDegree::new(9)));
+
+        // bitwise byte requests over the bitwise chiplet response
+        aux_degrees.push(TransitionConstraintDegree::new(9));
+
+        // hperm request over the hash chiplet response
+        aux_degrees.push(TransitionConstraintDegree::new(9));
+
+        // the output ciphertext is the result of the pass of the output handle
+        // a handle past the passes of the trace wraps to a pass with another handle and fails its handle assertion
+        let num_passes = trace_info.length() / cycle;
+        let output_pass = (pub_inputs.stack_outputs[0].as_int() % num_passes as u128) as usize;
+
+        // the output pass is active and takes the output handle, then one assertion per element
+        let num_ciphertext_assertions = match pub_inputs.ciphertext_output.is_empty() {
+            true => 0,
+            false => 2 + pub_inputs.ciphertext_output.len(),
+        };
+
+        let num_assertions = 46 + range_max_assertions(trace_info.length()) + num_ciphertext_assertions;
 
         // to improve the column degree computation
         // set transitions exemptions to allow random values on last row
-        let air_context = AirContext::new(trace_info, degrees, 22, options).set_num_transition_exemptions(2);
+        let air_context =
+            AirContext::new_multi_segment(trace_info, degrees, aux_degrees, num_assertions, 2, None, options)
+                .set_num_transition_exemptions(2);
 
         ProcessorAir {
             context: air_context,
             program_hash: pub_inputs.program_hash,
             stack_outputs: pub_inputs.stack_outputs,
-            server_key: pub_inputs.server_key,
+            ciphertext_output: pub_inputs.ciphertext_output,
+            output_pass,
+            scheme: pub_inputs.scheme,
         }
     }
 
@@ -107,93 +316,362 @@ impl Air for ProcessorAir {
         periodic_values: &[E],
         result: &mut [E],
     ) {
+        let ct_width = stack_ct_width(self.scheme.ciphertext_width());
+
         // increase clk
         // clk' - (clk + 1) = 0 || deegre 1
         result[0] = constrains::enforce_clock_increase(frame);
 
-        // increse or decrese stack depth by one or five
-        // d' - d - flag_shr + flag_shr - (flag_read2 + flag_add2) * 4 = 0 || deegre 5
-        result[1] = constrains::enforce_stack_depth(frame);
+        // increse or decrese stack depth by one or the ciphertext width
+        // d' - d - flag_shr + flag_shl - (flag_read2 + flag_dup2 + flag_push2 - flag_add2 - flag_drop2) * (w - 1)
+        //    + flag_csel + flag_csel2 * w = 0 || deegre 7
+        result[1] = constrains::enforce_stack_depth(frame, ct_width);
 
-        // ensure shift is a binary operation
-        // flag_shl * flag_shr = 0 || degree 2
-        result[2] = constrains::enforce_stack_shift(frame);
+        // ensure the op bits are binary
+        // b * (1 - b) = 0 || degree 2
+        constrains::enforce_op_bits(frame, &mut result[2..9]);
 
         // add the two top stack elements
-        // s0' - (s1 + s0) = 0 || degree 6
-        result[3] = constrains::enforce_add(frame);
-
-        // add the top scalar and ciphertext stack elements
-        // s[0..5]' - (s0 + s[1..6]) = 0 || degree 6
-        result[4] = constrains::enforce_sadd(frame, &self.server_key);
-
-        // add the top ciphertext stack elements
-        // s[0..5]' - (s0[0..5] + s[5..10]) = 0 || degree 6
-        result[5] = constrains::enforce_add2(frame, &self.server_key);
+        // s0' - (s1 + s0) = 0 || degree 8
+        result[9] = constrains::enforce_add(frame);
 
         // multiply the two top stack elements
-        // s0' - (s1 * s0) = 0 || degree 7
-        result[6] = constrains::enforce_mul(frame);
-
-        // multiply the top scalar and ciphertext stack elements
-        // s[0..5]' - (s0 * s[1..6]) = 0 || degree 7
-        result[7] = constrains::enforce_smul(frame, &self.server_key);
+        // s0' - (s1 * s0) = 0 || degree 9
+        result[10] = constrains::enforce_mul(frame);
 
-        // push a value the top of the stack
-        // (s1' - s0) = 0 || degree 6
-        // Pushed value onto the stack is injected (enforced) into sponge state
-        result[8] = constrains::enforce_push(frame);
+        // push the op value to the top of the stack
+        // (s0' - imm) = 0, (s1' - s0) = 0 || degree 8
+        // the op value is injected (enforced) into sponge state
+        constrains::enforce_push(frame, &mut result[11..13]);
 
         // read an input and push to to the top of the stach
-        // (s1' - s0) = 0 || degree 6
-        result[9] = constrains::enforce_read(frame);
+        // (s1' - s0) = 0 || degree 8
+        result[13] = constrains::enforce_read(frame);
 
-        // read2 a ciphertext and push to to the top of the stach
-        // (s1' - s0) = 0 || degree 6
-        result[10] = constrains::enforce_read2(frame);
+        // read2, mem_load2 and push2 push a ciphertext to the top of the stack
+        // (sw' - s0) = 0 || degree 8
+        result[14] = constrains::enforce_read2(frame, ct_width);
 
         // copy the stack state
-        // (s0' - s0) = 0 || degree 6
-        result[11] = constrains::enforce_noop(frame);
+        // (s0' - s0) = 0 || degree 8
+        result[15] = constrains::enforce_noop(frame);
+
+        // duplicate the n-th stack element
+        // (s0' - sn) = 0 || degree 8
+        // (s1' - s0) = 0 || degree 4, any dup.n
+        constrains::enforce_dup(frame, &mut result[16..18]);
+
+        // swap the two top stack elements
+        // (s0' - s1) = 0, (s1' - s0) = 0 || degree 8
+        constrains::enforce_swap(frame, &mut result[18..20]);
+
+        // drop the top stack element
+        // (s0' - s1) = 0 || degree 8
+        result[20] = constrains::enforce_drop(frame);
+
+        // move the n-th stack element to the top
+        // (s0' - sn) = 0 || degree 8
+        result[21] = constrains::enforce_movup(frame);
+
+        // move the top stack element to the n-th position
+        // (sn' - s0) = 0 || degree 8
+        result[22] = constrains::enforce_movdn(frame);
+
+        // subtract the top stack element from the second one
+        // s0' - (s1 - s0) = 0 || degree 8
+        result[23] = constrains::enforce_sub(frame);
+
+        // negate the top stack element
+        // s0' + s0 = 0 || degree 8
+        result[24] = constrains::enforce_neg(frame);
+
+        // invert the top stack element, the inverse is a nondeterministic witness
+        // s0' * s0 - 1 = 0 || degree 9
+        result[25] = constrains::enforce_inv(frame);
+
+        // the condition of cswap, csel, cswap2 and csel2 is binary
+        // s0 * (1 - s0) = 0 || degree 7, the conditional op codes only differ in the two low bits
+        result[26] = constrains::enforce_condition(frame);
+
+        // swap the two elements below the condition when it is 1
+        // s0' - (s0 * s2 + (1 - s0) * s1) = 0, s1' - (s0 * s1 + (1 - s0) * s2) = 0 || degree 9
+        constrains::enforce_cswap(frame, &mut result[27..29]);
+
+        // select the first element below the condition when it is 1, the second otherwise
+        // s0' - (s0 * s1 + (1 - s0) * s2) = 0 || degree 9
+        result[29] = constrains::enforce_csel(frame);
+
+        // a branch starts popping its condition, 1 for the true branch and 0 for the false branch
+        // s0 - 1 = 0, s0 = 0 || degree 8
+        constrains::enforce_branch(frame, &mut result[30..32]);
+
+        // assert pops a 1, assertz a 0 and assert_eq two equal elements
+        // s0 - 1 = 0, s0 = 0, s0 - s1 = 0 || degree 8
+        constrains::enforce_assert(frame, &mut result[32..35]);
+
+        // u32 operations flag their row and check their values through the range chiplet
+        // u32_flag - (u32add + u32sub + u32mul + u32div + u32mod + and + or + xor + shl + shr + lt + lte) = 0 || degree 7
+        result[35] = constrains::enforce_u32_flag(frame);
+
+        // (u32_flag - shift) * (s1 - v0) + shift * (s0 - v0) = 0 || degree 8
+        // (u32_flag - shift) * (s0 - v1) + shift * (imm - v1) = 0 || degree 8
+        constrains::enforce_u32_operands(frame, &mut result[36..38]);
+
+        // s1 + s0 - s1' - s0' * 2^32 = 0, s1 - s0 - s1' + s0' * 2^32 = 0, s0' * (1 - s0') = 0 || degree 9
+        // s1' - v2 = 0 || degree 8
+        constrains::enforce_u32add(frame, &mut result[38..42]);
+
+        // s1 * s0 - v2 - v3 * 2^32 = 0, s1' - v2 = 0 || degree 9
+        // s0' - v3 * inv = 0, v3 * (1 - s0') = 0 || degree 9
+        constrains::enforce_u32mul(frame, &mut result[42..46]);
+
+        // s1 - v2 * s0 - v3 = 0, v4 - (s0 - v3 - 1) = 0, s0' - v2 = 0 (u32div), s0' - v3 = 0 (u32mod) || degree 9
+        constrains::enforce_u32div(frame, &mut result[46..50]);
+
+        // shl and shr shift the top stack element by the power of two of the op value
+        // s0 * imm - v2 - v3 * 2^32 = 0, s0' - v2 = 0 (shl) || degree 9
+        // s0 - v2 * imm - v3 = 0, v4 - (imm - v3 - 1) = 0, s0' - v2 = 0 (shr) || degree 9
+        constrains::enforce_shift(frame, &mut result[50..55]);
+
+        // bitwise_flag - (and + or + xor) = 0 || degree 7
+        // s0' - v2 = 0 (and), s0' - v2 - v3 = 0 (or), s0' - v3 = 0 (xor) || degree 8
+        constrains::enforce_bitwise(frame, &mut result[55..59]);
+
+        // lt and lte push the borrow of a u32 difference, a - b for lt and b - a negated for lte
+        // s1 - s0 - v2 + s0' * 2^32 = 0, s0 - s1 - v2 + (1 - s0') * 2^32 = 0 || degree 8, s0' * (1 - s0') = 0 || degree 9
+        constrains::enforce_compare(frame, &mut result[59..62]);
+
+        // eq and neq push the equality of the two top stack elements, the inverse is a nondeterministic witness
+        // s0' + (s1 - s0) * inv - 1 = 0, (s1 - s0) * s0' = 0 (eq) || degree 9
+        // s0' - (s1 - s0) * inv = 0, (s1 - s0) * (1 - s0') = 0 (neq) || degree 9
+        constrains::enforce_eq(frame, &mut result[62..64]);
 
         // Rescue-Prime
         let hash_flag = periodic_values[0];
         let ark = &periodic_values[1..];
 
         // apply hash round
-        constrains::enforce_hash_round(frame, hash_flag, ark, &mut result[12..16]);
+        constrains::enforce_hash_round(frame, hash_flag, ark, &mut result[64..68]);
 
         // copy hash state and reset capacity values to 0
-        constrains::enforce_hash_copy(frame, hash_flag, &mut result[16..20]);
+        // tend and fend merge the executed branch digest with the sibling digest
+        // call clears the state, repeat restores the loop entry rate, wend merges the body digest || degree 4
+        constrains::enforce_hash_copy(frame, hash_flag, &mut result[68..72]);
+
+        // only noop, tend, fend, loop, call, repeat and wend run on copy steps
+        // op * (op - tend) * (op - fend) * (op - loop) * (op - call) * (op - repeat) * (op - wend) = 0 || degree 7
+        result[72] = constrains::enforce_copy_ops(frame, hash_flag);
+
+        // an iteration ends with the hash state equal to the loop body digest
+        // (s0 - d0) = 0, (s1 - d1) = 0 || degree 3
+        constrains::enforce_loop_body(frame, hash_flag, &mut result[73..75]);
+
+        // loop and call record the entry rate and the body digest, wend clears them
+        // c' - c - (loop * ([s0, s1, c3, imm] - c) - wend * c) = 0 || degree 3
+        constrains::enforce_loop_ctx(frame, hash_flag, &mut result[75..79]);
+
+        // hperm permutations of the hash chiplet, the flag and the input state are kept through the rounds
+        // f * (1 - f) = 0, mask * (f' - f) = 0, mask * (x' - x) = 0, first * (x - h) = 0 || degree 2
+        // apply the rounds without injected values to the flagged cycles || degree 4
+        let hperm_first = periodic_values[HPERM_PERIODIC_OFFSET];
+        constrains::enforce_hperm_table(frame, hash_flag, hperm_first, ark, &mut result[79..93]);
+
+        // the memory table flags are binary, padding rows are not writes
+        // w * (1 - w) = 0, w * (1 - m) = 0 || degree 2
+        constrains::enforce_memory_flags(frame, &mut result[93..98]);
+
+        // the memory table is sorted by address then clock
+        // (1 - n') * (addr' - addr) = 0, delta - (n' * (addr' - addr - 1) + (1 - n') * (clk' - clk - 1)) = 0 || degree 2
+        constrains::enforce_memory_order(frame, &mut result[98..100]);
+
+        // the delta limbs are in [0, 4)
+        // l * (l - 1) * (l - 2) * (l - 3) = 0 || degree 4
+        constrains::enforce_memory_delta(frame, &mut result[100..109]);
+
+        // reads keep the word of the address, scalar writes clear the word but the first element
+        // (1 - w') * (v' - (1 - n') * v) + e' * w' * v' = 0 || degree 3
+        constrains::enforce_memory_word(frame, &mut result[109..125]);
+
+        // the fhe flags are binary, a pass is a read, a mac or padding
+        // f * (1 - f) = 0, (active - mac) * (1 - active + mac) = 0 || degree 2
+        constrains::enforce_fhe_flags(frame, &mut result[125..129]);
+
+        // a pass keeps its flags, operands and scalar, the next one takes the next handle and restarts the index
+        // (1 - last) * (x' - x) = 0, h' - h - last = 0, i' - (1 - last) * (i + 1) = 0 || degree 1
+        let fhe_last = periodic_values[FHE_PERIODIC_OFFSET];
+        constrains::enforce_fhe_pass(frame, fhe_last, &mut result[129..137]);
+
+        // mac passes compute the ciphertext element-wise
+        // mac * (c - use_b * b - scalar * a) = 0 || degree 3
+        result[137] = constrains::enforce_fhe_mac(frame);
+
+        // the range lookup flag is binary, the sorted values step by 0 or 1
+        // f * (1 - f) = 0, (v' - v) * (v' - v - 1) = 0 || degree 2
+        constrains::enforce_range_table(frame, &mut result[138..140]);
+
+        // the bitwise lookup flag and the bits of the bytes are binary
+        // f * (1 - f) = 0, a * (1 - a) = 0, b * (1 - b) = 0 || degree 2
+        constrains::enforce_bitwise_table(frame, &mut result[140..157]);
+
+        // the free register limbs are in [0, 4)
+        // l * (l - 1) * (l - 2) * (l - 3) = 0 || degree 4
+        constrains::enforce_stack_free(frame, &mut result[157..159]);
+
+        // the room limbs are in [0, 4) and make up the room left below the maximum depth
+        // l * (l - 1) * (l - 2) * (l - 3) = 0 || degree 4
+        // MAX_STACK_DEPTH - d - sum(l_i * 4^i) = 0 || degree 1
+        constrains::enforce_stack_room(frame, &mut result[159..168]);
+
+        // the overflow slots are binary, a shift past the 16 registers flags the slots of the moved registers
+        // o * (1 - o) = 0 || degree 2
+        // (1 - o) * (flag_shr * (d' - 16 + free') + flag_shl * (d - 16 + free)) = 0 || degree 9
+        let ct_ops = 168 + NUM_OVERFLOW_SLOTS + 1 + ct_width;
+        constrains::enforce_overflow_slots(frame, ct_width, &mut result[168..ct_ops]);
+
+        // the ciphertext ops constrain each element of the two top ciphertexts of the next row
+        // the op flags are exclusive, every op adds its terms to the constraint of the element
+        // s[0..w]' - (s0 + s[1..w+1]) = 0 (sadd), s[0..w]' - (s[0..w] + s[w..2w]) = 0 (add2) || degree 8
+        // s[0..w]' - (s0 * s[1..w+1]) = 0 (smul) || degree 9
+        // s[0..w]' - encrypt_trivial(imm) = 0 (push2), the op value is injected (enforced) into sponge state || degree 8
+        // s[0..w]' - s[0..w] = 0, s[w..2w]' - s[0..w] = 0 (dup2) || degree 8
+        // s[0..w]' - s[w..2w] = 0, s[w..2w]' - s[0..w] = 0 (swap2) || degree 8
+        // s[0..w]' - s[w..2w] = 0 (drop2, mem_store2) || degree 8
+        // s[0..w]' - (s0 * s[w+1..2w+1] + (1 - s0) * s[1..w+1]) = 0
+        // s[w..2w]' - (s0 * s[1..w+1] + (1 - s0) * s[w+1..2w+1]) = 0 (cswap2) || degree 9
+        // s[0..w]' - (s0 * s[1..w+1] + (1 - s0) * s[w+1..2w+1]) = 0 (csel2) || degree 9
+        constrains::enforce_ct_ops(frame, &self.scheme, ct_width, &mut result[ct_ops..]);
+    }
+
+    fn evaluate_aux_transition<F, E>(
+        &self,
+        main_frame: &EvaluationFrame<F>,
+        aux_frame: &EvaluationFrame<E>,
+        periodic_values: &[F],
+        aux_rand_elements: &[E],
+        result: &mut [E],
+    ) where
+        F: FieldElement<BaseField = Self::BaseField>,
+        E: FieldElement<BaseField = Self::BaseField> + ExtensionOf<F>,
+    {
+        let ct_width = self.scheme.ciphertext_width();
+
+        // memory loads, stores, overflow slots and fhe rows are a permutation of the memory table accesses
+        // q - p * request_0 * request_1, ... || degree 9
+        constrains::enforce_overflow_perm(main_frame, aux_frame, aux_rand_elements, &mut result[1..5]);
+
+        // the fhe passes are a permutation of the ciphertext ops of the stack
+        // q * response - p * write_c * read_a = 0 || degree 5, q - p * read_b = 0 || degree 4, q - p * request = 0 || degree 9
+        let fhe_periodic = [
+            periodic_values[FHE_PERIODIC_OFFSET],
+            periodic_values[FHE_PERIODIC_OFFSET + 1],
+        ];
+        constrains::enforce_fhe_perm(
+            main_frame,
+            aux_frame,
+            ct_width,
+            fhe_periodic,
+            aux_rand_elements,
+            &mut result[5..8],
+        );
+
+        // the u32 limbs are a permutation of the lookup rows of the range chiplet
+        // q * response - p * request_0 * ... * request_3 = 0, q - p * request_0 * ... * request_3 = 0 || degree 9
+        constrains::enforce_range_perm(main_frame, aux_frame, aux_rand_elements, &mut result[8..13]);
+
+        // the bytes of the bitwise operations are a permutation of the lookup rows of the bitwise chiplet
+        // q * response - p * request_0 * ... * request_3 = 0 || degree 9
+        result[13] = constrains::enforce_bitwise_perm(main_frame, aux_frame, aux_rand_elements);
+
+        // the stack states of hperm are a permutation of the flagged cycles of the hash chiplet
+        // q * response - p * request = 0 || degree 9
+        let hperm_output = periodic_values[HPERM_PERIODIC_OFFSET + 1];
+        result[14] = constrains::enforce_hperm_perm(main_frame, aux_frame, hperm_output, aux_rand_elements);
+
+        // p' * response - q * request = 0 || degree 9
+        result[0] = constrains::enforce_memory_perm(main_frame, aux_frame, stack_ct_width(ct_width), aux_rand_elements);
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Self::BaseField>> {
-        let mut assertions = Vec::with_capacity(18);
+        let mut assertions = Vec::with_capacity(48 + self.ciphertext_output.len());
         // initial clock value is 0
-        assertions.push(Assertion::single(0, 0, Self::BaseField::ZERO));
+        assertions.push(Assertion::single(CLK_COL, 0, Self::BaseField::ZERO));
 
         // initial stack depth is 0
-        assertions.push(Assertion::single(11, 0, Self::BaseField::ZERO));
+        assertions.push(Assertion::single(STACK_DEPTH_COL, 0, Self::BaseField::ZERO));
+
+        // no loop is open initially
+        for i in 0..LOOP_CTX_WIDTH {
+            assertions.push(Assertion::single(LOOP_CTX_OFFSET + i, 0, Self::BaseField::ZERO));
+        }
+
+        // the memory table starts with a zero read, a first access to an address reads 0
+        assertions.push(Assertion::single(MEM_WRITE_COL, 0, Self::BaseField::ZERO));
+        for i in 0..MEM_WORD_WIDTH {
+            assertions.push(Assertion::single(MEM_WORD_OFFSET + i, 0, Self::BaseField::ZERO));
+        }
 
         let last_step = self.last_step();
 
         // initial hash state is 0
         // final hash state equals to program hash
         for i in 0..2 {
-            assertions.push(Assertion::single(i + 7, 0, Self::BaseField::ZERO));
-            assertions.push(Assertion::single(i + 7, last_step, self.program_hash[i]));
+            assertions.push(Assertion::single(HASH_STATE_OFFSET + i, 0, Self::BaseField::ZERO));
+            assertions.push(Assertion::single(
+                HASH_STATE_OFFSET + i,
+                last_step,
+                self.program_hash[i],
+            ));
         }
 
         // initials stack state is 0
         // final stack state equals to output
         for i in 0..8 {
-            assertions.push(Assertion::single(i + 12, 0, Self::BaseField::ZERO));
-            assertions.push(Assertion::single(i + 12, last_step, self.stack_outputs[i]));
+            assertions.push(Assertion::single(STACK_OFFSET + i, 0, Self::BaseField::ZERO));
+            assertions.push(Assertion::single(STACK_OFFSET + i, last_step, self.stack_outputs[i]));
+        }
+
+        // the range chiplet starts at 0, longer traces than its values end it at 255
+        assertions.push(Assertion::single(RANGE_VALUE_COL, 0, Self::BaseField::ZERO));
+        if range_max_assertions(self.trace_length()) > 0 {
+            assertions.push(Assertion::single(
+                RANGE_VALUE_COL,
+                last_step,
+                Self::BaseField::from(RANGE_MAX),
+            ));
+        }
+
+        // the first fhe pass takes handle 0 and starts at element 0
+        assertions.push(Assertion::single(FHE_HANDLE_COL, 0, Self::BaseField::ZERO));
+        assertions.push(Assertion::single(FHE_INDEX_COL, 0, Self::BaseField::ZERO));
+
+        // the output ciphertext is the result of the pass of the output handle
+        // the pass keeps its flags, it is an executed pass when its first row is active
+        // the handle column counts the passes, its second row holds the output handle
+        // the first row is left to the handle 0 assertion of the first pass
+        if !self.ciphertext_output.is_empty() {
+            let start = self.output_pass * fhe_cycle_length(self.scheme.ciphertext_width());
+
+            assertions.push(Assertion::single(FHE_ACTIVE_COL, start, Self::BaseField::ONE));
+            assertions.push(Assertion::single(FHE_HANDLE_COL, start + 1, self.stack_outputs[0]));
+            for (i, &value) in self.ciphertext_output.iter().enumerate() {
+                assertions.push(Assertion::single(FHE_VALUE_OFFSET + 2, start + i, value));
+            }
         }
 
         assertions
     }
 
+    fn get_aux_assertions<E: FieldElement<BaseField = Self::BaseField>>(
+        &self,
+        _aux_rand_elements: &[E],
+    ) -> Vec<Assertion<E>> {
+        // the memory running product starts and ends at 1
+        vec![
+            Assertion::single(MEM_PERM_COL, 0, E::ONE),
+            Assertion::single(MEM_PERM_COL, self.last_step(), E::ONE),
+        ]
+    }
+
     fn context(&self) -> &AirContext<Self::BaseField> {
         &self.context
     }
@@ -201,10 +679,32 @@ impl Air for ProcessorAir {
     fn get_periodic_column_values(&self) -> Vec<Vec<Self::BaseField>> {
         let mut result = vec![CYCLE_MASK.to_vec()];
         result.append(&mut rescue::get_round_constants());
+
+        // the last row and the rows below the ciphertext width of a fhe pass
+        let ct_width = self.scheme.ciphertext_width();
+        let cycle = fhe_cycle_length(ct_width);
+        result.push((0..cycle).map(|i| to_flag(i == cycle - 1)).collect());
+        result.push((0..cycle).map(|i| to_flag(i < ct_width)).collect());
+
+        // the first row of a hash cycle and the row after its rounds
+        result.push((0..CYCLE_LENGTH).map(|i| to_flag(i == 0)).collect());
+        result.push((0..CYCLE_LENGTH).map(|i| to_flag(i == NUM_ROUNDS)).collect());
+
         result
     }
 }
 
+// the hash flag and the round constants come first
+const FHE_PERIODIC_OFFSET: usize = 1 + 2 * STATE_WIDTH;
+const HPERM_PERIODIC_OFFSET: usize = FHE_PERIODIC_OFFSET + 2;
+
+fn to_flag(value: bool) -> BaseElement {
+    match value {
+        true => BaseElement::ONE,
+        false => BaseElement::ZERO,
+    }
+}
+
 const CYCLE_MASK: [BaseElement; CYCLE_LENGTH] = [
     BaseElement::ONE,
     BaseElement::ONE,
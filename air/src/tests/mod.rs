@@ -3,17 +3,26 @@ use super::*;
 use fhe::LweParameters;
 use rescue::ARK;
 
-use crate::flags::opcode_to_element;
+use crate::flags::{self, opcode_to_element};
+use crate::layout::{
+    BITWISE_A_BITS_OFFSET, BITWISE_B_BITS_OFFSET, BITWISE_FLAG_COL, BITWISE_LOOKUP_COL, CLK_COL, FHE_ACTIVE_COL,
+    FHE_HANDLE_A_COL, FHE_HANDLE_COL, FHE_INDEX_COL, FHE_MAC_COL, FHE_SCALAR_COL, FHE_USE_B_COL, FHE_VALUE_OFFSET,
+    HASHER_OP_COL, HASH_STATE_OFFSET, HPERM_FLAG_COL, HPERM_INPUT_OFFSET, HPERM_STATE_OFFSET, IMM_COL, LOOP_CTX_OFFSET,
+    MAX_STACK_DEPTH, MEM_ACCESS_COL, MEM_ADDR_COL, MEM_CLK_COL, MEM_DELTA_OFFSET, MEM_ELEMENT_COL, MEM_NEW_ADDR_COL,
+    MEM_WORD_OFFSET, MEM_WRITE_COL, NUM_STACK_ROOM_LIMBS, OP_BITS_OFFSET, OVERFLOW_SLOTS_OFFSET, RANGE_LOOKUP_COL,
+    RANGE_VALUE_COL, STACK_DEPTH_COL, STACK_FREE_OFFSET, STACK_OFFSET, STACK_ROOM_OFFSET, U32_FLAG_COL, U32_INV_COL,
+    U32_LIMBS_OFFSET,
+};
 
 use crate::constrains;
 
 #[test]
 fn test_enforce_clock_increase() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[0] = BaseElement::from(3u8);
-    next[0] = BaseElement::from(4u8);
+    current[CLK_COL] = BaseElement::from(3u8);
+    next[CLK_COL] = BaseElement::from(4u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
@@ -21,57 +30,71 @@ fn test_enforce_clock_increase() {
 }
 
 #[test]
-fn test_enforce_stack_shift() {
-    for values in [[0u8, 0], [1, 0], [0, 1]].iter() {
-        let mut current = Vec::from([BaseElement::ZERO; 28]);
-        let next = Vec::from([BaseElement::ZERO; 28]);
+fn test_enforce_op_bits() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-        current[4] = BaseElement::from(values[0]);
-        current[5] = BaseElement::from(values[1]);
+    set_op(&mut current, flags::MOVDN + 15);
 
-        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-        assert_eq!(constrains::enforce_stack_shift(&frame), BaseElement::ZERO);
-    }
+    let mut result = [BaseElement::ONE; 7];
+
+    constrains::enforce_op_bits(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 7]);
 }
 
 #[test]
 fn test_enforce_stack_depth() {
-    for (&depth, opcode) in
-        [1i8, -1, 5, -5]
-            .iter()
-            .zip([[0u8, 0, 0, 0, 1], [0, 0, 0, 1, 0], [0, 1, 0, 0, 1], [1, 1, 0, 1, 0]])
+    for (&depth, code) in [1i8, -1, 5, -5, 5, -5, 0, -1, -2, -1, -6, 1, -1, 5, -5, -1, -2]
+        .iter()
+        .zip([
+            flags::READ,
+            flags::MUL,
+            flags::READ2,
+            flags::ADD2,
+            flags::DUP2,
+            flags::DROP2,
+            flags::MOVUP + 3,
+            flags::CSWAP,
+            flags::CSEL,
+            flags::CSWAP2,
+            flags::CSEL2,
+            flags::MEM_LOAD,
+            flags::MEM_STORE,
+            flags::MEM_LOAD2,
+            flags::MEM_STORE2,
+            flags::ASSERT,
+            flags::ASSERT_EQ,
+        ])
     {
-        let mut current = Vec::from([BaseElement::ZERO; 28]);
-        let mut next = Vec::from([BaseElement::ZERO; 28]);
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-        current[1] = BaseElement::from(opcode[0]);
-        current[2] = BaseElement::from(opcode[1]);
-        current[3] = BaseElement::from(opcode[2]);
-        current[4] = BaseElement::from(opcode[3]);
-        current[5] = BaseElement::from(opcode[4]);
+        set_op(&mut current, code);
 
-        current[11] = BaseElement::from(10u8);
+        current[STACK_DEPTH_COL] = BaseElement::from(10u8);
 
-        next[11] = BaseElement::from((10 + depth) as u8);
+        next[STACK_DEPTH_COL] = BaseElement::from((10 + depth) as u8);
 
         let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-        assert_eq!(constrains::enforce_stack_depth(&frame), BaseElement::ZERO);
+        assert_eq!(constrains::enforce_stack_depth(&frame, 5), BaseElement::ZERO);
     }
 }
 
 #[test]
 fn test_enforce_add() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[4] = BaseElement::ONE;
+    set_op(&mut current, flags::ADD);
 
-    current[12] = BaseElement::from(4u8);
-    current[13] = BaseElement::from(2u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = BaseElement::from(2u8);
 
-    next[12] = BaseElement::from(6u8);
+    next[STACK_OFFSET] = BaseElement::from(6u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
@@ -80,43 +103,48 @@ fn test_enforce_add() {
 
 #[test]
 fn test_enforce_sadd() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SADD);
 
     let server_key = server_key();
 
     let value = server_key.encrypt(4u8);
     let value_ct = value.ciphertext();
 
-    current[2] = BaseElement::ONE;
-    current[4] = BaseElement::ONE;
-
-    current[12] = BaseElement::from(4u8);
-    current[13] = value_ct[0];
-    current[14] = value_ct[1];
-    current[15] = value_ct[2];
-    current[16] = value_ct[3];
-    current[17] = value_ct[4];
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = value_ct[0];
+    current[STACK_OFFSET + 2] = value_ct[1];
+    current[STACK_OFFSET + 3] = value_ct[2];
+    current[STACK_OFFSET + 4] = value_ct[3];
+    current[STACK_OFFSET + 5] = value_ct[4];
 
     let result = server_key.scalar_add(&BaseElement::from(4u8), &value);
 
     let result_ct = result.ciphertext();
 
-    next[12] = result_ct[0];
-    next[13] = result_ct[1];
-    next[14] = result_ct[2];
-    next[15] = result_ct[3];
-    next[16] = result_ct[4];
+    next[STACK_OFFSET] = result_ct[0];
+    next[STACK_OFFSET + 1] = result_ct[1];
+    next[STACK_OFFSET + 2] = result_ct[2];
+    next[STACK_OFFSET + 3] = result_ct[3];
+    next[STACK_OFFSET + 4] = result_ct[4];
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(constrains::enforce_sadd(&frame, &server_key), BaseElement::ZERO)
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_sadd(&frame, &server_key, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
 }
 
 #[test]
 fn test_enforce_add2() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::ADD2);
 
     let server_key = server_key();
 
@@ -126,115 +154,212 @@ fn test_enforce_add2() {
     let value1 = server_key.encrypt(6u8);
     let value_ct1 = value1.ciphertext();
 
-    current[1] = BaseElement::ONE;
-    current[2] = BaseElement::ONE;
-    current[4] = BaseElement::ONE;
+    current[STACK_OFFSET] = value_ct0[0];
+    current[STACK_OFFSET + 1] = value_ct0[1];
+    current[STACK_OFFSET + 2] = value_ct0[2];
+    current[STACK_OFFSET + 3] = value_ct0[3];
+    current[STACK_OFFSET + 4] = value_ct0[4];
 
-    current[12] = value_ct0[0];
-    current[13] = value_ct0[1];
-    current[14] = value_ct0[2];
-    current[15] = value_ct0[3];
-    current[16] = value_ct0[4];
-
-    current[17] = value_ct1[0];
-    current[18] = value_ct1[1];
-    current[19] = value_ct1[2];
-    current[20] = value_ct1[3];
-    current[21] = value_ct1[4];
+    current[STACK_OFFSET + 5] = value_ct1[0];
+    current[STACK_OFFSET + 6] = value_ct1[1];
+    current[STACK_OFFSET + 7] = value_ct1[2];
+    current[STACK_OFFSET + 8] = value_ct1[3];
+    current[STACK_OFFSET + 9] = value_ct1[4];
 
     let result = server_key.add(&value0, &value1);
 
     let result_ct = result.ciphertext();
 
-    next[12] = result_ct[0];
-    next[13] = result_ct[1];
-    next[14] = result_ct[2];
-    next[15] = result_ct[3];
-    next[16] = result_ct[4];
+    next[STACK_OFFSET] = result_ct[0];
+    next[STACK_OFFSET + 1] = result_ct[1];
+    next[STACK_OFFSET + 2] = result_ct[2];
+    next[STACK_OFFSET + 3] = result_ct[3];
+    next[STACK_OFFSET + 4] = result_ct[4];
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(constrains::enforce_add2(&frame, &server_key), BaseElement::ZERO)
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_add2(&frame, &server_key, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
 }
 
 #[test]
 fn test_enforce_mul() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[1] = BaseElement::ONE;
-    current[4] = BaseElement::ONE;
+    set_op(&mut current, flags::MUL);
 
-    current[12] = BaseElement::from(4u8);
-    current[13] = BaseElement::from(2u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = BaseElement::from(2u8);
 
-    next[12] = BaseElement::from(8u8);
+    next[STACK_OFFSET] = BaseElement::from(8u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
     assert_eq!(constrains::enforce_mul(&frame), BaseElement::ZERO)
 }
 
+#[test]
+fn test_enforce_sub() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SUB);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = BaseElement::from(2u8);
+
+    next[STACK_OFFSET] = -BaseElement::from(2u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_sub(&frame), BaseElement::ZERO)
+}
+
+#[test]
+fn test_enforce_neg() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::NEG);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET] = -BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_neg(&frame), BaseElement::ZERO)
+}
+
+#[test]
+fn test_enforce_inv() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::INV);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET] = BaseElement::from(4u8).inv();
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_inv(&frame), BaseElement::ZERO);
+
+    // a wrong witness is rejected
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+    next[STACK_OFFSET] = BaseElement::from(4u8);
+
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    set_op(&mut current, flags::INV);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_ne!(constrains::enforce_inv(&frame), BaseElement::ZERO)
+}
+
 #[test]
 fn test_enforce_smul() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SMUL);
 
     let server_key = server_key();
 
     let value = server_key.encrypt(4u8);
     let value_ct = value.ciphertext();
 
-    current[3] = BaseElement::ONE;
-    current[4] = BaseElement::ONE;
-
-    current[12] = BaseElement::from(4u8);
-    current[13] = value_ct[0];
-    current[14] = value_ct[1];
-    current[15] = value_ct[2];
-    current[16] = value_ct[3];
-    current[17] = value_ct[4];
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = value_ct[0];
+    current[STACK_OFFSET + 2] = value_ct[1];
+    current[STACK_OFFSET + 3] = value_ct[2];
+    current[STACK_OFFSET + 4] = value_ct[3];
+    current[STACK_OFFSET + 5] = value_ct[4];
 
     let result = server_key.scalar_mul(&BaseElement::from(4u8), &value);
 
     let result_ct = result.ciphertext();
 
-    next[12] = result_ct[0];
-    next[13] = result_ct[1];
-    next[14] = result_ct[2];
-    next[15] = result_ct[3];
-    next[16] = result_ct[4];
+    next[STACK_OFFSET] = result_ct[0];
+    next[STACK_OFFSET + 1] = result_ct[1];
+    next[STACK_OFFSET + 2] = result_ct[2];
+    next[STACK_OFFSET + 3] = result_ct[3];
+    next[STACK_OFFSET + 4] = result_ct[4];
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(constrains::enforce_smul(&frame, &server_key), BaseElement::ZERO)
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_smul(&frame, &server_key, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
 }
 
 #[test]
 fn test_enforce_push() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::PUSH);
 
-    current[5] = BaseElement::ONE;
+    current[IMM_COL] = BaseElement::from(7u8);
 
-    current[12] = BaseElement::from(4u8);
-    next[13] = BaseElement::from(4u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET] = BaseElement::from(7u8);
+    next[STACK_OFFSET + 1] = BaseElement::from(4u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(constrains::enforce_push(&frame), BaseElement::ZERO)
+    let mut result = [BaseElement::ONE; 2];
+
+    constrains::enforce_push(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 2])
+}
+
+#[test]
+fn test_enforce_push2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::PUSH2);
+
+    let server_key = server_key();
+
+    current[IMM_COL] = BaseElement::from(3u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+
+    let value_ct = server_key
+        .encrypt_trivial(&BaseElement::from(3u8))
+        .ciphertext()
+        .to_vec();
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct);
+    next[STACK_OFFSET + 5] = BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_push2(&frame, &server_key, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10]);
+    assert_eq!(constrains::enforce_read2(&frame, 5), BaseElement::ZERO)
 }
 
 #[test]
 fn test_enforce_read() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[1] = BaseElement::ONE;
-    current[5] = BaseElement::ONE;
+    set_op(&mut current, flags::READ);
 
-    current[12] = BaseElement::from(4u8);
-    next[13] = BaseElement::from(4u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET + 1] = BaseElement::from(4u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
@@ -243,56 +368,93 @@ fn test_enforce_read() {
 
 #[test]
 fn test_enforce_read2() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[2] = BaseElement::ONE;
-    current[5] = BaseElement::ONE;
+    set_op(&mut current, flags::READ2);
 
-    current[12] = BaseElement::from(4u8);
-    next[17] = BaseElement::from(4u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET + 5] = BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next);
+
+    assert_eq!(constrains::enforce_read2(&frame, 5), BaseElement::ZERO);
+
+    // the previous top moves below a ciphertext of 2 elements for k = 1
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+    next[STACK_OFFSET + 2] = BaseElement::from(4u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(constrains::enforce_read2(&frame), BaseElement::ZERO)
+    assert_eq!(constrains::enforce_read2(&frame, 2), BaseElement::ZERO);
+    assert_ne!(constrains::enforce_read2(&frame, 5), BaseElement::ZERO)
 }
 
 #[test]
 fn test_enforce_noop() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[12] = BaseElement::from(4u8);
-    next[12] = BaseElement::from(4u8);
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    next[STACK_OFFSET] = BaseElement::from(4u8);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
     assert_eq!(constrains::enforce_noop(&frame), BaseElement::ZERO)
 }
 
+#[test]
+fn test_enforce_assert() {
+    let cases = [
+        (flags::ASSERT, [BaseElement::ONE, BaseElement::ZERO]),
+        (flags::ASSERTZ, [BaseElement::ZERO, BaseElement::ONE]),
+        (flags::ASSERT_EQ, [BaseElement::new(5), BaseElement::new(5)]),
+    ];
+
+    for (i, (code, [s0, s1])) in cases.into_iter().enumerate() {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[STACK_OFFSET] = s0;
+        current[STACK_OFFSET + 1] = s1;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 3];
+
+        constrains::enforce_assert(&frame, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 3]);
+
+        // a different top element fails the assertion
+        current[STACK_OFFSET] = s0 + BaseElement::ONE;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_assert(&frame, &mut result);
+
+        assert_ne!(result[i], BaseElement::ZERO);
+    }
+}
+
 #[test]
 fn test_enforce_hash_round() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
     let mut state = [BaseElement::ZERO; 4];
 
-    current[5] = BaseElement::ONE;
-
-    current[6] = BaseElement::ONE;
-    current[7] = state[0];
-    current[8] = state[1];
-    current[9] = state[2];
-    current[10] = state[3];
+    set_op(&mut current, flags::PUSH);
+    current[IMM_COL] = BaseElement::from(2u8);
 
-    rescue::apply_round(&mut state, 16, 2, 0);
+    current[HASHER_OP_COL] = BaseElement::ONE;
+    current[HASH_STATE_OFFSET..(HASH_STATE_OFFSET + 4)].copy_from_slice(&state);
 
-    next[7] = state[0];
-    next[8] = state[1];
-    next[9] = state[2];
-    next[10] = state[3];
+    rescue::apply_round(&mut state, flags::PUSH, BaseElement::from(2u8), 0);
 
-    next[12] = BaseElement::from(2u8);
+    next[HASH_STATE_OFFSET..(HASH_STATE_OFFSET + 4)].copy_from_slice(&state);
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
@@ -305,19 +467,19 @@ fn test_enforce_hash_round() {
 
 #[test]
 fn test_enforce_hash_copy() {
-    let mut current = Vec::from([BaseElement::ZERO; 28]);
-    let mut next = Vec::from([BaseElement::ZERO; 28]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
 
-    current[6] = BaseElement::ONE;
-    current[7] = BaseElement::from(2u8);
-    current[8] = BaseElement::from(4u8);
-    current[9] = BaseElement::from(6u8);
-    current[10] = BaseElement::from(8u8);
+    current[HASHER_OP_COL] = BaseElement::ONE;
+    current[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+    current[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+    current[HASH_STATE_OFFSET + 2] = BaseElement::from(6u8);
+    current[HASH_STATE_OFFSET + 3] = BaseElement::from(8u8);
 
-    next[7] = BaseElement::from(2u8);
-    next[8] = BaseElement::from(4u8);
-    next[9] = BaseElement::ZERO;
-    next[10] = BaseElement::ZERO;
+    next[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+    next[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+    next[HASH_STATE_OFFSET + 2] = BaseElement::ZERO;
+    next[HASH_STATE_OFFSET + 3] = BaseElement::ZERO;
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
@@ -330,16 +492,1376 @@ fn test_enforce_hash_copy() {
 
 #[test]
 fn test_opcode_to_element() {
-    let mut current = Vec::from([BaseElement::ZERO; 6]);
-    let next = Vec::from([BaseElement::ZERO; 6]);
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DUP + 11);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(opcode_to_element(&frame), BaseElement::from(flags::DUP + 11))
+}
+
+#[test]
+fn test_enforce_dup() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DUP + 3);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 3] = BaseElement::from(9u8);
+
+    next[STACK_OFFSET] = BaseElement::from(9u8);
+    next[STACK_OFFSET + 1] = BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ONE; 2];
+
+    constrains::enforce_dup(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 2])
+}
+
+#[test]
+fn test_enforce_swap() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SWAP);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = BaseElement::from(9u8);
+
+    next[STACK_OFFSET] = BaseElement::from(9u8);
+    next[STACK_OFFSET + 1] = BaseElement::from(4u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ONE; 2];
+
+    constrains::enforce_swap(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 2])
+}
+
+#[test]
+fn test_enforce_drop() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DROP);
+
+    current[STACK_OFFSET] = BaseElement::from(4u8);
+    current[STACK_OFFSET + 1] = BaseElement::from(9u8);
+
+    next[STACK_OFFSET] = BaseElement::from(9u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_drop(&frame), BaseElement::ZERO)
+}
+
+#[test]
+fn test_enforce_movup() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::MOVUP + 15);
+
+    current[STACK_OFFSET + 15] = BaseElement::from(9u8);
+    next[STACK_OFFSET] = BaseElement::from(9u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_movup(&frame), BaseElement::ZERO)
+}
 
-    current[1] = BaseElement::ONE;
-    current[2] = BaseElement::ONE;
-    current[4] = BaseElement::ONE;
+#[test]
+fn test_enforce_movdn() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::MOVDN + 2);
+
+    current[STACK_OFFSET] = BaseElement::from(9u8);
+    next[STACK_OFFSET + 2] = BaseElement::from(9u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    assert_eq!(constrains::enforce_movdn(&frame), BaseElement::ZERO)
+}
+
+#[test]
+fn test_enforce_dup2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DUP2);
+
+    let value_ct = server_key().encrypt(4u8).ciphertext().to_vec();
+
+    current[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct);
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct);
+    next[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_dup2(&frame, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
+}
+
+#[test]
+fn test_enforce_swap2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SWAP2);
+
+    let server_key = server_key();
+    let value_ct0 = server_key.encrypt(4u8).ciphertext().to_vec();
+    let value_ct1 = server_key.encrypt(6u8).ciphertext().to_vec();
+
+    current[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct0);
+    current[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct1);
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct1);
+    next[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct0);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_swap2(&frame, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
+}
+
+#[test]
+fn test_enforce_drop2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DROP2);
+
+    let server_key = server_key();
+    let value_ct0 = server_key.encrypt(4u8).ciphertext().to_vec();
+    let value_ct1 = server_key.encrypt(6u8).ciphertext().to_vec();
+
+    current[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct0);
+    current[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct1);
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct1);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_drop2(&frame, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
+}
+
+#[test]
+fn test_enforce_condition() {
+    for (c, expected) in [
+        (0u8, BaseElement::ZERO),
+        (1, BaseElement::ZERO),
+        (2, -BaseElement::from(2u8)),
+    ] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, flags::CSEL);
+
+        current[STACK_OFFSET] = BaseElement::from(c);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        assert_eq!(constrains::enforce_condition(&frame), expected);
+    }
+}
+
+#[test]
+fn test_enforce_cswap() {
+    for c in [0u8, 1] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, flags::CSWAP);
+
+        current[STACK_OFFSET] = BaseElement::from(c);
+        current[STACK_OFFSET + 1] = BaseElement::from(3u8);
+        current[STACK_OFFSET + 2] = BaseElement::from(5u8);
+
+        next[STACK_OFFSET] = BaseElement::from(if c == 1 { 5u8 } else { 3 });
+        next[STACK_OFFSET + 1] = BaseElement::from(if c == 1 { 3u8 } else { 5 });
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 2];
+
+        constrains::enforce_cswap(&frame, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 2])
+    }
+}
+
+#[test]
+fn test_enforce_csel() {
+    for c in [0u8, 1] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, flags::CSEL);
+
+        current[STACK_OFFSET] = BaseElement::from(c);
+        current[STACK_OFFSET + 1] = BaseElement::from(3u8);
+        current[STACK_OFFSET + 2] = BaseElement::from(5u8);
+
+        next[STACK_OFFSET] = BaseElement::from(if c == 1 { 3u8 } else { 5 });
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        assert_eq!(constrains::enforce_csel(&frame), BaseElement::ZERO)
+    }
+}
+
+#[test]
+fn test_enforce_cswap2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::CSWAP2);
+
+    let server_key = server_key();
+    let value_ct0 = server_key.encrypt(4u8).ciphertext().to_vec();
+    let value_ct1 = server_key.encrypt(6u8).ciphertext().to_vec();
+
+    current[STACK_OFFSET] = BaseElement::ONE;
+    current[(STACK_OFFSET + 1)..(STACK_OFFSET + 6)].copy_from_slice(&value_ct0);
+    current[(STACK_OFFSET + 6)..(STACK_OFFSET + 11)].copy_from_slice(&value_ct1);
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct1);
+    next[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct0);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_cswap2(&frame, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
+}
+
+#[test]
+fn test_enforce_csel2() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::CSEL2);
+
+    let server_key = server_key();
+    let value_ct0 = server_key.encrypt(4u8).ciphertext().to_vec();
+    let value_ct1 = server_key.encrypt(6u8).ciphertext().to_vec();
+
+    current[(STACK_OFFSET + 1)..(STACK_OFFSET + 6)].copy_from_slice(&value_ct0);
+    current[(STACK_OFFSET + 6)..(STACK_OFFSET + 11)].copy_from_slice(&value_ct1);
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct1);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_csel2(&frame, 5, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 10])
+}
+
+#[test]
+fn test_enforce_ct_ops_elements() {
+    let server_key = server_key();
+    let value_ct0 = server_key.encrypt(4u8).ciphertext().to_vec();
+    let value_ct1 = server_key.encrypt(6u8).ciphertext().to_vec();
+
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::SWAP2);
+
+    current[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct0);
+    current[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct1);
+
+    next[STACK_OFFSET..(STACK_OFFSET + 5)].copy_from_slice(&value_ct1);
+    next[(STACK_OFFSET + 5)..(STACK_OFFSET + 10)].copy_from_slice(&value_ct0);
+
+    // move one from the first element to the second, the sum of the ciphertext is kept
+    next[STACK_OFFSET] += BaseElement::ONE;
+    next[STACK_OFFSET + 1] -= BaseElement::ONE;
 
     let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
 
-    assert_eq!(opcode_to_element(&frame), BaseElement::from(11u8))
+    let mut result = [BaseElement::ZERO; 10];
+
+    constrains::enforce_ct_ops(&frame, &server_key, 5, &mut result);
+
+    assert_eq!(result[0], BaseElement::ONE);
+    assert_eq!(result[1], -BaseElement::ONE);
+    assert_eq!(result[2..], [BaseElement::ZERO; 8]);
+}
+
+#[test]
+fn test_enforce_branch() {
+    for (code, condition) in [(flags::TRUE, BaseElement::ONE), (flags::FALSE, BaseElement::ZERO)] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[STACK_OFFSET] = condition;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 2];
+
+        constrains::enforce_branch(&frame, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 2]);
+
+        // the other condition is rejected
+        current[STACK_OFFSET] = BaseElement::ONE - condition;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_branch(&frame, &mut result);
+
+        assert_ne!(result, [BaseElement::ZERO; 2]);
+    }
+}
+
+#[test]
+fn test_enforce_hash_merge() {
+    // tend keeps the executed digest first, fend moves it to the capacity
+    for (code, merged) in [(flags::TEND, [2u8, 4, 8, 9]), (flags::FEND, [4, 9, 8, 2])] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[HASHER_OP_COL] = BaseElement::ONE;
+        current[IMM_COL] = BaseElement::from(9u8);
+        current[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+        current[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+        current[HASH_STATE_OFFSET + 2] = BaseElement::from(6u8);
+        current[HASH_STATE_OFFSET + 3] = BaseElement::from(8u8);
+
+        for (i, &value) in merged.iter().enumerate() {
+            next[HASH_STATE_OFFSET + i] = BaseElement::from(value);
+        }
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 4];
+
+        constrains::enforce_hash_copy(&frame, BaseElement::ZERO, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 4])
+    }
+}
+
+#[test]
+fn test_enforce_copy_ops() {
+    for (code, valid) in [
+        (flags::NOOP, true),
+        (flags::TEND, true),
+        (flags::FEND, true),
+        (flags::LOOP, true),
+        (flags::CALL, true),
+        (flags::REPEAT, true),
+        (flags::WEND, true),
+        (flags::ADD, false),
+        (flags::TRUE, false),
+    ] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[HASHER_OP_COL] = BaseElement::ONE;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        // operations on round steps are absorbed
+        assert_eq!(
+            constrains::enforce_copy_ops(&frame, BaseElement::ONE),
+            BaseElement::ZERO
+        );
+        assert_eq!(
+            constrains::enforce_copy_ops(&frame, BaseElement::ZERO) == BaseElement::ZERO,
+            valid
+        );
+    }
+}
+
+#[test]
+fn test_enforce_loop_hash() {
+    // call clears the state, repeat restores the loop entry rate, wend merges the body digest into the capacity
+    for (code, merged) in [
+        (flags::CALL, [0u8, 0, 0, 0]),
+        (flags::REPEAT, [1, 3, 0, 0]),
+        (flags::WEND, [2, 4, 5, 7]),
+    ] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[HASHER_OP_COL] = BaseElement::ONE;
+        current[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+        current[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+        current[HASH_STATE_OFFSET + 2] = BaseElement::from(6u8);
+        current[HASH_STATE_OFFSET + 3] = BaseElement::from(8u8);
+
+        for (i, value) in [1u8, 3, 5, 7].into_iter().enumerate() {
+            current[LOOP_CTX_OFFSET + i] = BaseElement::from(value);
+        }
+
+        for (i, &value) in merged.iter().enumerate() {
+            next[HASH_STATE_OFFSET + i] = BaseElement::from(value);
+        }
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 4];
+
+        constrains::enforce_hash_copy(&frame, BaseElement::ZERO, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 4])
+    }
+}
+
+#[test]
+fn test_enforce_loop_body() {
+    for (digest, valid) in [([2u8, 4], true), ([2, 5], false)] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, flags::REPEAT);
+
+        current[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+        current[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+        current[LOOP_CTX_OFFSET + 2] = BaseElement::from(digest[0]);
+        current[LOOP_CTX_OFFSET + 3] = BaseElement::from(digest[1]);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 2];
+
+        constrains::enforce_loop_body(&frame, BaseElement::ZERO, &mut result);
+
+        assert_eq!(result == [BaseElement::ZERO; 2], valid);
+    }
+}
+
+#[test]
+fn test_enforce_loop_ctx() {
+    // loop shifts the entry rate and the digest element in, wend clears the ctx, noop keeps it
+    for (code, ctx_next) in [
+        (flags::LOOP, [2u8, 4, 7, 9]),
+        (flags::CALL, [2, 4, 7, 9]),
+        (flags::WEND, [0, 0, 0, 0]),
+        (flags::NOOP, [1, 3, 5, 7]),
+    ] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+
+        current[IMM_COL] = BaseElement::from(9u8);
+        current[HASH_STATE_OFFSET] = BaseElement::from(2u8);
+        current[HASH_STATE_OFFSET + 1] = BaseElement::from(4u8);
+
+        for (i, value) in [1u8, 3, 5, 7].into_iter().enumerate() {
+            current[LOOP_CTX_OFFSET + i] = BaseElement::from(value);
+            next[LOOP_CTX_OFFSET + i] = BaseElement::from(ctx_next[i]);
+        }
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 4];
+
+        constrains::enforce_loop_ctx(&frame, BaseElement::ZERO, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 4])
+    }
+}
+
+#[test]
+fn test_enforce_memory_flags() {
+    let current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    for col in [MEM_WRITE_COL, MEM_ELEMENT_COL, MEM_ACCESS_COL, MEM_NEW_ADDR_COL] {
+        next[col] = BaseElement::ONE;
+    }
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let mut result = [BaseElement::ONE; 5];
+
+    constrains::enforce_memory_flags(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 5])
+}
+
+#[test]
+fn test_enforce_memory_order() {
+    // same address with a clock delta of 6, a new address with an address delta of 10
+    for (new_addr, addr_next, clk_next, delta) in [(0u8, 3u8, 11u8, [1u8, 1]), (1, 13, 2, [1, 2])] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        current[MEM_ADDR_COL] = BaseElement::from(3u8);
+        current[MEM_CLK_COL] = BaseElement::from(5u8);
+        current[MEM_DELTA_OFFSET] = BaseElement::from(delta[0]);
+        current[MEM_DELTA_OFFSET + 1] = BaseElement::from(delta[1]);
+
+        next[MEM_ADDR_COL] = BaseElement::from(addr_next);
+        next[MEM_CLK_COL] = BaseElement::from(clk_next);
+        next[MEM_NEW_ADDR_COL] = BaseElement::from(new_addr);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 2];
+        constrains::enforce_memory_order(&frame, &mut result);
+        assert_eq!(result, [BaseElement::ZERO; 2]);
+
+        let mut result = [BaseElement::ONE; 8];
+        constrains::enforce_memory_delta(&frame, &mut result);
+        assert_eq!(result, [BaseElement::ZERO; 8]);
+    }
+}
+
+#[test]
+fn test_enforce_memory_word() {
+    // read keeps the word, scalar write clears the rest, new address read starts from 0
+    for (write, element, new_addr, word_next) in [
+        (0u8, 1u8, 0u8, [4u8, 6, 8]),
+        (1, 1, 0, [9, 0, 0]),
+        (1, 0, 0, [9, 1, 2]),
+        (0, 0, 1, [0, 0, 0]),
+    ] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        for (i, value) in [4u8, 6, 8].into_iter().enumerate() {
+            current[MEM_WORD_OFFSET + i] = BaseElement::from(value);
+            next[MEM_WORD_OFFSET + i] = BaseElement::from(word_next[i]);
+        }
+
+        next[MEM_WRITE_COL] = BaseElement::from(write);
+        next[MEM_ELEMENT_COL] = BaseElement::from(element);
+        next[MEM_ACCESS_COL] = BaseElement::ONE;
+        next[MEM_NEW_ADDR_COL] = BaseElement::from(new_addr);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        let mut result = [BaseElement::ONE; 16];
+
+        constrains::enforce_memory_word(&frame, &mut result);
+
+        assert_eq!(result, [BaseElement::ZERO; 16])
+    }
+}
+
+#[test]
+fn test_memory_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // a ciphertext load at clock 7 from address 2 matches the table row of the access
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::MEM_LOAD2);
+
+    current[IMM_COL] = BaseElement::from(2u8);
+    next[CLK_COL] = BaseElement::from(7u8);
+
+    next[MEM_ADDR_COL] = BaseElement::from(2u8);
+    next[MEM_CLK_COL] = BaseElement::from(7u8);
+    next[MEM_ACCESS_COL] = BaseElement::ONE;
+
+    for i in 0..5 {
+        next[STACK_OFFSET + i] = BaseElement::from(i as u8 + 1);
+        next[MEM_WORD_OFFSET + i] = BaseElement::from(i as u8 + 1);
+    }
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::memory_request(&frame, 5, &rand);
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::memory_response(&frame, &rand));
+
+    // any other operation and a padding row leave the running product unchanged
+    let frame = EvaluationFrame::<BaseElement>::new(TRACE_WIDTH);
+
+    assert_eq!(constrains::memory_request(&frame, 5, &rand), BaseElement::ONE);
+    assert_eq!(constrains::memory_response(&frame, &rand), BaseElement::ONE);
+}
+
+#[test]
+fn test_enforce_overflow_slots() {
+    // read2 from 14 elements moves registers 11 to 15 through the overflow table, from 10 elements it does not
+    for (depth, free, slots) in [(14u8, [2u8, 0u8], 5), (10, [2, 1], 0)] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, flags::READ2);
+
+        current[STACK_DEPTH_COL] = BaseElement::from(depth);
+        current[STACK_FREE_OFFSET] = BaseElement::from(free[0]);
+        current[STACK_FREE_OFFSET + 1] = BaseElement::from(free[1]);
+
+        next[STACK_DEPTH_COL] = BaseElement::from(depth + 5);
+        if depth + 5 < 16 {
+            next[STACK_FREE_OFFSET] = BaseElement::from(1u8);
+        }
+
+        for m in 0..slots {
+            current[OVERFLOW_SLOTS_OFFSET + m] = BaseElement::ONE;
+        }
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 14];
+        constrains::enforce_overflow_slots(&frame, 5, &mut result);
+        assert_eq!(result, [BaseElement::ZERO; 14]);
+
+        let mut result = [BaseElement::ONE; 2];
+        constrains::enforce_stack_free(&frame, &mut result);
+        assert_eq!(result, [BaseElement::ZERO; 2]);
+
+        // an overflowing shift without its slots flagged fails
+        if slots > 0 {
+            current[OVERFLOW_SLOTS_OFFSET + 4] = BaseElement::ZERO;
+
+            let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+            let mut result = [BaseElement::ZERO; 14];
+            constrains::enforce_overflow_slots(&frame, 5, &mut result);
+            assert_ne!(result[12], BaseElement::ZERO);
+        }
+    }
+}
+
+#[test]
+fn test_enforce_stack_room() {
+    // the room limbs make up the maximum depth minus the depth, base 4 from the lowest limb
+    for depth in [0, 16, 17, MAX_STACK_DEPTH - 1, MAX_STACK_DEPTH] {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        current[STACK_DEPTH_COL] = BaseElement::from(depth);
+        for i in 0..NUM_STACK_ROOM_LIMBS {
+            current[STACK_ROOM_OFFSET + i] = BaseElement::from((MAX_STACK_DEPTH - depth) >> (2 * i) & 0b11);
+        }
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, vec![BaseElement::ZERO; TRACE_WIDTH]);
+
+        let mut result = [BaseElement::ONE; NUM_STACK_ROOM_LIMBS + 1];
+        constrains::enforce_stack_room(&frame, &mut result);
+        assert_eq!(result, [BaseElement::ZERO; NUM_STACK_ROOM_LIMBS + 1]);
+    }
+
+    // a depth past the maximum has no room in 16 bits, the limbs wrap around the field
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    current[STACK_DEPTH_COL] = BaseElement::from(MAX_STACK_DEPTH + 1);
+    for i in 0..NUM_STACK_ROOM_LIMBS {
+        current[STACK_ROOM_OFFSET + i] = BaseElement::from(3u8);
+    }
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, vec![BaseElement::ZERO; TRACE_WIDTH]);
+
+    let mut result = [BaseElement::ZERO; NUM_STACK_ROOM_LIMBS + 1];
+    constrains::enforce_stack_room(&frame, &mut result);
+    assert_eq!(
+        result[..NUM_STACK_ROOM_LIMBS],
+        [BaseElement::ZERO; NUM_STACK_ROOM_LIMBS]
+    );
+    assert_ne!(result[NUM_STACK_ROOM_LIMBS], BaseElement::ZERO);
+}
+
+#[test]
+fn test_overflow_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // drop from 18 elements reads register 15 at the overflow address of depth 17
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::DROP);
+
+    current[STACK_DEPTH_COL] = BaseElement::from(18u8);
+    current[OVERFLOW_SLOTS_OFFSET] = BaseElement::ONE;
+
+    next[CLK_COL] = BaseElement::from(7u8);
+    next[STACK_DEPTH_COL] = BaseElement::from(17u8);
+    next[STACK_OFFSET + 15] = BaseElement::from(9u8);
+
+    next[MEM_ADDR_COL] = BaseElement::from((1u32 << 16) + 17);
+    next[MEM_CLK_COL] = BaseElement::from(7u8);
+    next[MEM_ELEMENT_COL] = BaseElement::ONE;
+    next[MEM_ACCESS_COL] = BaseElement::ONE;
+    next[MEM_WORD_OFFSET] = BaseElement::from(9u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::overflow_request(&frame, 0, &rand);
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::memory_response(&frame, &rand));
+
+    // the other slots are not flagged
+    assert_eq!(constrains::overflow_request(&frame, 1, &rand), BaseElement::ONE);
+}
+
+#[test]
+fn test_enforce_fhe_pass() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    // row 2 of the smul pass of handle 1 computes 3 * 4
+    for row in [&mut current, &mut next] {
+        row[FHE_ACTIVE_COL] = BaseElement::ONE;
+        row[FHE_MAC_COL] = BaseElement::ONE;
+        row[FHE_HANDLE_COL] = BaseElement::ONE;
+        row[FHE_SCALAR_COL] = BaseElement::from(3u8);
+    }
+
+    current[FHE_INDEX_COL] = BaseElement::from(2u8);
+    next[FHE_INDEX_COL] = BaseElement::from(3u8);
+
+    current[FHE_VALUE_OFFSET] = BaseElement::from(4u8);
+    current[FHE_VALUE_OFFSET + 2] = BaseElement::from(12u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+    let mut result = [BaseElement::ONE; 4];
+    constrains::enforce_fhe_flags(&frame, &mut result);
+    assert_eq!(result, [BaseElement::ZERO; 4]);
+
+    // a mac pass is active
+    let mut inactive = current.clone();
+    inactive[FHE_ACTIVE_COL] = BaseElement::ZERO;
+
+    let mut result = [BaseElement::ZERO; 4];
+    constrains::enforce_fhe_flags(&EvaluationFrame::from_rows(inactive, next.clone()), &mut result);
+    assert_ne!(result[3], BaseElement::ZERO);
+
+    let mut result = [BaseElement::ONE; 8];
+    constrains::enforce_fhe_pass(&frame, BaseElement::ZERO, &mut result);
+    assert_eq!(result, [BaseElement::ZERO; 8]);
+
+    assert_eq!(constrains::enforce_fhe_mac(&frame), BaseElement::ZERO);
+
+    // the last row of the pass hands over to the next handle with the index reset
+    next[FHE_ACTIVE_COL] = BaseElement::ZERO;
+    next[FHE_MAC_COL] = BaseElement::ZERO;
+    next[FHE_HANDLE_COL] = BaseElement::from(2u8);
+    next[FHE_INDEX_COL] = BaseElement::ZERO;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next);
+
+    let mut result = [BaseElement::ONE; 8];
+    constrains::enforce_fhe_pass(&frame, BaseElement::ONE, &mut result);
+    assert_eq!(result, [BaseElement::ZERO; 8]);
+
+    // a wrong product fails
+    current[FHE_VALUE_OFFSET + 2] = BaseElement::from(13u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, vec![BaseElement::ZERO; TRACE_WIDTH]);
+
+    assert_ne!(constrains::enforce_fhe_mac(&frame), BaseElement::ZERO);
+}
+
+#[test]
+fn test_fhe_memory_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // row 1 of the smul pass of handle 2 reads element 1 of handle 0
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    current[FHE_ACTIVE_COL] = BaseElement::ONE;
+    current[FHE_MAC_COL] = BaseElement::ONE;
+    current[FHE_HANDLE_COL] = BaseElement::from(2u8);
+    current[FHE_INDEX_COL] = BaseElement::ONE;
+    current[FHE_VALUE_OFFSET] = BaseElement::from(9u8);
+
+    next[MEM_ADDR_COL] = BaseElement::from((1u32 << 17) + 1);
+    next[MEM_CLK_COL] = BaseElement::from(6u8);
+    next[MEM_ELEMENT_COL] = BaseElement::ONE;
+    next[MEM_ACCESS_COL] = BaseElement::ONE;
+    next[MEM_WORD_OFFSET] = BaseElement::from(9u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let [_, read_a, read_b] = constrains::fhe_memory_requests(&frame, 5, BaseElement::ONE, &rand);
+
+    assert_ne!(read_a, BaseElement::ONE);
+    assert_eq!(read_a, constrains::memory_response(&frame, &rand));
+    assert_eq!(read_b, BaseElement::ONE);
+
+    // rows past the ciphertext width are idle
+    let requests = constrains::fhe_memory_requests(&frame, 5, BaseElement::ZERO, &rand);
+    assert_eq!(requests, [BaseElement::ONE; 3]);
+}
+
+#[test]
+fn test_fhe_pass_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // ct_smul [3, h0] pushes h1
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::CT_SMUL);
+
+    current[STACK_OFFSET] = BaseElement::from(3u8);
+    next[STACK_OFFSET] = BaseElement::ONE;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::fhe_pass_request(&frame, &rand);
+
+    // the last row of the pass of handle 1
+    let mut row = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    row[FHE_ACTIVE_COL] = BaseElement::ONE;
+    row[FHE_MAC_COL] = BaseElement::ONE;
+    row[FHE_HANDLE_COL] = BaseElement::ONE;
+    row[FHE_HANDLE_A_COL] = BaseElement::ZERO;
+    row[FHE_SCALAR_COL] = BaseElement::from(3u8);
+
+    let pass = EvaluationFrame::<BaseElement>::from_rows(row.clone(), row.clone());
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::fhe_pass_response(&pass, BaseElement::ONE, &rand));
+
+    // the other rows of the pass and padding passes send nothing
+    assert_eq!(
+        constrains::fhe_pass_response(&pass, BaseElement::ZERO, &rand),
+        BaseElement::ONE
+    );
+
+    row[FHE_ACTIVE_COL] = BaseElement::ZERO;
+    row[FHE_MAC_COL] = BaseElement::ZERO;
+    row[FHE_USE_B_COL] = BaseElement::ZERO;
+
+    let padding = EvaluationFrame::<BaseElement>::from_rows(row.clone(), row);
+
+    assert_eq!(
+        constrains::fhe_pass_response(&padding, BaseElement::ONE, &rand),
+        BaseElement::ONE
+    );
+}
+
+#[test]
+fn test_enforce_u32_ops() {
+    let max = u32::MAX as u64;
+
+    // a, b, the result on top of the stack and the u32 values of the helpers
+    let cases = [
+        (flags::U32ADD, [max, 2], [1, 1], [max, 2, 1, 0, 0]),
+        (flags::U32SUB, [0, 2], [1, max - 1], [0, 2, max - 1, 0, 0]),
+        (flags::U32MUL, [max - 1, 3], [1, max - 5], [max - 1, 3, max - 5, 2, 0]),
+        (flags::U32DIV, [17, 5], [3, 0], [17, 5, 3, 2, 2]),
+        (flags::U32MOD, [17, 5], [2, 0], [17, 5, 3, 2, 2]),
+    ];
+
+    for (code, [a, b], [s0, s1], values) in cases {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+        set_u32_values(&mut current, values);
+
+        current[U32_FLAG_COL] = BaseElement::ONE;
+        current[U32_INV_COL] = BaseElement::from(values[3]).inv();
+        current[STACK_OFFSET] = BaseElement::from(b);
+        current[STACK_OFFSET + 1] = BaseElement::from(a);
+        next[STACK_OFFSET] = BaseElement::from(s0);
+        next[STACK_OFFSET + 1] = BaseElement::from(s1);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 14];
+
+        constrains::enforce_u32_operands(&frame, &mut result[..2]);
+        constrains::enforce_u32add(&frame, &mut result[2..6]);
+        constrains::enforce_u32mul(&frame, &mut result[6..10]);
+        constrains::enforce_u32div(&frame, &mut result[10..]);
+
+        assert_eq!(constrains::enforce_u32_flag(&frame), BaseElement::ZERO);
+        assert_eq!(result, [BaseElement::ZERO; 14]);
+
+        // a wrong result breaks the operation
+        next[STACK_OFFSET] += BaseElement::ONE;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_u32add(&frame, &mut result[2..6]);
+        constrains::enforce_u32mul(&frame, &mut result[6..10]);
+        constrains::enforce_u32div(&frame, &mut result[10..]);
+
+        assert_ne!(result, [BaseElement::ZERO; 14]);
+    }
+}
+
+#[test]
+fn test_enforce_range_table() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    current[RANGE_VALUE_COL] = BaseElement::from(7u8);
+    current[RANGE_LOOKUP_COL] = BaseElement::ONE;
+    next[RANGE_VALUE_COL] = BaseElement::from(8u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next.clone());
+
+    let mut result = [BaseElement::ONE; 2];
+
+    constrains::enforce_range_table(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 2]);
+
+    // the values cannot skip 7
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    current[RANGE_VALUE_COL] = BaseElement::from(6u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    constrains::enforce_range_table(&frame, &mut result);
+
+    assert_ne!(result[1], BaseElement::ZERO);
+}
+
+#[test]
+fn test_range_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // the first limb of a u32 operation matches the lookup row of its value
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    current[U32_FLAG_COL] = BaseElement::ONE;
+    current[U32_LIMBS_OFFSET] = BaseElement::from(42u8);
+    current[RANGE_VALUE_COL] = BaseElement::from(42u8);
+    current[RANGE_LOOKUP_COL] = BaseElement::ONE;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::range_request(&frame, 0, &rand);
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::range_response(&frame, &rand));
+
+    // rows without u32 operations and filler rows leave the running product unchanged
+    let frame = EvaluationFrame::<BaseElement>::new(TRACE_WIDTH);
+
+    assert_eq!(constrains::range_request(&frame, 0, &rand), BaseElement::ONE);
+    assert_eq!(constrains::range_response(&frame, &rand), BaseElement::ONE);
+}
+
+#[test]
+fn test_enforce_bitwise_ops() {
+    let (a, b) = (0xf0f0a5a5u64, 0x0ff0ff00u64);
+
+    // the op code, the op value, the stack, the top of the next stack and the u32 values
+    let cases = [
+        (flags::AND, 0u64, [b, a], a & b, [a, b, a & b, a ^ b, 0]),
+        (flags::OR, 0, [b, a], a | b, [a, b, a & b, a ^ b, 0]),
+        (flags::XOR, 0, [b, a], a ^ b, [a, b, a & b, a ^ b, 0]),
+        (flags::SHL, 1 << 8, [a, 0], 0xf0a5a500, [a, 1 << 8, 0xf0a5a500, 0xf0, 0]),
+        (flags::SHR, 1 << 4, [a, 0], a >> 4, [a, 1 << 4, a >> 4, 5, 10]),
+    ];
+
+    for (code, imm, [s0, s1], result, values) in cases {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+        set_u32_values(&mut current, values);
+
+        current[IMM_COL] = BaseElement::from(imm);
+        current[U32_FLAG_COL] = BaseElement::ONE;
+        current[BITWISE_FLAG_COL] = BaseElement::from((imm == 0) as u8);
+        current[STACK_OFFSET] = BaseElement::from(s0);
+        current[STACK_OFFSET + 1] = BaseElement::from(s1);
+        next[STACK_OFFSET] = BaseElement::from(result);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 11];
+
+        constrains::enforce_u32_operands(&frame, &mut result[..2]);
+        constrains::enforce_shift(&frame, &mut result[2..7]);
+        constrains::enforce_bitwise(&frame, &mut result[7..]);
+
+        assert_eq!(constrains::enforce_u32_flag(&frame), BaseElement::ZERO);
+        assert_eq!(result, [BaseElement::ZERO; 11]);
+
+        // a wrong result breaks the operation
+        next[STACK_OFFSET] += BaseElement::ONE;
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_shift(&frame, &mut result[2..7]);
+        constrains::enforce_bitwise(&frame, &mut result[7..]);
+
+        assert_ne!(result, [BaseElement::ZERO; 11]);
+    }
+}
+
+#[test]
+fn test_enforce_comparisons() {
+    let modulus = 1u64 << 32;
+
+    // the op code, the stack, the top of the next stack and the u32 values
+    let cases = [
+        (flags::LT, [5u64, 3], 1u64, [3, 5, modulus - 2, 0, 0]),
+        (flags::LT, [3, 5], 0, [5, 3, 2, 0, 0]),
+        (flags::LTE, [5, 5], 1, [5, 5, 0, 0, 0]),
+        (flags::LTE, [3, 5], 0, [5, 3, modulus - 2, 0, 0]),
+    ];
+
+    for (code, [s0, s1], result, values) in cases {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        set_op(&mut current, code);
+        set_u32_values(&mut current, values);
+
+        current[U32_FLAG_COL] = BaseElement::ONE;
+        current[STACK_OFFSET] = BaseElement::from(s0);
+        current[STACK_OFFSET + 1] = BaseElement::from(s1);
+        next[STACK_OFFSET] = BaseElement::from(result);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 5];
+
+        constrains::enforce_u32_operands(&frame, &mut result[..2]);
+        constrains::enforce_compare(&frame, &mut result[2..]);
+
+        assert_eq!(constrains::enforce_u32_flag(&frame), BaseElement::ZERO);
+        assert_eq!(result, [BaseElement::ZERO; 5]);
+
+        // a flipped result breaks the difference
+        next[STACK_OFFSET] = BaseElement::ONE - next[STACK_OFFSET];
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_compare(&frame, &mut result[2..]);
+
+        assert_ne!(result, [BaseElement::ZERO; 5]);
+    }
+}
+
+#[test]
+fn test_enforce_eq() {
+    // the op code, the stack and the top of the next stack
+    let cases = [
+        (flags::EQ, [4u64, 4], 1u64),
+        (flags::EQ, [9, 3], 0),
+        (flags::NEQ, [4, 4], 0),
+        (flags::NEQ, [9, 3], 1),
+    ];
+
+    for (code, [s0, s1], result) in cases {
+        let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+        let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+        let diff = BaseElement::from(s1) - BaseElement::from(s0);
+
+        set_op(&mut current, code);
+
+        current[U32_INV_COL] = if diff == BaseElement::ZERO { diff } else { diff.inv() };
+        current[STACK_OFFSET] = BaseElement::from(s0);
+        current[STACK_OFFSET + 1] = BaseElement::from(s1);
+        next[STACK_OFFSET] = BaseElement::from(result);
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+        let mut result = [BaseElement::ONE; 2];
+
+        constrains::enforce_eq(&frame, &mut result);
+
+        assert_eq!(constrains::enforce_u32_flag(&frame), BaseElement::ZERO);
+        assert_eq!(result, [BaseElement::ZERO; 2]);
+
+        // a flipped result is rejected for any inverse
+        next[STACK_OFFSET] = BaseElement::ONE - next[STACK_OFFSET];
+
+        let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+        constrains::enforce_eq(&frame, &mut result);
+
+        assert_ne!(result, [BaseElement::ZERO; 2]);
+    }
+}
+
+#[test]
+fn test_enforce_bitwise_table() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    current[BITWISE_LOOKUP_COL] = BaseElement::ONE;
+    current[BITWISE_A_BITS_OFFSET + 3] = BaseElement::ONE;
+    current[BITWISE_B_BITS_OFFSET + 7] = BaseElement::ONE;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+    let mut result = [BaseElement::ONE; 17];
+
+    constrains::enforce_bitwise_table(&frame, &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 17]);
+
+    // the bits are binary
+    current[BITWISE_B_BITS_OFFSET] = BaseElement::from(2u8);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    constrains::enforce_bitwise_table(&frame, &mut result);
+
+    assert_ne!(result[9], BaseElement::ZERO);
+}
+
+#[test]
+fn test_bitwise_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    // the first byte of an and matches the lookup row of its two bytes
+    let (a, b) = (0xa5u8, 0x0fu8);
+
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    current[BITWISE_FLAG_COL] = BaseElement::ONE;
+    set_u32_values(&mut current, [a as u64, b as u64, (a & b) as u64, (a ^ b) as u64, 0]);
+
+    current[BITWISE_LOOKUP_COL] = BaseElement::ONE;
+    for i in 0..8 {
+        current[BITWISE_A_BITS_OFFSET + i] = BaseElement::from(a >> i & 1);
+        current[BITWISE_B_BITS_OFFSET + i] = BaseElement::from(b >> i & 1);
+    }
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::bitwise_request(&frame, 0, &rand);
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::bitwise_response(&frame, &rand));
+
+    // rows without bitwise operations and padding rows leave the running product unchanged
+    let frame = EvaluationFrame::<BaseElement>::new(TRACE_WIDTH);
+
+    assert_eq!(constrains::bitwise_request(&frame, 0, &rand), BaseElement::ONE);
+    assert_eq!(constrains::bitwise_response(&frame, &rand), BaseElement::ONE);
+}
+
+#[test]
+fn test_enforce_hperm_table() {
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    let input = [5u8, 6, 7, 8].map(BaseElement::from);
+    let mut state = [1u8, 2, 3, 4].map(BaseElement::from);
+
+    // the round 3 of a flagged cycle
+    current[HPERM_FLAG_COL] = BaseElement::ONE;
+    next[HPERM_FLAG_COL] = BaseElement::ONE;
+    current[HPERM_STATE_OFFSET..HPERM_STATE_OFFSET + 4].copy_from_slice(&state);
+    current[HPERM_INPUT_OFFSET..HPERM_INPUT_OFFSET + 4].copy_from_slice(&input);
+    next[HPERM_INPUT_OFFSET..HPERM_INPUT_OFFSET + 4].copy_from_slice(&input);
+
+    rescue::apply_round(&mut state, 0, BaseElement::ZERO, 3);
+    next[HPERM_STATE_OFFSET..HPERM_STATE_OFFSET + 4].copy_from_slice(&state);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+    let mut result = [BaseElement::ONE; 14];
+
+    constrains::enforce_hperm_table(&frame, BaseElement::ONE, BaseElement::ZERO, &ARK[3], &mut result);
+
+    assert_eq!(result, [BaseElement::ZERO; 14]);
+
+    // the first row of a cycle holds the input state
+    constrains::enforce_hperm_table(&frame, BaseElement::ONE, BaseElement::ONE, &ARK[3], &mut result);
+
+    assert_ne!(result[6..10], [BaseElement::ZERO; 4]);
+
+    // the flag and the input state are kept through the rounds
+    next[HPERM_FLAG_COL] = BaseElement::ZERO;
+    next[HPERM_INPUT_OFFSET] += BaseElement::ONE;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current.clone(), next.clone());
+
+    constrains::enforce_hperm_table(&frame, BaseElement::ONE, BaseElement::ZERO, &ARK[3], &mut result);
+
+    assert_ne!(result[1], BaseElement::ZERO);
+    assert_ne!(result[2], BaseElement::ZERO);
+
+    // a wrong round breaks a flagged cycle
+    next[HPERM_STATE_OFFSET] += BaseElement::ONE;
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    constrains::enforce_hperm_table(&frame, BaseElement::ONE, BaseElement::ZERO, &ARK[3], &mut result);
+
+    assert_ne!(result[10], BaseElement::ZERO);
+}
+
+#[test]
+fn test_hperm_request_response() {
+    let rand: Vec<BaseElement> = (0..NUM_AUX_RAND_ELEMENTS)
+        .map(|i| BaseElement::from(i as u8 + 3))
+        .collect();
+
+    let input = [1u8, 2, 3, 4].map(BaseElement::from);
+    let mut output = input;
+    rescue::apply_permutation(&mut output);
+
+    // the stack states of hperm match the row after the rounds of its cycle
+    let mut current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+
+    set_op(&mut current, flags::HPERM);
+    current[STACK_OFFSET..STACK_OFFSET + 4].copy_from_slice(&input);
+    next[STACK_OFFSET..STACK_OFFSET + 4].copy_from_slice(&output);
+
+    current[HPERM_FLAG_COL] = BaseElement::ONE;
+    current[HPERM_STATE_OFFSET..HPERM_STATE_OFFSET + 4].copy_from_slice(&output);
+    current[HPERM_INPUT_OFFSET..HPERM_INPUT_OFFSET + 4].copy_from_slice(&input);
+
+    let frame = EvaluationFrame::<BaseElement>::from_rows(current, next);
+
+    let request = constrains::hperm_request(&frame, &rand);
+
+    assert_ne!(request, BaseElement::ONE);
+    assert_eq!(request, constrains::hperm_response(&frame, BaseElement::ONE, &rand));
+
+    // the rounds of the cycle do not answer
+    assert_eq!(
+        constrains::hperm_response(&frame, BaseElement::ZERO, &rand),
+        BaseElement::ONE
+    );
+
+    // rows without hperm and padding cycles leave the running product unchanged
+    let frame = EvaluationFrame::<BaseElement>::new(TRACE_WIDTH);
+
+    assert_eq!(constrains::hperm_request(&frame, &rand), BaseElement::ONE);
+    assert_eq!(
+        constrains::hperm_response(&frame, BaseElement::ONE, &rand),
+        BaseElement::ONE
+    );
+}
+
+#[test]
+fn test_output_pass_assertions() {
+    let server_key = server_key();
+    let ciphertext = server_key.encrypt(3u8).ciphertext().to_vec();
+
+    // passes of 8 rows, handle 2 starts at row 16
+    let mut outputs = [BaseElement::ZERO; 16];
+    outputs[0] = BaseElement::from(2u8);
+
+    let pub_inputs =
+        PublicInputs::new([BaseElement::ZERO; DIGEST_SIZE], outputs, server_key).with_ciphertext_output(&ciphertext);
+    let air = ProcessorAir::new(trace_info(64), pub_inputs, proof_options());
+
+    let assertions = air.get_assertions();
+
+    assert_eq!(assertions.len(), 46 + 2 + ciphertext.len());
+    assert!(assertions.contains(&Assertion::single(FHE_ACTIVE_COL, 16, BaseElement::ONE)));
+    assert!(assertions.contains(&Assertion::single(FHE_HANDLE_COL, 17, BaseElement::from(2u8))));
+    for (i, &value) in ciphertext.iter().enumerate() {
+        assert!(assertions.contains(&Assertion::single(FHE_VALUE_OFFSET + 2, 16 + i, value)));
+    }
+}
+
+#[test]
+fn test_output_handle_past_passes() {
+    let server_key = server_key();
+    let ciphertext = server_key.encrypt(3u8).ciphertext().to_vec();
+
+    // a trace of 64 rows holds 8 passes, handle 8 wraps to the pass of handle 0
+    let mut outputs = [BaseElement::ZERO; 16];
+    outputs[0] = BaseElement::from(8u8);
+
+    let pub_inputs =
+        PublicInputs::new([BaseElement::ZERO; DIGEST_SIZE], outputs, server_key).with_ciphertext_output(&ciphertext);
+    let air = ProcessorAir::new(trace_info(64), pub_inputs, proof_options());
+
+    let assertions = air.get_assertions();
+
+    // the pass of handle 0 keeps the handle 0 of its first row, it cannot take handle 8
+    assert!(assertions.contains(&Assertion::single(FHE_HANDLE_COL, 0, BaseElement::ZERO)));
+    assert!(assertions.contains(&Assertion::single(FHE_HANDLE_COL, 1, BaseElement::from(8u8))));
+
+    let current = vec![BaseElement::ZERO; TRACE_WIDTH];
+    let mut next = vec![BaseElement::ZERO; TRACE_WIDTH];
+    next[FHE_INDEX_COL] = BaseElement::ONE;
+    next[FHE_HANDLE_COL] = BaseElement::from(8u8);
+
+    let mut result = [BaseElement::ZERO; 8];
+    constrains::enforce_fhe_pass(
+        &EvaluationFrame::from_rows(current, next),
+        BaseElement::ZERO,
+        &mut result,
+    );
+    assert_ne!(result[6], BaseElement::ZERO);
+}
+
+fn trace_info(length: usize) -> TraceInfo {
+    TraceInfo::new_multi_segment(TRACE_WIDTH, AUX_TRACE_WIDTH, NUM_AUX_RAND_ELEMENTS, length, Vec::new())
+}
+
+fn proof_options() -> ProofOptions {
+    ProofOptions::new(32, 8, 0, winterfell::FieldExtension::None, 8, 127)
+}
+
+fn set_op(row: &mut [BaseElement], code: u8) {
+    for i in 0..7 {
+        row[OP_BITS_OFFSET + i] = BaseElement::from(code >> i & 1);
+    }
+}
+
+// writes the little endian byte limbs of the u32 values
+fn set_u32_values(row: &mut [BaseElement], values: [u64; 5]) {
+    for (i, value) in values.iter().enumerate() {
+        for j in 0..4 {
+            row[U32_LIMBS_OFFSET + 4 * i + j] = BaseElement::from((value >> (8 * j)) as u8);
+        }
+    }
 }
 
 fn server_key() -> ServerKey {
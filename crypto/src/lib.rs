@@ -1,2 +1,5 @@
 pub mod rescue;
 pub use rescue::{Hash, Rescue128};
+
+pub mod merkle;
+pub use merkle::MerkleTree;
@@ -0,0 +1,69 @@
+use crate::rescue::{self, Hash};
+
+// binary tree over a power of two number of leaves, a node hashes the digests of its children
+// nodes[1] is the root and the children of node i are 2i and 2i + 1, the leaves start at nodes[n]
+pub struct MerkleTree {
+    nodes: Vec<Hash>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: &[Hash]) -> Self {
+        assert!(
+            leaves.len().is_power_of_two(),
+            "the number of leaves must be a power of two"
+        );
+
+        let n = leaves.len();
+        let mut nodes = vec![Hash::default(); 2 * n];
+        nodes[n..].copy_from_slice(leaves);
+
+        for i in (1..n).rev() {
+            nodes[i] = merge(&nodes[2 * i], &nodes[2 * i + 1]);
+        }
+
+        MerkleTree { nodes }
+    }
+
+    pub fn root(&self) -> Hash {
+        // a single leaf is its own root
+        self.nodes[1]
+    }
+
+    pub fn depth(&self) -> usize {
+        (self.nodes.len() / 2).trailing_zeros() as usize
+    }
+
+    // the siblings of the leaf from the bottom of the tree to the children of the root
+    pub fn path(&self, index: usize) -> Vec<Hash> {
+        let n = self.nodes.len() / 2;
+        assert!(index < n, "the leaf index is out of the tree");
+
+        let mut node = n + index;
+        let mut path = Vec::with_capacity(self.depth());
+
+        while node > 1 {
+            path.push(self.nodes[node ^ 1]);
+            node /= 2;
+        }
+
+        path
+    }
+}
+
+// the parent digest of two nodes, hash_elements of [left, right]
+pub fn merge(left: &Hash, right: &Hash) -> Hash {
+    let [l0, l1] = left.to_elements();
+    let [r0, r1] = right.to_elements();
+
+    rescue::hash_elements(&[l0, l1, r0, r1])
+}
+
+// the root of a leaf and its path, bit i of the index tells whether the node is the right child at level i
+pub fn compute_root(leaf: Hash, index: usize, path: &[Hash]) -> Hash {
+    path.iter()
+        .enumerate()
+        .fold(leaf, |node, (i, sibling)| match index >> i & 1 {
+            0 => merge(&node, sibling),
+            _ => merge(sibling, &node),
+        })
+}
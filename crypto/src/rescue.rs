@@ -13,6 +13,7 @@ pub const NUM_ROUNDS: usize = 14;
 
 pub const CYCLE_LENGTH: usize = 16;
 
+#[derive(Clone)]
 pub struct Rescue128 {
     state: [BaseElement; STATE_WIDTH],
     step: usize,
@@ -43,7 +44,7 @@ impl Rescue128 {
         self.step % CYCLE_LENGTH < NUM_ROUNDS
     }
 
-    pub fn update(&mut self, op_code: u8, op_value: u8) {
+    pub fn update(&mut self, op_code: u8, op_value: BaseElement) {
         if self.is_apply_round() {
             apply_round(&mut self.state, op_code, op_value, self.step);
         } else {
@@ -53,6 +54,38 @@ impl Rescue128 {
         self.step += 1;
     }
 
+    // branch merge on a copy step, the executed branch digest stays first
+    // and the sibling digest is absorbed into the capacity: [s0, s1, s3, value]
+    pub fn merge_right(&mut self, value: BaseElement) {
+        self.state = [self.state[0], self.state[1], self.state[3], value];
+        self.step += 1;
+    }
+
+    // branch merge on a copy step, the executed branch digest moves to the capacity
+    // and the sibling digest is absorbed first: [s1, value, s3, s0]
+    pub fn merge_left(&mut self, value: BaseElement) {
+        self.state = [self.state[1], value, self.state[3], self.state[0]];
+        self.step += 1;
+    }
+
+    // loop iteration end on a copy step, restores the loop entry rate and resets the capacity
+    pub fn restore(&mut self, rate: [BaseElement; RATE_WIDTH]) {
+        self.state = [rate[0], rate[1], BaseElement::ZERO, BaseElement::ZERO];
+        self.step += 1;
+    }
+
+    // procedure call on a copy step, the procedure is hashed from the initial state
+    pub fn clear(&mut self) {
+        self.state = [BaseElement::ZERO; STATE_WIDTH];
+        self.step += 1;
+    }
+
+    // loop exit on a copy step, absorbs the loop body digest into the capacity
+    pub fn merge_capacity(&mut self, digest: [BaseElement; DIGEST_SIZE]) {
+        self.state = [self.state[0], self.state[1], digest[0], digest[1]];
+        self.step += 1;
+    }
+
     pub fn hash(self) -> Hash {
         Hash([self.state[0], self.state[1]])
     }
@@ -99,7 +132,7 @@ impl Deserializable for Hash {
     }
 }
 
-pub fn apply_round(state: &mut [BaseElement], op_code: u8, op_value: u8, step: usize) {
+pub fn apply_round(state: &mut [BaseElement], op_code: u8, op_value: BaseElement, step: usize) {
     // determine which round constants to use
     let ark = ARK[step % CYCLE_LENGTH];
 
@@ -109,7 +142,7 @@ pub fn apply_round(state: &mut [BaseElement], op_code: u8, op_value: u8, step: u
     add_constants(state, &ark, 0);
 
     state[0] += BaseElement::from(op_code);
-    state[1] += BaseElement::from(op_value);
+    state[1] += op_value;
 
     // apply second half of Rescue round
     apply_inv_sbox(state);
@@ -117,6 +150,29 @@ pub fn apply_round(state: &mut [BaseElement], op_code: u8, op_value: u8, step: u
     add_constants(state, &ark, STATE_WIDTH);
 }
 
+// sponge over field elements, absorbs RATE_WIDTH elements per permutation
+// the capacity is initialized with the input length to avoid padding collisions
+pub fn hash_elements(elements: &[BaseElement]) -> Hash {
+    let mut state = [BaseElement::ZERO; STATE_WIDTH];
+    state[RATE_WIDTH] = BaseElement::from(elements.len() as u64);
+
+    for chunk in elements.chunks(RATE_WIDTH) {
+        for (value, element) in state.iter_mut().zip(chunk.iter()) {
+            *value += *element;
+        }
+        apply_permutation(&mut state);
+    }
+
+    Hash([state[0], state[1]])
+}
+
+// the rounds of a cycle without injected values, the hash chiplet runs it for hperm
+pub fn apply_permutation(state: &mut [BaseElement]) {
+    for step in 0..NUM_ROUNDS {
+        apply_round(state, 0, BaseElement::ZERO, step);
+    }
+}
+
 pub fn get_round_constants() -> Vec<Vec<BaseElement>> {
     let mut constants = Vec::new();
     for _ in 0..(STATE_WIDTH * 2) {
@@ -0,0 +1,2 @@
+mod utils;
+pub use utils::{InputData, OutputData};
@@ -2,7 +2,7 @@ use std::path::Path;
 
 use fhe::{FheUInt8, LweParameters, ServerKey};
 
-use vm::{Program, ProgramInputs};
+use vm::{Policy, Program, ProgramInputs};
 
 use winterfell::{
     crypto::{hashers::Blake3_256, DefaultRandomCoin},
@@ -12,8 +12,7 @@ use winterfell::{
 
 use air::{ProcessorAir, PublicInputs};
 
-mod utils;
-use utils::{InputData, OutputData};
+use linear_regression::{InputData, OutputData};
 
 type Blake3 = Blake3_256<BaseElement>;
 
@@ -29,8 +28,10 @@ fn main() {
     let clear_x3 = 3u8;
     let clear_x4 = 2u8;
 
+    let path = Path::new("lr.txt");
+
     // Client
-    let (input_data, client_key) = {
+    let (input_data, client_key, policy) = {
         let plaintext_modulus: u32 = 8u32; // p
         let ciphertext_modulus: u32 = 128u32; // q
         let k: usize = 4; // This is the number of mask elements
@@ -44,20 +45,22 @@ fn main() {
         let x3 = client_key.encrypt(clear_x3);
         let x4 = client_key.encrypt(clear_x4);
 
-        let data = InputData::new(&[b1, b2, b3, b4, b0], &[x1, x2, x3, x4], &client_key);
+        // only the linear regression model may consume the data
+        let policy = Policy::new(&[Program::load(path).unwrap().hash()]);
+
+        let data = InputData::new(&[b1, b2, b3, b4, b0], &[x1, x2, x3, x4], &client_key, &policy);
 
-        (data.to_bytes(), client_key)
+        (data.to_bytes(), client_key, policy)
     };
 
     // Server
     let output_data = {
         let payload = InputData::read_from_bytes(&input_data).unwrap();
 
-        let path = Path::new("lr.txt");
-
         let program = Program::load(path).unwrap();
 
-        let inputs = ProgramInputs::new(payload.public_inputs(), payload.secret_inputs(), payload.server_key());
+        let inputs = ProgramInputs::new(payload.public_inputs(), payload.secret_inputs(), payload.server_key())
+            .with_policy(payload.policy());
 
         let (hash, output, proof) = vm::prove(program, inputs).unwrap();
 
@@ -80,7 +83,10 @@ fn main() {
 
     let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
 
-    let inputs = PublicInputs::new(results.hash().to_elements(), results.output(), client_key);
+    assert!(policy.allows(results.hash()));
+
+    let inputs = PublicInputs::new(results.hash().to_elements(), results.output(), client_key)
+        .with_policy_digest(policy.digest().to_elements());
 
     verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(results.proof(), inputs, &min_opts).unwrap()
 }
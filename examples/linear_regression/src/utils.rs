@@ -1,5 +1,6 @@
 use crypto::rescue::Hash;
-use fhe::{FheUInt8, ServerKey};
+use fhe::{FheUInt8, ReadLimits, ServerKey};
+use vm::Policy;
 use winterfell::{
     math::fields::f128::BaseElement, ByteReader, ByteWriter, Deserializable, DeserializationError, Proof, Serializable,
 };
@@ -9,14 +10,16 @@ pub struct InputData {
     public_inputs: Vec<u8>,
     secret_inputs: Vec<FheUInt8>,
     server_key: ServerKey,
+    policy: Policy,
 }
 
 impl InputData {
-    pub fn new(public_inputs: &[u8], secret_inputs: &[FheUInt8], server_key: &ServerKey) -> InputData {
+    pub fn new(public_inputs: &[u8], secret_inputs: &[FheUInt8], server_key: &ServerKey, policy: &Policy) -> InputData {
         InputData {
             public_inputs: public_inputs.to_vec(),
             secret_inputs: secret_inputs.to_vec(),
             server_key: server_key.clone(),
+            policy: policy.clone(),
         }
     }
 
@@ -31,44 +34,61 @@ impl InputData {
     pub fn server_key(&self) -> &ServerKey {
         &self.server_key
     }
-}
-
-impl Serializable for InputData {
-    fn write_into<W: ByteWriter>(&self, target: &mut W) {
-        self.server_key.write_into(target);
-
-        target.write_usize(self.secret_inputs.len());
-        for secret in self.secret_inputs.iter() {
-            secret.write_into(target);
-        }
 
-        target.write_usize(self.public_inputs.len());
-        target.write_bytes(&self.public_inputs);
+    pub fn policy(&self) -> &Policy {
+        &self.policy
     }
-}
 
-impl Deserializable for InputData {
-    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
-        let server_key = ServerKey::read_from(source)?;
+    pub fn read_with_limits<R: ByteReader>(source: &mut R, limits: &ReadLimits) -> Result<Self, DeserializationError> {
+        let server_key = ServerKey::read_with_limits(source, limits)?;
 
         let sec_len = source.read_usize()?;
+        limits.check_ciphertexts(sec_len)?;
+
         let mut secret_inputs = Vec::new();
 
         for _ in 0..sec_len {
-            secret_inputs.push(FheUInt8::read_from(source)?);
+            secret_inputs.push(FheUInt8::read_with_parameters(source, server_key.parameters())?);
         }
 
         let pub_len = source.read_usize()?;
+        limits.check_public_inputs(pub_len)?;
+
         let public_inputs = source.read_vec(pub_len)?;
 
+        let policy = Policy::read_from(source)?;
+
         Ok(InputData {
             public_inputs,
             secret_inputs,
             server_key,
+            policy,
         })
     }
 }
 
+impl Serializable for InputData {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        self.server_key.write_into(target);
+
+        target.write_usize(self.secret_inputs.len());
+        for secret in self.secret_inputs.iter() {
+            secret.write_into(target);
+        }
+
+        target.write_usize(self.public_inputs.len());
+        target.write_bytes(&self.public_inputs);
+
+        self.policy.write_into(target);
+    }
+}
+
+impl Deserializable for InputData {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        InputData::read_with_limits(source, &ReadLimits::default())
+    }
+}
+
 #[derive(Clone)]
 pub struct OutputData {
     hash: Hash,
@@ -112,6 +132,12 @@ impl Deserializable for OutputData {
         let proof = Proof::read_from(source)?;
         let output_len = source.read_usize()?;
 
+        if output_len != 16 {
+            return Err(DeserializationError::InvalidValue(format!(
+                "expected an output of length 16, but was {output_len}"
+            )));
+        }
+
         let mut out = Vec::new();
 
         for _ in 0..output_len {
@@ -0,0 +1,104 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use fhe::{FheUInt8, FheVecUInt8, LweParameters, ServerKey};
+use winterfell::math::fields::f128::BaseElement;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn server_key() -> ServerKey {
+    let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+    ServerKey::new(parameters)
+}
+
+fn inputs(server_key: &ServerKey, size: usize) -> (Vec<FheUInt8>, Vec<FheUInt8>, Vec<BaseElement>) {
+    let a = (0..size).map(|i| server_key.encrypt(i as u8 % 8)).collect();
+    let b = (0..size).map(|i| server_key.encrypt((i as u8 + 1) % 8)).collect();
+    let scalars = (0..size).map(|i| BaseElement::from(i as u8 % 4)).collect();
+    (a, b, scalars)
+}
+
+fn add(c: &mut Criterion) {
+    let server_key = server_key();
+    let mut group = c.benchmark_group("add");
+
+    for size in SIZES {
+        let (a, b, _) = inputs(&server_key, size);
+        let vector_a = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+        let vector_b = FheVecUInt8::from_elements(server_key.lwe_size(), &b).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("server_key", size), &size, |bench, _| {
+            bench.iter(|| {
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(x, y)| server_key.add(x, y))
+                    .collect::<Vec<_>>()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("fhe_vec", size), &size, |bench, _| {
+            bench.iter_batched_ref(
+                || vector_a.clone(),
+                |vector| vector.add_assign(black_box(&vector_b)).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn scalar_mul(c: &mut Criterion) {
+    let server_key = server_key();
+    let mut group = c.benchmark_group("scalar_mul");
+
+    for size in SIZES {
+        let (a, _, scalars) = inputs(&server_key, size);
+        let vector_a = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+
+        group.bench_with_input(BenchmarkId::new("server_key", size), &size, |bench, _| {
+            bench.iter(|| {
+                a.iter()
+                    .zip(scalars.iter())
+                    .map(|(x, s)| server_key.scalar_mul(s, x))
+                    .collect::<Vec<_>>()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("fhe_vec", size), &size, |bench, _| {
+            bench.iter_batched_ref(
+                || vector_a.clone(),
+                |vector| vector.scalar_mul_assign(black_box(&scalars)).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn dot(c: &mut Criterion) {
+    let server_key = server_key();
+    let mut group = c.benchmark_group("dot");
+
+    for size in SIZES {
+        let (a, _, scalars) = inputs(&server_key, size);
+        let vector_a = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+        let zero = server_key.encrypt_trivial(&BaseElement::from(0u8));
+
+        group.bench_with_input(BenchmarkId::new("server_key", size), &size, |bench, _| {
+            bench.iter(|| {
+                a.iter().zip(scalars.iter()).fold(zero.clone(), |acc, (x, s)| {
+                    server_key.add(&acc, &server_key.scalar_mul(s, x))
+                })
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("fhe_vec", size), &size, |bench, _| {
+            bench.iter(|| vector_a.dot(black_box(&scalars)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, add, scalar_mul, dot);
+criterion_main!(benches);
@@ -0,0 +1,415 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+
+use winterfell::{ByteReader, ByteWriter, DeserializationError, Serializable, SliceReader};
+
+use super::{limits::MAX_CHUNK_BYTES, Error, FheUInt8, LweParameters, ReadLimits, ServerKey};
+
+// file layout:
+// preamble: magic | version
+// header: header length in bytes (u64 le) | lwe parameters | column schema
+// chunks: chunk length in bytes (u64 le) | record count | records
+const MAGIC: [u8; 4] = *b"FHED";
+const VERSION: u8 = 1;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+// the record count of a chunk is a vint64, at most 9 bytes
+const MAX_RECORD_COUNT_BYTES: usize = 9;
+
+const MAX_COLUMN_NAME: usize = 256;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(u8)]
+pub enum ColumnKind {
+    Public = 0,
+    Secret = 1,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Column {
+    name: String,
+    kind: ColumnKind,
+}
+
+impl Column {
+    pub fn public(name: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            kind: ColumnKind::Public,
+        }
+    }
+
+    pub fn secret(name: &str) -> Column {
+        Column {
+            name: name.to_string(),
+            kind: ColumnKind::Secret,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> ColumnKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatasetHeader {
+    parameters: LweParameters,
+    columns: Vec<Column>,
+}
+
+impl DatasetHeader {
+    pub fn new(parameters: &LweParameters, columns: &[Column]) -> DatasetHeader {
+        DatasetHeader {
+            parameters: parameters.clone(),
+            columns: columns.to_vec(),
+        }
+    }
+
+    pub fn parameters(&self) -> &LweParameters {
+        &self.parameters
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    fn num_columns(&self, kind: ColumnKind) -> usize {
+        self.columns.iter().filter(|column| column.kind == kind).count()
+    }
+
+    fn read_with_limits<R: ByteReader>(source: &mut R, limits: &ReadLimits) -> Result<Self, DeserializationError> {
+        let parameters = LweParameters::read_with_limits(source, limits)?;
+
+        let num_columns = source.read_usize()?;
+        limits.check_public_inputs(num_columns)?;
+
+        if num_columns == 0 {
+            return Err(DeserializationError::InvalidValue(String::from(
+                "schema must have at least one column",
+            )));
+        }
+
+        let mut columns = Vec::new();
+
+        for _ in 0..num_columns {
+            let name_len = source.read_usize()?;
+            if name_len > MAX_COLUMN_NAME {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "column name length {name_len} exceeds the limit of {MAX_COLUMN_NAME}"
+                )));
+            }
+            let name = source.read_string(name_len)?;
+
+            let kind = match source.read_u8()? {
+                0 => ColumnKind::Public,
+                1 => ColumnKind::Secret,
+                kind => {
+                    return Err(DeserializationError::InvalidValue(format!(
+                        "unknown column kind {kind}"
+                    )))
+                }
+            };
+
+            columns.push(Column { name, kind });
+        }
+
+        let header = DatasetHeader { parameters, columns };
+        limits.check_ciphertexts(header.num_columns(ColumnKind::Secret))?;
+
+        Ok(header)
+    }
+}
+
+impl Serializable for DatasetHeader {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        self.parameters.write_into(target);
+
+        target.write_usize(self.columns.len());
+        for column in self.columns.iter() {
+            target.write_usize(column.name.len());
+            target.write_bytes(column.name.as_bytes());
+            target.write_u8(column.kind as u8);
+        }
+    }
+}
+
+// a dataset row split into the public and secret tapes of a program
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    public: Vec<u8>,
+    secret: Vec<FheUInt8>,
+}
+
+impl Record {
+    pub fn public(&self) -> &[u8] {
+        &self.public
+    }
+
+    pub fn secret(&self) -> &[FheUInt8] {
+        &self.secret
+    }
+}
+
+pub struct DatasetWriter<'a, W: Write> {
+    writer: W,
+    server_key: &'a ServerKey,
+    header: DatasetHeader,
+    chunk_size: usize,
+}
+
+impl<'a, W: Write> DatasetWriter<'a, W> {
+    pub fn new(writer: W, server_key: &'a ServerKey, columns: &[Column]) -> Result<Self, Error> {
+        DatasetWriter::with_chunk_size(writer, server_key, columns, DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(
+        mut writer: W,
+        server_key: &'a ServerKey,
+        columns: &[Column],
+        chunk_size: usize,
+    ) -> Result<Self, Error> {
+        if chunk_size == 0 {
+            return Err(Error::new(String::from("chunk size must be greater than 0")));
+        }
+
+        // records of an empty schema take no bytes, a chunk could claim any number of them
+        if columns.is_empty() {
+            return Err(Error::new(String::from("schema must have at least one column")));
+        }
+
+        // a chunk fits the default read limit, every ciphertext of the key takes the same bytes
+        let ciphertext_bytes = server_key.encrypt(0).to_bytes().len();
+        let record_bytes = columns
+            .iter()
+            .map(|column| match column.kind {
+                ColumnKind::Public => 1,
+                ColumnKind::Secret => ciphertext_bytes,
+            })
+            .sum::<usize>();
+
+        let max_records = (MAX_CHUNK_BYTES - MAX_RECORD_COUNT_BYTES) / record_bytes;
+        if max_records == 0 {
+            return Err(Error::new(format!(
+                "a record takes {record_bytes} bytes, over the chunk limit of {MAX_CHUNK_BYTES}"
+            )));
+        }
+
+        let header = DatasetHeader::new(server_key.parameters(), columns);
+
+        write_all(&mut writer, &MAGIC)?;
+        write_all(&mut writer, &[VERSION])?;
+        write_section(&mut writer, &header.to_bytes())?;
+
+        Ok(DatasetWriter {
+            writer,
+            server_key,
+            header,
+            chunk_size: chunk_size.min(max_records),
+        })
+    }
+
+    // each record holds one clear value per column, secret columns are encrypted in bulk
+    pub fn write_records(&mut self, records: &[Vec<u8>]) -> Result<(), Error> {
+        for (i, record) in records.iter().enumerate() {
+            if record.len() != self.header.columns.len() {
+                return Err(Error::new(format!(
+                    "record {i} has {} values, but the schema has {} columns",
+                    record.len(),
+                    self.header.columns.len()
+                )));
+            }
+        }
+
+        for chunk in records.chunks(self.chunk_size) {
+            self.write_chunk(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Err(err) = self.writer.flush() {
+            return Err(Error::new(err.to_string().to_lowercase()));
+        }
+        Ok(self.writer)
+    }
+
+    fn write_chunk(&mut self, records: &[Vec<u8>]) -> Result<(), Error> {
+        let columns = &self.header.columns;
+
+        let clear_secrets: Vec<u8> = records
+            .iter()
+            .flat_map(|record| {
+                record
+                    .iter()
+                    .zip(columns.iter())
+                    .filter(|(_, column)| column.kind == ColumnKind::Secret)
+                    .map(|(&value, _)| value)
+            })
+            .collect();
+
+        let mut secrets = self.server_key.encrypt_many(&clear_secrets).into_iter();
+
+        let mut chunk = Vec::new();
+        chunk.write_usize(records.len());
+
+        for record in records.iter() {
+            for (&value, column) in record.iter().zip(columns.iter()) {
+                match column.kind {
+                    ColumnKind::Public => chunk.write_u8(value),
+                    ColumnKind::Secret => secrets.next().unwrap().write_into(&mut chunk),
+                }
+            }
+        }
+
+        write_section(&mut self.writer, &chunk)
+    }
+}
+
+pub struct DatasetReader<R: Read> {
+    reader: R,
+    header: DatasetHeader,
+    limits: ReadLimits,
+    records: VecDeque<Record>,
+}
+
+impl<R: Read> DatasetReader<R> {
+    pub fn new(reader: R) -> Result<Self, Error> {
+        DatasetReader::with_limits(reader, ReadLimits::default())
+    }
+
+    pub fn with_limits(mut reader: R, limits: ReadLimits) -> Result<Self, Error> {
+        let mut preamble = [0u8; 5];
+        if let Err(err) = reader.read_exact(&mut preamble) {
+            return Err(Error::new(err.to_string().to_lowercase()));
+        }
+
+        if preamble[..4] != MAGIC {
+            return Err(Error::new(String::from("not an encrypted dataset")));
+        }
+
+        if preamble[4] != VERSION {
+            return Err(Error::new(format!("unsupported dataset version {}", preamble[4])));
+        }
+
+        let header = match read_section(&mut reader, &limits)? {
+            Some(bytes) => match DatasetHeader::read_with_limits(&mut SliceReader::new(&bytes), &limits) {
+                Ok(header) => header,
+                Err(err) => return Err(Error::new(err.to_string().to_lowercase())),
+            },
+            None => return Err(Error::new(String::from("missing dataset header"))),
+        };
+
+        Ok(DatasetReader {
+            reader,
+            header,
+            limits,
+            records: VecDeque::new(),
+        })
+    }
+
+    pub fn header(&self) -> &DatasetHeader {
+        &self.header
+    }
+
+    // reads the next chunk of records, returns None at the end of the dataset
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<Record>>, Error> {
+        let chunk = match read_section(&mut self.reader, &self.limits)? {
+            Some(chunk) => chunk,
+            None => return Ok(None),
+        };
+
+        match self.read_records(&mut SliceReader::new(&chunk)) {
+            Ok(records) => Ok(Some(records)),
+            Err(err) => Err(Error::new(err.to_string().to_lowercase())),
+        }
+    }
+
+    fn read_records(&self, source: &mut SliceReader) -> Result<Vec<Record>, DeserializationError> {
+        let num_records = source.read_usize()?;
+        // the schema has a column, every record takes at least one byte
+        source.check_eor(num_records)?;
+
+        let mut records = Vec::new();
+
+        for _ in 0..num_records {
+            let mut public = Vec::new();
+            let mut secret = Vec::new();
+
+            for column in self.header.columns.iter() {
+                match column.kind {
+                    ColumnKind::Public => public.push(source.read_u8()?),
+                    ColumnKind::Secret => secret.push(FheUInt8::read_with_parameters(source, &self.header.parameters)?),
+                }
+            }
+
+            records.push(Record { public, secret });
+        }
+
+        if source.has_more_bytes() {
+            return Err(DeserializationError::UnconsumedBytes);
+        }
+
+        Ok(records)
+    }
+}
+
+impl<R: Read> Iterator for DatasetReader<R> {
+    type Item = Result<Record, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.records.is_empty() {
+            match self.next_chunk() {
+                Ok(Some(records)) => self.records.extend(records),
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.records.pop_front().map(Ok)
+    }
+}
+
+// a section is its length in bytes (u64 le) followed by its content
+fn write_section<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    write_all(writer, &(bytes.len() as u64).to_le_bytes())?;
+    write_all(writer, bytes)
+}
+
+// returns None when the reader is exhausted before a new section starts
+fn read_section<R: Read>(reader: &mut R, limits: &ReadLimits) -> Result<Option<Vec<u8>>, Error> {
+    let mut len_bytes = [0u8; 8];
+
+    match reader.read_exact(&mut len_bytes) {
+        Ok(_) => (),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(Error::new(err.to_string().to_lowercase())),
+    }
+
+    let len = u64::from_le_bytes(len_bytes);
+    if len > limits.max_chunk_bytes as u64 {
+        return Err(Error::new(format!(
+            "section length {len} exceeds the limit of {}",
+            limits.max_chunk_bytes
+        )));
+    }
+
+    // take bounds the allocation by the bytes actually present in the reader
+    let mut bytes = Vec::new();
+    match reader.take(len).read_to_end(&mut bytes) {
+        Ok(read) if read as u64 == len => Ok(Some(bytes)),
+        Ok(_) => Err(Error::new(String::from("unexpected end of dataset section"))),
+        Err(err) => Err(Error::new(err.to_string().to_lowercase())),
+    }
+}
+
+fn write_all<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), Error> {
+    match writer.write_all(bytes) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(Error::new(err.to_string().to_lowercase())),
+    }
+}
@@ -3,7 +3,7 @@ use winterfell::{
     ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable,
 };
 
-use super::{Export, Import};
+use super::{Export, Import, LweParameters, ReadLimits};
 
 pub type FheUInt8 = FheElement<BaseElement>;
 
@@ -25,6 +25,50 @@ impl<E: FieldElement> FheElement<E> {
     pub fn ciphertext(&self) -> &[E] {
         &self.ciphertext
     }
+
+    pub fn read_with_limits<R: ByteReader>(source: &mut R, limits: &ReadLimits) -> Result<Self, DeserializationError> {
+        let ct_len = source.read_usize()?;
+
+        // a ciphertext holds k mask elements and one body element
+        if ct_len == 0 {
+            return Err(DeserializationError::InvalidValue(
+                "ciphertext must not be empty".to_string(),
+            ));
+        }
+        limits.check_lwe_dimension(ct_len - 1)?;
+
+        read_elements(source, ct_len)
+    }
+
+    pub fn read_with_parameters<R: ByteReader>(
+        source: &mut R,
+        parameters: &LweParameters,
+    ) -> Result<Self, DeserializationError> {
+        let ct_len = source.read_usize()?;
+
+        if ct_len != parameters.k + 1 {
+            return Err(DeserializationError::InvalidValue(format!(
+                "expected a ciphertext of length {}, but was {ct_len}",
+                parameters.k + 1
+            )));
+        }
+
+        read_elements(source, ct_len)
+    }
+}
+
+// E::read_from rejects values outside the canonical range of the field
+fn read_elements<E: FieldElement, R: ByteReader>(
+    source: &mut R,
+    ct_len: usize,
+) -> Result<FheElement<E>, DeserializationError> {
+    let mut ciphertext = Vec::new();
+
+    for _ in 0..ct_len {
+        ciphertext.push(E::read_from(source)?);
+    }
+
+    Ok(FheElement { ciphertext })
 }
 
 impl<E: FieldElement> Serializable for FheElement<E> {
@@ -38,15 +82,7 @@ impl<E: FieldElement> Serializable for FheElement<E> {
 
 impl<E: FieldElement> Deserializable for FheElement<E> {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
-        let ct_len = source.read_usize()?;
-
-        let mut ciphertext = Vec::new();
-
-        for _ in 0..ct_len {
-            ciphertext.push(E::read_from(source)?);
-        }
-
-        Ok(FheElement { ciphertext })
+        FheElement::read_with_limits(source, &ReadLimits::default())
     }
 }
 
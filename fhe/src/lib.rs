@@ -4,15 +4,27 @@ use std::path::Path;
 use std::{fs::File, io::Cursor};
 use winterfell::{Deserializable, Serializable};
 
+mod dataset;
+pub use dataset::{Column, ColumnKind, DatasetHeader, DatasetReader, DatasetWriter, Record};
+
+mod limits;
+pub use limits::ReadLimits;
+
 mod parameters;
 pub use parameters::LweParameters;
 
 mod server_key;
 pub use server_key::ServerKey;
 
+mod scheme;
+pub use scheme::HomomorphicScheme;
+
 mod integer;
 pub use integer::{FheElement, FheUInt8};
 
+mod vector;
+pub use vector::{FheVec, FheVecUInt8};
+
 #[cfg(test)]
 mod tests;
 
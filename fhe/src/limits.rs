@@ -0,0 +1,60 @@
+use winterfell::DeserializationError;
+
+// default bounds applied by every Deserializable impl
+// large enough for the example parameters, small enough to bound allocations
+pub const MAX_LWE_DIMENSION: usize = 1 << 12;
+pub const MAX_CIPHERTEXTS: usize = 1 << 16;
+pub const MAX_PUBLIC_INPUTS: usize = 1 << 16;
+pub const MAX_CHUNK_BYTES: usize = 1 << 26;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReadLimits {
+    pub max_lwe_dimension: usize,
+    pub max_ciphertexts: usize,
+    pub max_public_inputs: usize,
+    pub max_chunk_bytes: usize,
+}
+
+impl ReadLimits {
+    pub fn new(max_lwe_dimension: usize, max_ciphertexts: usize, max_public_inputs: usize) -> Self {
+        ReadLimits {
+            max_lwe_dimension,
+            max_ciphertexts,
+            max_public_inputs,
+            max_chunk_bytes: MAX_CHUNK_BYTES,
+        }
+    }
+
+    // bounds the sections of an encrypted dataset
+    pub fn with_max_chunk_bytes(mut self, max_chunk_bytes: usize) -> Self {
+        self.max_chunk_bytes = max_chunk_bytes;
+        self
+    }
+
+    pub fn check_lwe_dimension(&self, k: usize) -> Result<(), DeserializationError> {
+        check_length("lwe dimension", k, self.max_lwe_dimension)
+    }
+
+    pub fn check_ciphertexts(&self, count: usize) -> Result<(), DeserializationError> {
+        check_length("ciphertext count", count, self.max_ciphertexts)
+    }
+
+    pub fn check_public_inputs(&self, count: usize) -> Result<(), DeserializationError> {
+        check_length("public input count", count, self.max_public_inputs)
+    }
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        ReadLimits::new(MAX_LWE_DIMENSION, MAX_CIPHERTEXTS, MAX_PUBLIC_INPUTS)
+    }
+}
+
+fn check_length(name: &str, length: usize, max: usize) -> Result<(), DeserializationError> {
+    if length > max {
+        return Err(DeserializationError::InvalidValue(format!(
+            "{name} {length} exceeds the limit of {max}"
+        )));
+    }
+    Ok(())
+}
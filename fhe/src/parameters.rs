@@ -1,5 +1,7 @@
 use winterfell::{ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable};
 
+use super::ReadLimits;
+
 #[derive(Clone, PartialEq)]
 pub struct LweParameters {
     pub plaintext_modulus: u32,
@@ -19,20 +21,8 @@ impl LweParameters {
             std,
         }
     }
-}
 
-impl Serializable for LweParameters {
-    fn write_into<W: ByteWriter>(&self, target: &mut W) {
-        target.write_u32(self.plaintext_modulus);
-        target.write_u32(self.ciphertext_modulus);
-        target.write_u32(self.delta);
-        target.write_usize(self.k);
-        target.write(self.std.to_le_bytes());
-    }
-}
-
-impl Deserializable for LweParameters {
-    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+    pub fn read_with_limits<R: ByteReader>(source: &mut R, limits: &ReadLimits) -> Result<Self, DeserializationError> {
         let plaintext_modulus = source.read_u32()?;
         let ciphertext_modulus = source.read_u32()?;
         let delta = source.read_u32()?;
@@ -40,6 +30,33 @@ impl Deserializable for LweParameters {
         let std_bytes = source.read_array::<8>()?;
         let std = f64::from_le_bytes(std_bytes);
 
+        limits.check_lwe_dimension(k)?;
+
+        if k == 0 {
+            return Err(DeserializationError::InvalidValue(
+                "lwe dimension must be greater than 0".to_string(),
+            ));
+        }
+
+        // decryption rounds on log2(delta) - 1, delta must be at least 2
+        if plaintext_modulus == 0 || ciphertext_modulus / plaintext_modulus < 2 {
+            return Err(DeserializationError::InvalidValue(format!(
+                "invalid moduli: plaintext {plaintext_modulus}, ciphertext {ciphertext_modulus}"
+            )));
+        }
+
+        if delta != ciphertext_modulus / plaintext_modulus {
+            return Err(DeserializationError::InvalidValue(format!(
+                "delta {delta} does not match the ciphertext and plaintext moduli"
+            )));
+        }
+
+        if !std.is_finite() || std < 0.0 {
+            return Err(DeserializationError::InvalidValue(format!(
+                "invalid standard deviation {std}"
+            )));
+        }
+
         Ok(LweParameters {
             plaintext_modulus,
             ciphertext_modulus,
@@ -50,6 +67,22 @@ impl Deserializable for LweParameters {
     }
 }
 
+impl Serializable for LweParameters {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.plaintext_modulus);
+        target.write_u32(self.ciphertext_modulus);
+        target.write_u32(self.delta);
+        target.write_usize(self.k);
+        target.write(self.std.to_le_bytes());
+    }
+}
+
+impl Deserializable for LweParameters {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        LweParameters::read_with_limits(source, &ReadLimits::default())
+    }
+}
+
 impl std::fmt::Debug for LweParameters {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         writeln!(f, "Plaintext Modulus {}", self.plaintext_modulus)?;
@@ -0,0 +1,47 @@
+use winterfell::math::{fields::f128::BaseElement, FieldElement};
+
+use super::integer::FheElement;
+use super::server_key::ServerKey;
+
+// homomorphic operations available to the vm
+// every operation is evaluated over any field element so the air can reuse it
+// on the extension field used by the constraint evaluator
+pub trait HomomorphicScheme: Clone + Send + Sync + 'static {
+    // number of field elements (stack registers) taken by a ciphertext
+    fn ciphertext_width(&self) -> usize;
+
+    fn encrypt_trivial<E: FieldElement + From<BaseElement>>(&self, message: &E) -> FheElement<E>;
+
+    fn add<E: FieldElement + From<BaseElement>>(&self, value0: &FheElement<E>, value1: &FheElement<E>)
+        -> FheElement<E>;
+
+    fn scalar_add<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E>;
+
+    fn scalar_mul<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E>;
+}
+
+impl HomomorphicScheme for ServerKey {
+    fn ciphertext_width(&self) -> usize {
+        self.lwe_size()
+    }
+
+    fn encrypt_trivial<E: FieldElement + From<BaseElement>>(&self, message: &E) -> FheElement<E> {
+        ServerKey::encrypt_trivial(self, message)
+    }
+
+    fn add<E: FieldElement + From<BaseElement>>(
+        &self,
+        value0: &FheElement<E>,
+        value1: &FheElement<E>,
+    ) -> FheElement<E> {
+        ServerKey::add(self, value0, value1)
+    }
+
+    fn scalar_add<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E> {
+        ServerKey::scalar_add(self, scalar, value)
+    }
+
+    fn scalar_mul<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E> {
+        ServerKey::scalar_mul(self, scalar, value)
+    }
+}
@@ -1,5 +1,6 @@
 use rand::Rng;
 use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
 use std::ops::Mul;
 use winterfell::math::{FieldElement, StarkField};
 use winterfell::{
@@ -8,7 +9,7 @@ use winterfell::{
 
 use super::integer::{FheElement, FheUInt8};
 use super::parameters::LweParameters;
-use super::{Export, Import};
+use super::{Export, Import, ReadLimits};
 
 #[derive(Clone)]
 pub struct ServerKey {
@@ -61,6 +62,14 @@ impl ServerKey {
         FheUInt8::new(&ciphertext)
     }
 
+    pub fn encrypt_many(&self, values: &[u8]) -> Vec<FheUInt8> {
+        values.par_iter().map(|&value| self.encrypt(value)).collect()
+    }
+
+    pub fn decrypt_many(&self, values: &[FheUInt8]) -> Vec<u8> {
+        values.par_iter().map(|value| self.decrypt(value)).collect()
+    }
+
     pub fn decrypt(&self, value: &FheUInt8) -> u8 {
         let ciphertext = value.ciphertext().to_vec();
         let mut applied_mask = BaseElement::ZERO;
@@ -126,6 +135,39 @@ impl ServerKey {
     pub fn key(&self) -> &[BaseElement] {
         &self.key
     }
+
+    pub fn parameters(&self) -> &LweParameters {
+        &self.parameters
+    }
+
+    pub fn read_with_limits<R: ByteReader>(source: &mut R, limits: &ReadLimits) -> Result<Self, DeserializationError> {
+        let parameters = LweParameters::read_with_limits(source, limits)?;
+        let key_len = source.read_usize()?;
+
+        if key_len != parameters.k {
+            return Err(DeserializationError::InvalidValue(format!(
+                "expected a key of length {}, but was {key_len}",
+                parameters.k
+            )));
+        }
+
+        let mut key = Vec::new();
+
+        for _ in 0..key_len {
+            let value = BaseElement::read_from(source)?;
+
+            // secret key is a binary vector
+            if value != BaseElement::ZERO && value != BaseElement::ONE {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "key element {value} is not binary"
+                )));
+            }
+
+            key.push(value);
+        }
+
+        Ok(ServerKey { key, parameters })
+    }
 }
 
 impl Serializable for ServerKey {
@@ -141,16 +183,7 @@ impl Serializable for ServerKey {
 
 impl Deserializable for ServerKey {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
-        let parameters = LweParameters::read_from(source)?;
-        let key_len = source.read_usize()?;
-
-        let mut key = Vec::new();
-
-        for _ in 0..key_len {
-            key.push(BaseElement::read_from(source)?);
-        }
-
-        Ok(ServerKey { key, parameters })
+        ServerKey::read_with_limits(source, &ReadLimits::default())
     }
 }
 
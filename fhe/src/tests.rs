@@ -1,7 +1,12 @@
 use tempfile::NamedTempFile;
-use winterfell::math::fields::f128::BaseElement;
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    ByteWriter, DeserializationError, SliceReader,
+};
 
 use super::*;
+use dataset::DEFAULT_CHUNK_SIZE;
+use limits::{MAX_CHUNK_BYTES, MAX_LWE_DIMENSION};
 
 #[test]
 fn test_export_and_import_server_key() {
@@ -63,6 +68,117 @@ fn test_serialize_and_deserialize_integer() {
     assert_eq!(x, read_x);
 }
 
+#[test]
+fn test_read_integer_with_parameters() {
+    let server_key = default_key();
+
+    let x_bytes = server_key.encrypt(33u8).to_bytes();
+
+    let read_x = FheUInt8::read_with_parameters(&mut SliceReader::new(&x_bytes), server_key.parameters()).unwrap();
+
+    assert_eq!(server_key.decrypt(&read_x), 33u8);
+
+    let parameters = LweParameters::new(8u32, 128u32, 2, 2.412_390_240_121_573e-5);
+
+    let error = FheUInt8::read_with_parameters(&mut SliceReader::new(&x_bytes), &parameters).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("expected a ciphertext of length 3, but was 5".to_string())
+    );
+}
+
+#[test]
+fn test_read_integer_over_limits() {
+    let x_bytes = FheUInt8::new(&[BaseElement::ONE; 9]).to_bytes();
+
+    let limits = ReadLimits::new(4, 1, 1);
+
+    let error = FheUInt8::read_with_limits(&mut SliceReader::new(&x_bytes), &limits).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("lwe dimension 8 exceeds the limit of 4".to_string())
+    );
+}
+
+#[test]
+fn test_read_integer_huge_length() {
+    let mut x_bytes = Vec::new();
+    x_bytes.write_usize(usize::MAX);
+
+    assert!(FheUInt8::read_from_bytes(&x_bytes).is_err());
+}
+
+#[test]
+fn test_read_non_canonical_integer() {
+    let mut x_bytes = Vec::new();
+    x_bytes.write_usize(2);
+    x_bytes.write_u128(BaseElement::MODULUS);
+    x_bytes.write_u128(0);
+
+    assert!(FheUInt8::read_from_bytes(&x_bytes).is_err());
+}
+
+#[test]
+fn test_read_non_binary_server_key() {
+    let server_key = default_key();
+
+    let mut sk_bytes = Vec::new();
+    server_key.parameters().write_into(&mut sk_bytes);
+    sk_bytes.write_usize(4);
+    for _ in 0..4 {
+        BaseElement::from(2u8).write_into(&mut sk_bytes);
+    }
+
+    let error = ServerKey::read_from_bytes(&sk_bytes).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("key element 2 is not binary".to_string())
+    );
+}
+
+#[test]
+fn test_read_server_key_length_mismatch() {
+    let server_key = default_key();
+
+    let mut sk_bytes = Vec::new();
+    server_key.parameters().write_into(&mut sk_bytes);
+    sk_bytes.write_usize(2);
+    BaseElement::ZERO.write_into(&mut sk_bytes);
+    BaseElement::ONE.write_into(&mut sk_bytes);
+
+    let error = ServerKey::read_from_bytes(&sk_bytes).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("expected a key of length 4, but was 2".to_string())
+    );
+}
+
+#[test]
+fn test_read_inconsistent_parameters() {
+    let mut parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+    parameters.delta = 3;
+
+    let error = LweParameters::read_from_bytes(&parameters.to_bytes()).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("delta 3 does not match the ciphertext and plaintext moduli".to_string())
+    );
+
+    let parameters = LweParameters::new(8u32, 128u32, 5000, 2.412_390_240_121_573e-5);
+
+    let error = LweParameters::read_from_bytes(&parameters.to_bytes()).unwrap_err();
+
+    assert_eq!(
+        error,
+        DeserializationError::InvalidValue("lwe dimension 5000 exceeds the limit of 4096".to_string())
+    );
+}
+
 #[test]
 fn test_server_key_encryption() {
     let server_key = default_key();
@@ -126,6 +242,227 @@ fn test_scalar_multiplication() {
     assert_eq!(3u8 * 33u8, server_key.decrypt(&result))
 }
 
+#[test]
+fn test_vector_addition() {
+    let server_key = default_key();
+
+    let a = [
+        server_key.encrypt(1u8),
+        server_key.encrypt(2u8),
+        server_key.encrypt(3u8),
+    ];
+    let b = [
+        server_key.encrypt(4u8),
+        server_key.encrypt(5u8),
+        server_key.encrypt(6u8),
+    ];
+
+    let mut vector_a = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+    let vector_b = FheVecUInt8::from_elements(server_key.lwe_size(), &b).unwrap();
+
+    vector_a.add_assign(&vector_b).unwrap();
+
+    for i in 0..3 {
+        assert_eq!(vector_a.get(i), server_key.add(&a[i], &b[i]));
+    }
+}
+
+#[test]
+fn test_vector_scalar_multiplication() {
+    let server_key = default_key();
+
+    let a = [server_key.encrypt(1u8), server_key.encrypt(2u8)];
+    let scalars = [BaseElement::from(3u8), BaseElement::from(2u8)];
+
+    let mut vector = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+
+    vector.scalar_mul_assign(&scalars).unwrap();
+
+    assert_eq!(server_key.decrypt(&vector.get(0)), 3u8);
+    assert_eq!(server_key.decrypt(&vector.get(1)), 4u8);
+}
+
+#[test]
+fn test_vector_dot_product() {
+    let server_key = default_key();
+
+    let a = [
+        server_key.encrypt(1u8),
+        server_key.encrypt(2u8),
+        server_key.encrypt(3u8),
+    ];
+    let scalars = [BaseElement::from(3u8), BaseElement::from(2u8), BaseElement::from(4u8)];
+
+    let vector = FheVecUInt8::from_elements(server_key.lwe_size(), &a).unwrap();
+
+    let result = vector.dot(&scalars).unwrap();
+
+    let expected = a
+        .iter()
+        .zip(scalars.iter())
+        .fold(server_key.encrypt_trivial(&BaseElement::ZERO), |acc, (x, s)| {
+            server_key.add(&acc, &server_key.scalar_mul(s, x))
+        });
+
+    assert_eq!(result, expected);
+    assert_eq!(server_key.decrypt(&result), 19u8);
+}
+
+#[test]
+fn test_vector_shape_errors() {
+    let server_key = default_key();
+
+    let mut vector = FheVecUInt8::from_elements(server_key.lwe_size(), &[server_key.encrypt(1u8)]).unwrap();
+    let other = FheVecUInt8::new(server_key.lwe_size()).unwrap();
+
+    assert_eq!(
+        format!("{}", vector.add_assign(&other).unwrap_err()),
+        "expected 1 ciphertexts of length 5, but was 0 of length 5"
+    );
+    assert_eq!(
+        format!("{}", vector.dot(&[]).unwrap_err()),
+        "expected 1 scalars, but was 0"
+    );
+    assert_eq!(
+        format!("{}", vector.push(&FheUInt8::new(&[BaseElement::ONE])).unwrap_err()),
+        "expected a ciphertext of length 5, but was 1"
+    );
+}
+
+#[test]
+fn test_vector_empty_lwe_size_error() {
+    let server_key = default_key();
+
+    assert_eq!(
+        format!("{}", FheVecUInt8::new(0).unwrap_err()),
+        "lwe size must be greater than 0"
+    );
+    assert!(FheVecUInt8::with_capacity(0, 4).is_err());
+    assert!(FheVecUInt8::from_elements(0, &[server_key.encrypt(1u8)]).is_err());
+}
+
+#[test]
+fn test_bulk_encryption() {
+    let server_key = default_key();
+
+    let values: Vec<u8> = (0..64).map(|i| i % 8).collect();
+
+    let ciphertexts = server_key.encrypt_many(&values);
+
+    assert_eq!(server_key.decrypt_many(&ciphertexts), values);
+}
+
+#[test]
+fn test_write_and_read_dataset() {
+    let server_key = default_key();
+
+    let columns = [
+        Column::public("age"),
+        Column::secret("weight"),
+        Column::secret("height"),
+    ];
+    let records: Vec<Vec<u8>> = (0..10).map(|i| vec![20 + i, i % 8, (i + 3) % 8]).collect();
+
+    let mut writer = DatasetWriter::with_chunk_size(Vec::new(), &server_key, &columns, 3).unwrap();
+    writer.write_records(&records).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    let mut reader = DatasetReader::new(bytes.as_slice()).unwrap();
+
+    assert_eq!(reader.header().columns(), &columns);
+    assert_eq!(reader.header().parameters(), server_key.parameters());
+
+    // 10 records in chunks of 3
+    let chunk = reader.next_chunk().unwrap().unwrap();
+    assert_eq!(chunk.len(), 3);
+
+    let read_records: Vec<Record> = reader.map(|record| record.unwrap()).collect();
+    assert_eq!(read_records.len(), 7);
+
+    for (record, clear) in chunk.iter().chain(read_records.iter()).zip(records.iter()) {
+        assert_eq!(record.public(), &clear[..1]);
+        assert_eq!(server_key.decrypt_many(record.secret()), clear[1..]);
+    }
+}
+
+#[test]
+fn test_write_and_read_dataset_at_limits() {
+    let parameters = LweParameters::new(8u32, 128u32, MAX_LWE_DIMENSION, 2.412_390_240_121_573e-5);
+    let server_key = ServerKey::new(parameters);
+
+    let columns = [Column::secret("x")];
+    let records: Vec<Vec<u8>> = (0..DEFAULT_CHUNK_SIZE).map(|i| vec![i as u8 % 8]).collect();
+
+    let mut writer = DatasetWriter::new(Vec::new(), &server_key, &columns).unwrap();
+    writer.write_records(&records).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    // a full chunk of ciphertexts of the largest dimension is over the read limit, the writer splits it
+    let mut reader = DatasetReader::new(bytes.as_slice()).unwrap();
+
+    let chunk = reader.next_chunk().unwrap().unwrap();
+    assert_eq!(chunk.len(), DEFAULT_CHUNK_SIZE - 1);
+
+    let read_records: Vec<Record> = chunk.into_iter().chain(reader.map(|record| record.unwrap())).collect();
+    assert_eq!(read_records.len(), DEFAULT_CHUNK_SIZE);
+
+    for (record, clear) in read_records.iter().zip(records.iter()) {
+        assert_eq!(server_key.decrypt_many(record.secret()), *clear);
+    }
+
+    // a record over the chunk limit is rejected
+    let columns: Vec<Column> = (0..1024).map(|i| Column::secret(&format!("x{i}"))).collect();
+    let error = DatasetWriter::new(Vec::new(), &server_key, &columns).err().unwrap();
+    let record_bytes = 1024 * server_key.encrypt(0).to_bytes().len();
+    assert_eq!(
+        format!("{error}"),
+        format!("a record takes {record_bytes} bytes, over the chunk limit of {MAX_CHUNK_BYTES}")
+    );
+}
+
+#[test]
+fn test_read_dataset_errors() {
+    let server_key = default_key();
+
+    let error = DatasetReader::new(&b"FHEX\x01"[..]).err().unwrap();
+    assert_eq!(format!("{error}"), "not an encrypted dataset");
+
+    let mut writer = DatasetWriter::new(Vec::new(), &server_key, &[Column::secret("x")]).unwrap();
+    writer.write_records(&[vec![1], vec![2]]).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    // truncated chunk
+    let mut reader = DatasetReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(
+        format!("{}", reader.next_chunk().unwrap_err()),
+        "unexpected end of dataset section"
+    );
+
+    // chunk larger than the configured limit
+    let limits = ReadLimits::default().with_max_chunk_bytes(64);
+    let mut reader = DatasetReader::with_limits(bytes.as_slice(), limits).unwrap();
+    assert!(reader.next_chunk().is_err());
+
+    // records must match the schema
+    let mut writer = DatasetWriter::new(Vec::new(), &server_key, &[Column::secret("x")]).unwrap();
+    assert_eq!(
+        format!("{}", writer.write_records(&[vec![1, 2]]).unwrap_err()),
+        "record 0 has 2 values, but the schema has 1 columns"
+    );
+
+    // records of an empty schema take no bytes
+    let error = DatasetWriter::new(Vec::new(), &server_key, &[]).err().unwrap();
+    assert_eq!(format!("{error}"), "schema must have at least one column");
+
+    let header = DatasetHeader::new(server_key.parameters(), &[]).to_bytes();
+    let mut bytes = b"FHED\x01".to_vec();
+    bytes.extend((header.len() as u64).to_le_bytes());
+    bytes.extend(header);
+
+    let error = DatasetReader::new(bytes.as_slice()).err().unwrap();
+    assert_eq!(format!("{error}"), "schema must have at least one column");
+}
+
 fn default_key() -> ServerKey {
     let plaintext_modulus: u32 = 8u32;
     let ciphertext_modulus: u32 = 128u32;
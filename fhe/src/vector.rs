@@ -0,0 +1,144 @@
+use winterfell::math::{fields::f128::BaseElement, FieldElement};
+
+use super::integer::FheElement;
+use super::Error;
+
+pub type FheVecUInt8 = FheVec<BaseElement>;
+
+// ciphertexts stored back to back, each one taking lwe_size elements
+#[derive(Clone, PartialEq)]
+pub struct FheVec<E>
+where
+    E: FieldElement,
+{
+    lwe_size: usize,
+    data: Vec<E>,
+}
+
+impl<E: FieldElement> FheVec<E> {
+    pub fn new(lwe_size: usize) -> Result<FheVec<E>, Error> {
+        FheVec::with_capacity(lwe_size, 0)
+    }
+
+    pub fn with_capacity(lwe_size: usize, capacity: usize) -> Result<FheVec<E>, Error> {
+        // len and iter split the data in chunks of lwe_size
+        if lwe_size == 0 {
+            return Err(Error::new(String::from("lwe size must be greater than 0")));
+        }
+
+        Ok(FheVec {
+            lwe_size,
+            data: Vec::with_capacity(lwe_size * capacity),
+        })
+    }
+
+    pub fn from_elements(lwe_size: usize, values: &[FheElement<E>]) -> Result<FheVec<E>, Error> {
+        let mut vector = FheVec::with_capacity(lwe_size, values.len())?;
+        for value in values.iter() {
+            vector.push(value)?;
+        }
+        Ok(vector)
+    }
+
+    pub fn lwe_size(&self) -> usize {
+        self.lwe_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.lwe_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn push(&mut self, value: &FheElement<E>) -> Result<(), Error> {
+        if value.ciphertext().len() != self.lwe_size {
+            return Err(Error::new(format!(
+                "expected a ciphertext of length {}, but was {}",
+                self.lwe_size,
+                value.ciphertext().len()
+            )));
+        }
+        self.data.extend_from_slice(value.ciphertext());
+        Ok(())
+    }
+
+    pub fn ciphertext(&self, index: usize) -> &[E] {
+        &self.data[(index * self.lwe_size)..((index + 1) * self.lwe_size)]
+    }
+
+    pub fn get(&self, index: usize) -> FheElement<E> {
+        FheElement::new(self.ciphertext(index))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[E]> {
+        self.data.chunks_exact(self.lwe_size)
+    }
+
+    pub fn to_elements(&self) -> Vec<FheElement<E>> {
+        self.iter().map(FheElement::new).collect()
+    }
+
+    // self[i] = self[i] + other[i]
+    pub fn add_assign(&mut self, other: &FheVec<E>) -> Result<(), Error> {
+        self.check_shape(other)?;
+        for (value, &other_value) in self.data.iter_mut().zip(other.data.iter()) {
+            *value += other_value;
+        }
+        Ok(())
+    }
+
+    // self[i] = scalars[i] * self[i]
+    pub fn scalar_mul_assign(&mut self, scalars: &[E]) -> Result<(), Error> {
+        self.check_scalars(scalars)?;
+        for (ct, &scalar) in self.data.chunks_exact_mut(self.lwe_size).zip(scalars.iter()) {
+            for value in ct.iter_mut() {
+                *value *= scalar;
+            }
+        }
+        Ok(())
+    }
+
+    // sum(scalars[i] * self[i])
+    pub fn dot(&self, scalars: &[E]) -> Result<FheElement<E>, Error> {
+        self.check_scalars(scalars)?;
+        let mut result = vec![E::ZERO; self.lwe_size];
+        for (ct, &scalar) in self.iter().zip(scalars.iter()) {
+            for (acc, &value) in result.iter_mut().zip(ct.iter()) {
+                *acc += value * scalar;
+            }
+        }
+        Ok(FheElement::new(&result))
+    }
+
+    fn check_shape(&self, other: &FheVec<E>) -> Result<(), Error> {
+        if self.lwe_size != other.lwe_size || self.len() != other.len() {
+            return Err(Error::new(format!(
+                "expected {} ciphertexts of length {}, but was {} of length {}",
+                self.len(),
+                self.lwe_size,
+                other.len(),
+                other.lwe_size
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_scalars(&self, scalars: &[E]) -> Result<(), Error> {
+        if scalars.len() != self.len() {
+            return Err(Error::new(format!(
+                "expected {} scalars, but was {}",
+                self.len(),
+                scalars.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<E: FieldElement> std::fmt::Debug for FheVec<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use fhe::DatasetReader;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(reader) = DatasetReader::new(data) {
+        for record in reader {
+            if record.is_err() {
+                break;
+            }
+        }
+    }
+});
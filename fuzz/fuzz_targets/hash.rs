@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use winterfell::{Deserializable, Serializable};
+
+use crypto::Hash;
+
+fuzz_target!(|data: &[u8]| {
+    // anything accepted must survive a serialization round trip
+    if let Ok(value) = Hash::read_from_bytes(data) {
+        let bytes = Serializable::to_bytes(&value);
+        assert!(Hash::read_from_bytes(&bytes).is_ok());
+    }
+});
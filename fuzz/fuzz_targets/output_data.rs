@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use winterfell::{Deserializable, Serializable};
+
+use linear_regression::OutputData;
+
+fuzz_target!(|data: &[u8]| {
+    // anything accepted must survive a serialization round trip
+    if let Ok(value) = OutputData::read_from_bytes(data) {
+        let bytes = value.to_bytes();
+        assert!(OutputData::read_from_bytes(&bytes).is_ok());
+    }
+});
@@ -1,54 +1,108 @@
-use fhe::ServerKey;
+use fhe::HomomorphicScheme;
 use winterfell::{
     crypto::{hashers::Blake3_256, DefaultRandomCoin},
     math::{fields::f128::BaseElement, FieldElement},
     matrix::ColMatrix,
-    AuxRandElements, DefaultConstraintEvaluator, DefaultTraceLde, ProofOptions, Prover, StarkDomain, TraceInfo,
-    TracePolyTable, TraceTable,
+    AuxRandElements, DefaultConstraintEvaluator, DefaultTraceLde, EvaluationFrame, ProofOptions, Prover, StarkDomain,
+    Trace, TraceInfo, TracePolyTable,
 };
 
-use air::{ProcessorAir, PublicInputs};
+use air::{ProcessorAir, PublicInputs, AUX_TRACE_WIDTH, NUM_AUX_RAND_ELEMENTS};
 
 // We'll use BLAKE3 as the hash function during proof generation.
 type Blake3 = Blake3_256<BaseElement>;
 
+// The execution trace has an auxiliary segment built after the main trace commitment.
+pub struct ExecutionTrace {
+    info: TraceInfo,
+    trace: ColMatrix<BaseElement>,
+}
+
+impl ExecutionTrace {
+    pub fn new(columns: Vec<Vec<BaseElement>>) -> Self {
+        let info = TraceInfo::new_multi_segment(
+            columns.len(),
+            AUX_TRACE_WIDTH,
+            NUM_AUX_RAND_ELEMENTS,
+            columns[0].len(),
+            Vec::new(),
+        );
+
+        ExecutionTrace {
+            info,
+            trace: ColMatrix::new(columns),
+        }
+    }
+}
+
+impl Trace for ExecutionTrace {
+    type BaseField = BaseElement;
+
+    fn info(&self) -> &TraceInfo {
+        &self.info
+    }
+
+    fn main_segment(&self) -> &ColMatrix<BaseElement> {
+        &self.trace
+    }
+
+    fn read_main_frame(&self, row_idx: usize, frame: &mut EvaluationFrame<BaseElement>) {
+        let next_row_idx = (row_idx + 1) % self.info.length();
+        self.trace.read_row_into(row_idx, frame.current_mut());
+        self.trace.read_row_into(next_row_idx, frame.next_mut());
+    }
+}
+
 // Our prover needs to hold STARK protocol parameters which are specified via ProofOptions
 // struct.
-pub struct ExecutionProver<'a> {
+pub struct ExecutionProver<'a, S: HomomorphicScheme> {
     options: ProofOptions,
     program_hash: [BaseElement; 2],
+    policy_digest: [BaseElement; 2],
     stack_outputs: [BaseElement; 16],
-    server_key: &'a ServerKey,
+    ciphertext_output: Vec<BaseElement>,
+    scheme: &'a S,
 }
 
-impl<'a> ExecutionProver<'a> {
+impl<'a, S: HomomorphicScheme> ExecutionProver<'a, S> {
     pub fn new(
         options: ProofOptions,
         program_hash: [BaseElement; 2],
+        policy_digest: [BaseElement; 2],
         stack_outputs: [BaseElement; 16],
-        server_key: &'a ServerKey,
+        scheme: &'a S,
     ) -> Self {
         Self {
             options,
             stack_outputs,
             program_hash,
-            server_key,
+            policy_digest,
+            ciphertext_output: Vec::new(),
+            scheme,
         }
     }
+
+    // fhe chiplet ciphertext of the handle on top of the output stack
+    pub fn with_ciphertext_output(mut self, ciphertext: &[BaseElement]) -> Self {
+        self.ciphertext_output = ciphertext.to_vec();
+        self
+    }
 }
 
-impl Prover for ExecutionProver<'_> {
+impl<S: HomomorphicScheme> Prover for ExecutionProver<'_, S> {
     type BaseField = BaseElement;
-    type Air = ProcessorAir;
-    type Trace = TraceTable<BaseElement>;
+    type Air = ProcessorAir<S>;
+    type Trace = ExecutionTrace;
     type HashFn = Blake3;
     type RandomCoin = DefaultRandomCoin<Blake3>;
     type TraceLde<E: FieldElement<BaseField = BaseElement>> = DefaultTraceLde<E, Blake3>;
     type ConstraintEvaluator<'a, E: FieldElement<BaseField = BaseElement>> =
-        DefaultConstraintEvaluator<'a, ProcessorAir, E>;
+        DefaultConstraintEvaluator<'a, ProcessorAir<S>, E>;
 
-    fn get_pub_inputs(&self, _trace: &Self::Trace) -> PublicInputs {
-        PublicInputs::new(self.program_hash, self.stack_outputs, self.server_key.clone())
+    fn get_pub_inputs(&self, _trace: &Self::Trace) -> PublicInputs<S> {
+        PublicInputs::new(self.program_hash, self.stack_outputs, self.scheme.clone())
+            .with_policy_digest(self.policy_digest)
+            .with_ciphertext_output(&self.ciphertext_output)
     }
 
     // We'll use the default trace low-degree extension.
@@ -64,13 +118,25 @@ impl Prover for ExecutionProver<'_> {
     // We'll use the default constraint evaluator to evaluate AIR constraints.
     fn new_evaluator<'a, E: FieldElement<BaseField = BaseElement>>(
         &self,
-        air: &'a ProcessorAir,
+        air: &'a ProcessorAir<S>,
         aux_rand_elements: Option<AuxRandElements<E>>,
         composition_coefficients: winterfell::ConstraintCompositionCoefficients<E>,
     ) -> Self::ConstraintEvaluator<'a, E> {
         DefaultConstraintEvaluator::new(air, aux_rand_elements, composition_coefficients)
     }
 
+    fn build_aux_trace<E: FieldElement<BaseField = BaseElement>>(
+        &self,
+        main_trace: &Self::Trace,
+        aux_rand_elements: &AuxRandElements<E>,
+    ) -> ColMatrix<E> {
+        air::build_aux_trace(
+            main_trace.main_segment(),
+            self.scheme.ciphertext_width(),
+            aux_rand_elements.rand_elements(),
+        )
+    }
+
     fn options(&self) -> &ProofOptions {
         &self.options
     }
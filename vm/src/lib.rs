@@ -1,31 +1,71 @@
-use winterfell::{math::fields::f128::BaseElement, FieldExtension, Proof, ProofOptions, Prover, TraceTable};
+use winterfell::{math::fields::f128::BaseElement, FieldExtension, Proof, ProofOptions, Prover};
 
-use prover::ExecutionProver;
+use fhe::HomomorphicScheme;
+
+use prover::{ExecutionProver, ExecutionTrace};
 
 mod program;
-pub use program::{Program, ProgramInputs};
+pub use program::{Policy, Program, ProgramInputs};
 
 mod processor;
-use processor::{Processor, ProcessorError};
+use processor::Processor;
+pub use processor::ProcessorError;
 
 use crypto::rescue::Hash;
 
-pub fn prove(program: Program, inputs: ProgramInputs) -> Result<(Hash, [BaseElement; 16], Proof), ProcessorError> {
+pub fn prove<S: HomomorphicScheme>(
+    program: Program,
+    inputs: ProgramInputs<S>,
+) -> Result<(Hash, [BaseElement; 16], Proof), ProcessorError> {
     let processor = Processor::run(&program, &inputs)?;
 
     let output = processor.output();
 
-    let trace = TraceTable::init(processor.trace()?);
+    let proof = prove_trace(&program, &inputs, processor.trace()?, output, &[]);
 
-    let options = ProofOptions::new(32, 8, 0, FieldExtension::None, 8, 127);
+    Ok((program.hash(), output, proof))
+}
 
-    let hash = program.hash();
+// the program leaves a ciphertext handle on top of the stack
+// the fhe chiplet ciphertext of the handle is bound to the proof as a public output
+pub fn prove_ciphertext<S: HomomorphicScheme>(
+    program: Program,
+    inputs: ProgramInputs<S>,
+) -> Result<(Hash, [BaseElement; 16], Vec<BaseElement>, Proof), ProcessorError> {
+    let processor = Processor::run(&program, &inputs)?;
 
-    let prover = ExecutionProver::new(options, hash.to_elements(), output, inputs.server_key());
+    let output = processor.output();
+    let ciphertext = processor.ciphertext_output()?;
 
-    let proof = prover.prove(trace).unwrap();
+    let proof = prove_trace(&program, &inputs, processor.trace()?, output, &ciphertext);
 
-    Ok((hash, output, proof))
+    Ok((program.hash(), output, ciphertext, proof))
+}
+
+fn prove_trace<S: HomomorphicScheme>(
+    program: &Program,
+    inputs: &ProgramInputs<S>,
+    trace: Vec<Vec<BaseElement>>,
+    output: [BaseElement; 16],
+    ciphertext: &[BaseElement],
+) -> Proof {
+    let trace = ExecutionTrace::new(trace);
+
+    let options = ProofOptions::new(32, 8, 0, FieldExtension::None, 8, 127);
+
+    // programs without a policy bind the zero digest
+    let policy_digest = inputs.policy().map(|policy| policy.digest()).unwrap_or_default();
+
+    let prover = ExecutionProver::new(
+        options,
+        program.hash().to_elements(),
+        policy_digest.to_elements(),
+        output,
+        inputs.scheme(),
+    )
+    .with_ciphertext_output(ciphertext);
+
+    prover.prove(trace).unwrap()
 }
 
 #[cfg(test)]
@@ -33,12 +73,13 @@ mod tests {
     use super::*;
 
     use air::{ProcessorAir, PublicInputs};
-    use fhe::{FheUInt8, LweParameters, ServerKey};
-    use std::io::Write;
+    use crypto::{merkle, rescue, MerkleTree};
+    use fhe::{Column, DatasetReader, DatasetWriter, FheElement, FheUInt8, LweParameters, ServerKey};
+    use std::{fs::File, io::BufReader, io::Write};
     use tempfile::NamedTempFile;
     use winterfell::{
         crypto::{hashers::Blake3_256, DefaultRandomCoin},
-        math::fields::f128::BaseElement,
+        math::{fields::f128::BaseElement, FieldElement, StarkField},
         verify, AcceptableOptions,
     };
 
@@ -97,4 +138,1157 @@ mod tests {
         )
         .unwrap()
     }
+
+    #[test]
+    fn test_prove_dataset_records() {
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let tmpfile = NamedTempFile::new().unwrap();
+
+        let columns = [Column::secret("x"), Column::public("w")];
+        let mut writer = DatasetWriter::new(tmpfile.reopen().unwrap(), &server_key, &columns).unwrap();
+        writer.write_records(&[vec![2, 3], vec![1, 5]]).unwrap();
+        writer.finish().unwrap();
+
+        let reader = DatasetReader::new(BufReader::new(File::open(tmpfile.path()).unwrap())).unwrap();
+
+        // records are streamed one chunk at a time
+        for record in reader {
+            let record = record.unwrap();
+
+            let inputs = ProgramInputs::new(record.public(), record.secret(), &server_key);
+
+            let (_, output, _) = prove(Program::compile("read2\nread\nsmul").unwrap(), inputs).unwrap();
+
+            let expected = server_key.decrypt(&record.secret()[0]) * record.public()[0];
+
+            assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..5])), expected);
+        }
+    }
+
+    // cleartext scheme, values are stored in the last element of the ciphertext
+    #[test]
+    fn test_prove_stack_manipulation() {
+        let source = "
+            read2
+            read2
+            swap2
+            dup2
+            add2  # x + x
+            swap2
+            drop2
+            push.1
+            push.3
+            swap
+            dup.1
+            movdn.2
+            mul
+            drop
+            movup.2
+            movdn.2
+            smul  # 3 * (x + x)
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs = [server_key.encrypt(1u8), server_key.encrypt(5u8)];
+
+        let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..5])), 6);
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_scalar_arithmetic() {
+        // (3 - 5) * -(1 / 2) * 2^3
+        let source = "
+            read
+            read
+            sub
+            push.2
+            inv
+            neg
+            mul
+            push.2
+            pow.3
+            mul
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[3, 5], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(output[0], BaseElement::from(8u8));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_field_immediate() {
+        let program = Program::compile("push.0xffffffffffffffffffffffff\npush.1\nadd").unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(output[0], BaseElement::new(1 << 96));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_u32_arithmetic() {
+        // 4300000000 mod 2^32 = 5032704, the flags stay below the results
+        // [5032704 / 7, borrow, 5 - 9 + 2^32, overflow, 3 * 2^31 mod 2^32, 5032704 mod 7, carry]
+        let source = "
+            push.4000000000
+            push.300000000
+            u32add
+            swap
+            push.7
+            u32mod
+            push.2147483648
+            push.3
+            u32mul
+            push.5
+            push.9
+            u32sub
+            push.4000000000
+            push.300000000
+            u32add
+            drop
+            push.7
+            u32div
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        let expected: [u64; 7] = [718957, 1, 4294967292, 1, 2147483648, 5, 1];
+        assert_eq!(output[..7], expected.map(BaseElement::from));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_bitwise() {
+        // a = 0xf0f0a5a5, b = 0x0ff0ff00
+        // [a >> 4, (a << 8) mod 2^32, a xor b, a or b, a and b]
+        let source = "
+            push.0xf0f0a5a5
+            push.0x0ff0ff00
+            and
+            push.0xf0f0a5a5
+            push.0x0ff0ff00
+            or
+            push.0xf0f0a5a5
+            push.0x0ff0ff00
+            xor
+            push.0xf0f0a5a5
+            shl.8
+            push.0xf0f0a5a5
+            shr.4
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        let expected: [u64; 5] = [0x0f0f0a5a, 0xf0a5a500, 0xff005aa5, 0xfff0ffa5, 0x00f0a500];
+        assert_eq!(output[..5], expected.map(BaseElement::from));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_comparisons() {
+        // an age of 42 is checked against the bracket [18, 65)
+        // [42 != 0, 42 = 42, 42 < 65, 42 >= 18, 42 <= 17, 42 > 65]
+        let source = "
+            push.42
+            push.65
+            gt
+            push.42
+            push.17
+            lte
+            push.42
+            push.18
+            gte
+            push.42
+            push.65
+            lt
+            push.42
+            push.42
+            eq
+            push.42
+            push.0
+            neq
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(output[..6], [1u8, 1, 1, 1, 0, 0].map(BaseElement::from));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_hash() {
+        // commits to a secret ciphertext and a public input
+        // [hash(a), hash(x)]
+        let source = "
+            read2
+            hash.5
+            read
+            hash.1
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let x = server_key.encrypt(6u8);
+        let secret_inputs = [x.clone()];
+
+        let inputs = ProgramInputs::new(&[9], &secret_inputs, &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        let digest_a = rescue::hash_elements(&[BaseElement::from(9u8)]);
+        let digest_x = rescue::hash_elements(x.ciphertext());
+
+        assert_eq!(output[..2], digest_a.to_elements());
+        assert_eq!(output[2..4], digest_x.to_elements());
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_mtree_verify() {
+        // the owner commits to a dataset of [zip, age] records
+        let records: [[u8; 2]; 4] = [[10, 31], [12, 45], [17, 28], [10, 62]];
+        let leaves: Vec<Hash> = records
+            .iter()
+            .map(|record| rescue::hash_elements(&record.map(BaseElement::from)))
+            .collect();
+
+        let tree = MerkleTree::new(&leaves);
+        let (root, path) = (tree.root(), tree.path(2));
+
+        assert_eq!(merkle::compute_root(leaves[2], 2, &path), root);
+
+        // the path and the root are pushed below the leaf of the record read from the public inputs
+        let source = |root: Hash, index: usize| {
+            let pushes: String = [root, path[1], path[0]]
+                .iter()
+                .flat_map(|digest| digest.to_elements().into_iter().rev())
+                .map(|element| format!("push.{}\n", element.as_int()))
+                .collect();
+            format!("{pushes}read\nread\nhash.2\npush.{index}\nmtree_verify.2")
+        };
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        // read pushes the last input on top
+        let inputs = [records[2][1], records[2][0]];
+
+        let program = Program::compile(&source(root, 2)).unwrap();
+        let (hash, output, proof) = prove(program, ProgramInputs::new(&inputs, &[], &server_key)).unwrap();
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+            &min_opts,
+        )
+        .unwrap();
+
+        // another leaf index or another root fails the check
+        let program = Program::compile(&source(root, 1)).unwrap();
+        assert!(prove(program, ProgramInputs::new(&inputs, &[], &server_key)).is_err());
+
+        let program = Program::compile(&source(leaves[0], 2)).unwrap();
+        assert!(prove(program, ProgramInputs::new(&inputs, &[], &server_key)).is_err());
+    }
+
+    #[test]
+    fn test_prove_encrypted_constant() {
+        // x + 3
+        let program = Program::compile("read2\npush2.3\nadd2").unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs = [server_key.encrypt(2u8)];
+
+        let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..5])), 5);
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_conditional_selection() {
+        // piecewise model driven by the public features [1, 0]
+        let source = "
+            read2
+            read2
+            read
+            csel2  # y
+            push.3
+            push.1
+            read
+            cswap
+            drop   # 3
+            smul   # 3 * y
+        ";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs = [server_key.encrypt(1u8), server_key.encrypt(2u8)];
+
+        let inputs = ProgramInputs::new(&[1, 0], &secret_inputs, &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..5])), 6);
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_if_else() {
+        // public feature selects the coefficient, 2 * x or 3 * x
+        let source = "
+            read2
+            read
+            if.true
+                push.2
+            else
+                push.3
+            end
+            smul
+        ";
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs = [server_key.encrypt(2u8)];
+
+        for (feature, expected) in [(1u8, 4u8), (0, 6)] {
+            let program = Program::compile(source).unwrap();
+
+            let public_inputs = [feature];
+            let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &server_key);
+
+            let (hash, output, proof) = prove(program, inputs).unwrap();
+
+            assert_eq!(hash, Program::compile(source).unwrap().hash());
+            assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..5])), expected);
+
+            let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+            verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof,
+                PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+                &min_opts,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_prove_nested_if() {
+        // age brackets: 1 when both features are set, 2 when only the first one, 3 otherwise
+        let source = "
+            read
+            read
+            if.true
+                if.true
+                    push.1
+                else
+                    push.2
+                end
+            else
+                drop
+                push.3
+            end
+        ";
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        for (features, expected) in [([1u8, 1], 1u8), ([0, 1], 2), ([1, 0], 3)] {
+            let program = Program::compile(source).unwrap();
+
+            let inputs = ProgramInputs::new(&features, &[], &server_key);
+
+            let (hash, output, proof) = prove(program, inputs).unwrap();
+
+            assert_eq!(output[0], BaseElement::from(expected));
+
+            let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+            verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof,
+                PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+                &min_opts,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_prove_if_invalid_condition() {
+        let program = Program::compile(
+            "read
+if.true
+push.1
+else
+push.2
+end",
+        )
+        .unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[2], &[], &server_key);
+
+        assert!(prove(program, inputs).is_err());
+    }
+
+    #[test]
+    fn test_prove_while() {
+        // doubles the accumulator while the public inputs read 1
+        let source = "
+            push.1
+            read
+            while.true
+                push.2
+                mul
+                read
+            end
+            push.3
+            add
+        ";
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let hash = Program::compile(source).unwrap().hash();
+
+        for (conditions, expected) in [(vec![0u8], 4u8), (vec![1, 0], 5), (vec![1, 1, 1, 0], 11)] {
+            let program = Program::compile(source).unwrap();
+
+            let inputs = ProgramInputs::new(&conditions, &[], &server_key);
+
+            let (program_hash, output, proof) = prove(program, inputs).unwrap();
+
+            assert_eq!(program_hash, hash);
+            assert_eq!(output[0], BaseElement::from(expected));
+
+            let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+            verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof,
+                PublicInputs::new(program_hash.to_elements(), output, server_key.clone()),
+                &min_opts,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_prove_repeat() {
+        let program = Program::compile(
+            "push.1
+repeat.3
+push.2
+mul
+end",
+        )
+        .unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(output[0], BaseElement::from(8u8));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_procedures() {
+        // the scaled feature is computed by a called procedure, the offset is inlined
+        let source = "
+            proc.offset
+                push.3
+                add
+            end
+            proc.scale
+                read
+                if.true
+                    push.2
+                else
+                    push.4
+                end
+                mul
+                exec.offset
+            end
+            push.1
+            call.scale
+            call.scale
+            exec.offset
+        ";
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let hash = Program::compile(source).unwrap().hash();
+
+        for (features, expected) in [([1u8, 1], 16u8), ([0, 1], 20), ([1, 0], 26)] {
+            let program = Program::compile(source).unwrap();
+
+            let inputs = ProgramInputs::new(&features, &[], &server_key);
+
+            let (program_hash, output, proof) = prove(program, inputs).unwrap();
+
+            assert_eq!(program_hash, hash);
+            assert_eq!(output[0], BaseElement::from(expected));
+
+            let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+            verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof,
+                PublicInputs::new(program_hash.to_elements(), output, server_key.clone()),
+                &min_opts,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_prove_stdlib() {
+        let mut cases = Vec::new();
+
+        // the inputs are 1..=n, the weights cycle through 1, 2, 3
+        for n in 2..=8u8 {
+            let secret: Vec<u8> = (1..=n).collect();
+            let weights: Vec<u8> = (0..n).map(|i| i % 3 + 1).collect();
+            let sum = secret.iter().sum::<u8>();
+            let dot = secret.iter().zip(&weights).map(|(x, w)| x * w).sum::<u8>();
+            let scale = (16 + n / 2) / n;
+
+            cases.push((
+                format!("use.std::vector\nexec.vector::sum{n}"),
+                vec![],
+                secret.clone(),
+                sum,
+            ));
+            cases.push((
+                format!("use.std::vector\nexec.vector::dot{n}"),
+                weights,
+                secret.clone(),
+                dot,
+            ));
+            cases.push((
+                format!("use.std::stats\ncall.stats::mean{n}"),
+                vec![scale],
+                secret,
+                sum * scale,
+            ));
+        }
+
+        // the coefficients are 1..=d + 1 from the highest degree, x = 2 is read once
+        for d in 1..=4u8 {
+            let secret: Vec<u8> = (1..=d + 1).collect();
+            let value = secret.iter().fold(0, |acc, c| acc * 2 + c);
+
+            cases.push((format!("use.std::poly\nexec.poly::eval{d}"), vec![2], secret, value));
+        }
+
+        assert_eq!(cases.len(), 25);
+
+        for (source, public_inputs, secret_inputs, expected) in cases {
+            assert_eq!(
+                prove_and_verify(&source, &public_inputs, &secret_inputs),
+                expected,
+                "{source}"
+            );
+        }
+    }
+
+    // proves and verifies the program, returns the decrypted top ciphertext
+    fn prove_and_verify(source: &str, public_inputs: &[u8], secret_inputs: &[u8]) -> u8 {
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs: Vec<FheUInt8> = secret_inputs.iter().map(|&x| server_key.encrypt(x)).collect();
+
+        let program = Program::compile(source).unwrap();
+        let inputs = ProgramInputs::new(public_inputs, &secret_inputs, &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+            &min_opts,
+        )
+        .unwrap();
+
+        server_key.decrypt(&FheUInt8::new(&output[..5]))
+    }
+
+    #[test]
+    fn test_prove_memory() {
+        // stage the inputs in memory and combine them in reverse order
+        let source = "
+            read2
+            mem_store2.0
+            read2
+            mem_store2.1
+            read
+            mem_store.2
+            mem_load2.1
+            mem_load.2
+            smul
+            mem_load2.0
+            add2";
+
+        // 3 * 2 + 1
+        let result = prove_and_verify(source, &[3], &[1, 2]);
+
+        assert_eq!(result, 7);
+    }
+
+    #[test]
+    fn test_prove_assertions() {
+        // the public inputs must be 3, 1 and 0
+        let source = "
+            read
+            push.3
+            assert_eq
+            read
+            assert
+            read
+            assertz
+            read2
+            read2
+            add2";
+
+        let result = prove_and_verify(source, &[3, 1, 0], &[1, 2]);
+
+        assert_eq!(result, 3);
+    }
+
+    #[test]
+    fn test_prove_failed_assertion() {
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        for source in ["read\npush.3\nassert_eq", "read\nassert", "read\nassertz"] {
+            let program = Program::compile(source).unwrap();
+            let inputs = ProgramInputs::new(&[2], &[], &server_key);
+
+            assert!(prove(program, inputs).is_err());
+        }
+    }
+
+    #[test]
+    fn test_prove_stack_overflow() {
+        // four ciphertexts and a condition need 21 elements
+        let source = "
+            read2
+            read2
+            read2
+            read2
+            push.1
+            csel2
+            add2
+            add2";
+
+        // 4 + 2 + 1
+        let result = prove_and_verify(source, &[], &[1, 2, 3, 4]);
+
+        assert_eq!(result, 7);
+    }
+
+    #[test]
+    fn test_prove_deep_scalar_stack() {
+        // sum of 0 to 24
+        let source = "
+            push.0
+            repeat.24
+                dup.0
+                push.1
+                add
+            end
+            repeat.24
+                add
+            end";
+        let program = Program::compile(source).unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        assert_eq!(output[0], BaseElement::from(300u32));
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_memory_in_loop() {
+        // sum the inputs into an accumulator kept in memory
+        let source = "
+            push2.0
+            mem_store2.0
+            push.1
+            while.true
+                mem_load2.0
+                read2
+                add2
+                mem_store2.0
+                read
+            end
+            mem_load2.0";
+
+        let result = prove_and_verify(source, &[1, 1, 0], &[1, 2, 3]);
+
+        assert_eq!(result, 6);
+    }
+
+    #[test]
+    fn test_prove_lwe_dimensions() {
+        // the ciphertext width sets the stack shifts, c ? 2 * x + 1 : 2 * y
+        let source = "
+            read2
+            read2
+            swap2
+            dup2
+            add2   # 2 * x
+            push.1
+            sadd
+            dup2
+            drop2
+            mem_store2.3
+            push.2
+            smul   # 2 * y
+            mem_load2.3
+            read
+            csel2";
+
+        for k in [1, 2] {
+            let parameters = LweParameters::new(8u32, 128u32, k, 2.412_390_240_121_573e-5);
+            let server_key = ServerKey::new(parameters);
+
+            for (condition, expected) in [(1u8, 3u8), (0, 6)] {
+                let secret_inputs = [server_key.encrypt(1u8), server_key.encrypt(3u8)];
+
+                let public_inputs = [condition];
+
+                let program = Program::compile(source).unwrap();
+                let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &server_key);
+
+                let (hash, output, proof) = prove(program, inputs).unwrap();
+
+                assert_eq!(server_key.decrypt(&FheUInt8::new(&output[..k + 1])), expected);
+
+                let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+                verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                    proof,
+                    PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+                    &min_opts,
+                )
+                .unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn test_prove_wide_lwe_dimension() {
+        let parameters = LweParameters::new(8u32, 128u32, 8, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let secret_inputs = [server_key.encrypt(1u8), server_key.encrypt(3u8)];
+
+        // ciphertexts of 9 elements do not fit the stack
+        let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+        assert!(prove(Program::compile("read2\nread2\nadd2").unwrap(), inputs).is_err());
+
+        // 2 * y + x behind handles
+        let program = Program::compile("ct_read\nct_read\npush.2\nct_smul\nct_add").unwrap();
+        let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+
+        let (hash, output, ciphertext, proof) = prove_ciphertext(program, inputs).unwrap();
+
+        assert_eq!(server_key.decrypt(&FheUInt8::new(&ciphertext)), 7);
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key.clone()).with_ciphertext_output(&ciphertext),
+            &min_opts,
+        )
+        .unwrap();
+
+        // (x + y) * 2 with the stack instructions lowered to handles
+        let program = Program::compile_with_handles("read2\nread2\nadd2\nread\nsmul", &[]).unwrap();
+        let inputs = ProgramInputs::new(&[2], &secret_inputs, &server_key);
+
+        let (hash, output, ciphertext, proof) = prove_ciphertext(program, inputs).unwrap();
+
+        assert_eq!(ciphertext.len(), 9);
+        assert_eq!(server_key.decrypt(&FheUInt8::new(&ciphertext)), 8);
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key).with_ciphertext_output(&ciphertext),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_ciphertext_handles() {
+        // 2 * x + 3 * y + (x + y) in the fhe chiplet
+        let source = "
+            ct_read
+            dup.0
+            ct_read
+            dup.0
+            movup.2
+            ct_add   # x + y
+            push.1
+            movup.2
+            push.3
+            movup.4
+            push.2
+            ct_dot.3";
+
+        // the ciphertext width is not bound by the stack
+        for k in [4, 64] {
+            let parameters = LweParameters::new(8u32, 128u32, k, 2.412_390_240_121_573e-5);
+            let server_key = ServerKey::new(parameters);
+
+            let secret_inputs = [server_key.encrypt(1u8), server_key.encrypt(1u8)];
+
+            let program = Program::compile(source).unwrap();
+            let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+
+            let (hash, output, ciphertext, proof) = prove_ciphertext(program, inputs).unwrap();
+
+            assert_eq!(ciphertext.len(), k + 1);
+            assert_eq!(server_key.decrypt(&FheUInt8::new(&ciphertext)), 7);
+
+            let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+            // the proof is bound to the ciphertext of the output handle
+            let mut forged = ciphertext.clone();
+            forged[0] += BaseElement::ONE;
+
+            assert!(verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof.clone(),
+                PublicInputs::new(hash.to_elements(), output, server_key.clone()).with_ciphertext_output(&forged),
+                &min_opts,
+            )
+            .is_err());
+
+            verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+                proof,
+                PublicInputs::new(hash.to_elements(), output, server_key).with_ciphertext_output(&ciphertext),
+                &min_opts,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_prove_infinite_loop() {
+        let program = Program::compile(
+            "push.1
+while.true
+push.1
+end",
+        )
+        .unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let inputs = ProgramInputs::new(&[], &[], &server_key);
+
+        assert!(prove(program, inputs).is_err());
+    }
+
+    #[derive(Clone)]
+    // a mask of two elements and the message, narrower than the lwe ciphertexts of the examples
+    struct PlainScheme;
+
+    const PLAIN_WIDTH: usize = 3;
+
+    impl HomomorphicScheme for PlainScheme {
+        fn ciphertext_width(&self) -> usize {
+            PLAIN_WIDTH
+        }
+
+        fn encrypt_trivial<E: FieldElement + From<BaseElement>>(&self, message: &E) -> FheElement<E> {
+            let mut ciphertext = [E::ZERO; PLAIN_WIDTH];
+            ciphertext[PLAIN_WIDTH - 1] = *message;
+            FheElement::new(&ciphertext)
+        }
+
+        fn add<E: FieldElement + From<BaseElement>>(
+            &self,
+            value0: &FheElement<E>,
+            value1: &FheElement<E>,
+        ) -> FheElement<E> {
+            let ciphertext: Vec<E> = (0..PLAIN_WIDTH)
+                .map(|i| value0.ciphertext()[i] + value1.ciphertext()[i])
+                .collect();
+            FheElement::new(&ciphertext)
+        }
+
+        fn scalar_add<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E> {
+            self.add(value, &self.encrypt_trivial(scalar))
+        }
+
+        fn scalar_mul<E: FieldElement + From<BaseElement>>(&self, scalar: &E, value: &FheElement<E>) -> FheElement<E> {
+            let ciphertext: Vec<E> = value.ciphertext().iter().map(|&v| v * *scalar).collect();
+            FheElement::new(&ciphertext)
+        }
+    }
+
+    #[test]
+    fn test_prove_with_custom_scheme() {
+        let program = Program::compile("read2\nread\nsadd\nread2\nadd2\nread\nsmul").unwrap();
+
+        let scheme = PlainScheme;
+
+        let public_inputs = [3u8, 2];
+        let secret_inputs = [
+            scheme.encrypt_trivial(&BaseElement::from(4u8)),
+            scheme.encrypt_trivial(&BaseElement::from(5u8)),
+        ];
+
+        let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &scheme);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        // ((4 + 3) + 5) * 2
+        assert_eq!(output[PLAIN_WIDTH - 1], BaseElement::from(24u8));
+        assert_eq!(output[PLAIN_WIDTH], BaseElement::ZERO);
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        verify::<ProcessorAir<PlainScheme>, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, scheme),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_with_policy() {
+        let program = Program::compile("read2\nread\nsmul").unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let public_inputs = [3u8];
+        let secret_inputs = [server_key.encrypt(2u8)];
+
+        let policy = Policy::new(&[program.hash()]);
+
+        let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &server_key).with_policy(&policy);
+
+        let (hash, output, proof) = prove(program, inputs).unwrap();
+
+        let min_opts = AcceptableOptions::MinConjecturedSecurity(95);
+
+        // the proof is bound to the policy digest
+        assert!(verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof.clone(),
+            PublicInputs::new(hash.to_elements(), output, server_key.clone()),
+            &min_opts,
+        )
+        .is_err());
+
+        verify::<ProcessorAir, Blake3, DefaultRandomCoin<Blake3>>(
+            proof,
+            PublicInputs::new(hash.to_elements(), output, server_key).with_policy_digest(policy.digest().to_elements()),
+            &min_opts,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prove_program_not_allowed() {
+        let program = Program::compile("read2\nread\nsmul").unwrap();
+        let other = Program::compile("read2\nread\nsadd").unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let public_inputs = [3u8];
+        let secret_inputs = [server_key.encrypt(2u8)];
+
+        let policy = Policy::new(&[other.hash()]);
+
+        let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &server_key).with_policy(&policy);
+
+        let hash = program.hash();
+        let error = prove(program, inputs).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", processor::PolicyError::program_not_allowed(&hash))
+        );
+    }
+
+    #[test]
+    fn test_prove_expired_policy() {
+        let program = Program::compile("read2\nread\nsmul").unwrap();
+
+        let parameters = LweParameters::new(8u32, 128u32, 4, 2.412_390_240_121_573e-5);
+        let server_key = ServerKey::new(parameters);
+
+        let public_inputs = [3u8];
+        let secret_inputs = [server_key.encrypt(2u8)];
+
+        let policy = Policy::new(&[program.hash()]).with_expiry(1);
+
+        let inputs = ProgramInputs::new(&public_inputs, &secret_inputs, &server_key).with_policy(&policy);
+
+        let error = prove(program, inputs).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", processor::PolicyError::expired(1)));
+    }
 }
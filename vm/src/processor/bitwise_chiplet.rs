@@ -0,0 +1,46 @@
+use super::{ONE, ZERO};
+use winterfell::math::fields::f128::BaseElement;
+
+// bits of a byte, least significant first
+const NUM_BITS: usize = 8;
+
+// lookup flag | bits of a | bits of b
+const BITWISE_TRACE_WIDTH: usize = 1 + 2 * NUM_BITS;
+
+// byte lookups of the bitwise operations
+// each lookup gets its own row, the air computes a and b, a xor b from the bits of the two bytes
+pub struct BitwiseChiplet {
+    lookups: Vec<(u8, u8)>,
+}
+
+impl BitwiseChiplet {
+    pub fn new() -> BitwiseChiplet {
+        BitwiseChiplet { lookups: Vec::new() }
+    }
+
+    // the byte pairs come from the u32 helpers of the stack, a bitwise operation looks up its four bytes
+    pub fn check(&mut self, bytes: &[(u8, u8)]) {
+        self.lookups.extend_from_slice(bytes);
+    }
+
+    // lookup rows and the last row
+    pub fn trace_length(&self) -> usize {
+        self.lookups.len() + 1
+    }
+
+    // padding rows are zeros, they answer no lookup
+    pub fn into_trace(self, trace_length: usize) -> Vec<Vec<BaseElement>> {
+        let mut trace = vec![vec![ZERO; trace_length]; BITWISE_TRACE_WIDTH];
+
+        for (row, &(a, b)) in self.lookups.iter().enumerate() {
+            trace[0][row] = ONE;
+
+            for i in 0..NUM_BITS {
+                trace[1 + i][row] = BaseElement::from(a >> i & 1);
+                trace[1 + NUM_BITS + i][row] = BaseElement::from(b >> i & 1);
+            }
+        }
+
+        trace
+    }
+}
@@ -1,15 +1,24 @@
-use super::{errors::ChipletsError, HashOperation, OpCode, Operation, ONE, ZERO};
+use super::{errors::ChipletsError, HashOperation, OpCode, Operation, MAX_TRACE_LENGTH, ONE, ZERO};
 use crypto::{
-    rescue::{CYCLE_LENGTH, STATE_WIDTH},
+    rescue::{self, CYCLE_LENGTH, NUM_ROUNDS, STATE_WIDTH},
     Rescue128,
 };
 use winterfell::math::fields::f128::BaseElement;
 
+// loop entry rate and loop body digest
+pub const LOOP_CTX_WIDTH: usize = 4;
+
+// hperm flag | state | input state
+const HPERM_WIDTH: usize = 1 + 2 * STATE_WIDTH;
+
 pub struct Chiplets {
     clk: usize,
     sponge: Rescue128,
     op_bits_trace: [Vec<BaseElement>; 1],
     sponge_trace: [Vec<BaseElement>; STATE_WIDTH],
+    loop_ctx: [BaseElement; LOOP_CTX_WIDTH],
+    loop_ctx_trace: [Vec<BaseElement>; LOOP_CTX_WIDTH],
+    permutations: Vec<[BaseElement; STATE_WIDTH]>,
     trace_length: usize,
 }
 
@@ -28,17 +37,21 @@ impl Chiplets {
             clk: 0,
             sponge_trace,
             op_bits_trace,
+            loop_ctx: [ZERO; LOOP_CTX_WIDTH],
+            loop_ctx_trace: std::array::from_fn(|_| vec![ZERO; init_trace_length]),
             sponge: Rescue128::new(),
+            permutations: Vec::new(),
             trace_length: init_trace_length,
         }
     }
 
+    // the permutations take a hash cycle each
     pub fn trace_length(&self) -> usize {
-        self.trace_length
+        self.trace_length.max(self.permutations.len() * CYCLE_LENGTH)
     }
 
     pub fn into_trace(mut self, trace_length: usize) -> Result<Vec<Vec<BaseElement>>, ChipletsError> {
-        if self.clk % CYCLE_LENGTH != 0 {
+        if !self.clk.is_multiple_of(CYCLE_LENGTH) {
             return Err(ChipletsError::invalid_trace_length(CYCLE_LENGTH, self.clk, self.clk));
         }
 
@@ -52,6 +65,13 @@ impl Chiplets {
             col.resize(trace_length, ZERO);
         }
 
+        for col in self.loop_ctx_trace.iter_mut() {
+            col.resize(self.clk + 1, ZERO);
+            col.resize(trace_length, col[self.clk]);
+        }
+
+        let permutation_trace = self.permutation_trace(trace_length);
+
         let mut registers: Vec<Vec<BaseElement>> = Vec::new();
 
         let [b0] = self.op_bits_trace;
@@ -63,10 +83,49 @@ impl Chiplets {
         registers.push(h2);
         registers.push(h3);
 
+        registers.extend(self.loop_ctx_trace);
+
+        registers.extend(permutation_trace);
+
         Ok(registers)
     }
 
+    // records the input state of an hperm, the stack pushes the permuted state
+    pub fn permute(&mut self, state: &[BaseElement]) {
+        self.permutations.push(std::array::from_fn(|i| state[i]));
+    }
+
+    // a cycle holds the state after each round with its flag and input state, padding cycles are zeros
+    // the rows after the rounds keep the permuted state
+    fn permutation_trace(&self, trace_length: usize) -> Vec<Vec<BaseElement>> {
+        let mut trace: Vec<Vec<BaseElement>> = (0..HPERM_WIDTH).map(|_| vec![ZERO; trace_length]).collect();
+
+        for (k, input) in self.permutations.iter().enumerate() {
+            let mut state = *input;
+
+            for step in 0..CYCLE_LENGTH {
+                let row = k * CYCLE_LENGTH + step;
+
+                trace[0][row] = ONE;
+                for i in 0..STATE_WIDTH {
+                    trace[1 + i][row] = state[i];
+                    trace[1 + STATE_WIDTH + i][row] = input[i];
+                }
+
+                if step < NUM_ROUNDS {
+                    rescue::apply_round(&mut state, 0, ZERO, step);
+                }
+            }
+        }
+
+        trace
+    }
+
     pub fn hash_op(&mut self, op: &Operation) -> Result<(), ChipletsError> {
+        if self.clk + 1 >= MAX_TRACE_LENGTH {
+            return Err(ChipletsError::trace_length_exceeded(MAX_TRACE_LENGTH, self.clk));
+        }
+
         self.advance_clock();
         self.ensure_trace_capacity();
         self.apply_hacc_round(op)?;
@@ -86,16 +145,52 @@ impl Chiplets {
             for col in self.op_bits_trace.iter_mut() {
                 col.resize(self.trace_length, ZERO);
             }
+            for col in self.loop_ctx_trace.iter_mut() {
+                col.resize(self.trace_length, ZERO);
+            }
         }
     }
 
     fn apply_hacc_round(&mut self, op: &Operation) -> Result<(), ChipletsError> {
-        if !self.sponge.is_apply_round() && op.op_code() != OpCode::Noop {
-            return Err(ChipletsError::invalid_operation(op, self.clk));
+        let state = self.sponge.state();
+
+        // branch ends, loops and calls update the sponge state on the copy steps, other operations
+        // are absorbed on the round steps
+        match op.op_code() {
+            OpCode::Tend | OpCode::Fend | OpCode::Loop | OpCode::Call | OpCode::Repeat | OpCode::Wend
+                if self.sponge.is_apply_round() =>
+            {
+                return Err(ChipletsError::invalid_operation(op, self.clk));
+            }
+            OpCode::Tend => self.sponge.merge_right(op.value()),
+            OpCode::Fend => self.sponge.merge_left(op.value()),
+            OpCode::Loop => {
+                // the entry rate is kept, the body digest is shifted in
+                self.loop_ctx = [state[0], state[1], self.loop_ctx[3], op.value()];
+                self.sponge.update(op.code(), op.value());
+            }
+            OpCode::Call => {
+                // same as loop, the procedure starts from the initial state
+                self.loop_ctx = [state[0], state[1], self.loop_ctx[3], op.value()];
+                self.sponge.clear();
+            }
+            OpCode::Repeat => {
+                if [state[0], state[1]] != [self.loop_ctx[2], self.loop_ctx[3]] {
+                    return Err(ChipletsError::invalid_loop_body(self.clk));
+                }
+                self.sponge.restore([self.loop_ctx[0], self.loop_ctx[1]]);
+            }
+            OpCode::Wend => {
+                self.sponge.merge_capacity([self.loop_ctx[2], self.loop_ctx[3]]);
+                self.loop_ctx = [ZERO; LOOP_CTX_WIDTH];
+            }
+            OpCode::Noop => self.sponge.update(op.code(), op.value()),
+            _ if !self.sponge.is_apply_round() => {
+                return Err(ChipletsError::invalid_operation(op, self.clk));
+            }
+            _ => self.sponge.update(op.code(), op.value()),
         }
 
-        self.sponge.update(op.code(), op.value());
-
         let hash_op = HashOperation::round();
 
         self.op_bits_trace[0][self.clk - 1] = match hash_op.code() & 1 {
@@ -108,6 +203,10 @@ impl Chiplets {
             self.sponge_trace[col][self.clk] = *state;
         }
 
+        for (col, value) in self.loop_ctx.iter().enumerate() {
+            self.loop_ctx_trace[col][self.clk] = *value;
+        }
+
         Ok(())
     }
 }
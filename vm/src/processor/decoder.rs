@@ -1,25 +1,24 @@
 use super::{Operation, ONE, ZERO};
 use winterfell::math::fields::f128::BaseElement;
 
+pub const NUM_OP_BITS: usize = 7;
+
 pub struct Decoder {
     clk: usize,
-    op_bits_trace: [Vec<BaseElement>; 5],
+    op_bits_trace: [Vec<BaseElement>; NUM_OP_BITS],
+    // the operation value absorbed by the program hash, 0 for operations without a value
+    imm_trace: Vec<BaseElement>,
     trace_length: usize,
 }
 
 impl Decoder {
     pub fn new(init_trace_length: usize) -> Decoder {
-        let op_bits_trace = [
-            vec![ZERO; init_trace_length],
-            vec![ZERO; init_trace_length],
-            vec![ZERO; init_trace_length],
-            vec![ZERO; init_trace_length],
-            vec![ZERO; init_trace_length],
-        ];
+        let op_bits_trace = std::array::from_fn(|_| vec![ZERO; init_trace_length]);
 
         Decoder {
             clk: 0,
             op_bits_trace,
+            imm_trace: vec![ZERO; init_trace_length],
             trace_length: init_trace_length,
         }
     }
@@ -34,14 +33,13 @@ impl Decoder {
             col.resize(trace_length, ZERO);
         }
 
+        self.imm_trace.resize(self.clk + 1, ZERO);
+        self.imm_trace.resize(trace_length, ZERO);
+
         let mut registers: Vec<Vec<BaseElement>> = Vec::new();
 
-        let [b0, b1, b2, b3, b4] = self.op_bits_trace;
-        registers.push(b0);
-        registers.push(b1);
-        registers.push(b2);
-        registers.push(b3);
-        registers.push(b4);
+        registers.extend(self.op_bits_trace);
+        registers.push(self.imm_trace);
 
         registers
     }
@@ -50,6 +48,7 @@ impl Decoder {
         self.advance_clock();
         self.ensure_trace_capacity();
         self.decode_op_bits(op);
+        self.imm_trace[self.clk - 1] = op.value();
     }
 
     fn advance_clock(&mut self) {
@@ -62,11 +61,12 @@ impl Decoder {
             for col in self.op_bits_trace.iter_mut() {
                 col.resize(self.trace_length, ZERO);
             }
+            self.imm_trace.resize(self.trace_length, ZERO);
         }
     }
 
     fn decode_op_bits(&mut self, op: &Operation) {
-        for i in 0..5 {
+        for i in 0..NUM_OP_BITS {
             self.op_bits_trace[i][self.clk - 1] = match op.code() >> i & 1 {
                 0 => ZERO,
                 1 => ONE,
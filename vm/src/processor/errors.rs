@@ -1,6 +1,9 @@
 use std::error::Error;
 
-use super::Operation;
+use crypto::Hash;
+use winterfell::math::fields::f128::BaseElement;
+
+use super::{Operation, MAX_STACK_DEPTH};
 
 #[derive(Debug)]
 pub struct StackError {
@@ -20,7 +23,35 @@ impl StackError {
 
     pub fn stack_overflow(op: &Operation, step: usize) -> StackError {
         StackError {
-            message: format!("{op} operation stack overflow"),
+            message: format!("{op} operation stack overflow, the stack holds at most {MAX_STACK_DEPTH} elements"),
+            step,
+        }
+    }
+
+    pub fn division_by_zero(op: &Operation, step: usize) -> StackError {
+        StackError {
+            message: format!("{op} operation division by zero"),
+            step,
+        }
+    }
+
+    pub fn invalid_condition(op: &Operation, step: usize) -> StackError {
+        StackError {
+            message: format!("{op} operation condition must be 0 or 1"),
+            step,
+        }
+    }
+
+    pub fn assertion_failed(op: &Operation, expected: BaseElement, value: BaseElement, step: usize) -> StackError {
+        StackError {
+            message: format!("{op} operation expected {expected} but was {value}"),
+            step,
+        }
+    }
+
+    pub fn not_u32(op: &Operation, step: usize) -> StackError {
+        StackError {
+            message: format!("{op} operation operands must be u32 values"),
             step,
         }
     }
@@ -31,6 +62,15 @@ impl StackError {
             step,
         }
     }
+
+    pub fn ciphertext_width(op: &Operation, width: usize, step: usize) -> StackError {
+        StackError {
+            message: format!(
+                "{op} operation does not fit ciphertexts of width {width} on the stack, compile the program with handles"
+            ),
+            step,
+        }
+    }
 }
 
 impl std::fmt::Display for StackError {
@@ -55,12 +95,40 @@ impl ChipletsError {
         }
     }
 
+    pub fn invalid_loop_body(step: usize) -> ChipletsError {
+        ChipletsError {
+            message: String::from("loop body digest does not match the loop entry"),
+            step,
+        }
+    }
+
+    pub fn trace_length_exceeded(max: usize, step: usize) -> ChipletsError {
+        ChipletsError {
+            message: format!("execution exceeded the maximum trace length of {max}"),
+            step,
+        }
+    }
+
     pub fn invalid_trace_length(expected: usize, current: usize, step: usize) -> ChipletsError {
         ChipletsError {
             message: format!("trace length should be a multiple of {expected}, but was {current}"),
             step,
         }
     }
+
+    pub fn invalid_handle(op: &Operation, step: usize) -> ChipletsError {
+        ChipletsError {
+            message: format!("{op} operand is not a ciphertext handle"),
+            step,
+        }
+    }
+
+    pub fn invalid_output_handle(step: usize) -> ChipletsError {
+        ChipletsError {
+            message: String::from("top of the stack is not a ciphertext handle"),
+            step,
+        }
+    }
 }
 
 impl std::fmt::Display for ChipletsError {
@@ -69,10 +137,43 @@ impl std::fmt::Display for ChipletsError {
     }
 }
 
+#[derive(Debug)]
+pub struct PolicyError {
+    message: String,
+}
+
+impl Error for PolicyError {}
+
+impl PolicyError {
+    pub fn program_not_allowed(program_hash: &Hash) -> PolicyError {
+        let hex: String = program_hash
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        PolicyError {
+            message: format!("program {hex} is not allowed by the input policy"),
+        }
+    }
+
+    pub fn expired(expiry: u64) -> PolicyError {
+        PolicyError {
+            message: format!("input policy expired at {expiry}"),
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "policy error: {}", self.message)
+    }
+}
+
 #[derive(Debug)]
 pub enum ProcessorError {
     Stack(StackError),
     Chiplets(ChipletsError),
+    Policy(PolicyError),
 }
 
 impl std::fmt::Display for ProcessorError {
@@ -80,6 +181,7 @@ impl std::fmt::Display for ProcessorError {
         match self {
             ProcessorError::Stack(e) => write!(f, "{}", e),
             ProcessorError::Chiplets(e) => write!(f, "{}", e),
+            ProcessorError::Policy(e) => write!(f, "{}", e),
         }
     }
 }
@@ -0,0 +1,206 @@
+use super::errors::ChipletsError;
+use super::{OpCode, Operation, ONE, ZERO};
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+// ciphertext elements live in the memory table above the overflow table
+// element i of handle h is at FHE_ADDR + h * w + i
+const FHE_ADDR: u128 = 1 << 17;
+
+// active flag | mac flag | use b flag | element index | handle | handle a | handle b | scalar | a | b | c
+const FHE_TRACE_WIDTH: usize = 11;
+
+// a pass computes the ciphertext of its handle, the handle is the pass index
+// mac passes compute c = use_b * b + scalar * a element-wise, read passes take an input
+struct Pass {
+    read: bool,
+    use_b: bool,
+    operands: [usize; 2],
+    scalar: BaseElement,
+}
+
+pub struct FheChiplet {
+    ct_width: usize,
+    ciphertexts: Vec<Vec<BaseElement>>,
+    passes: Vec<Pass>,
+    accesses: Vec<(u128, usize, bool, BaseElement)>,
+}
+
+impl FheChiplet {
+    pub fn new(ct_width: usize) -> FheChiplet {
+        FheChiplet {
+            ct_width,
+            ciphertexts: Vec::new(),
+            passes: Vec::new(),
+            accesses: Vec::new(),
+        }
+    }
+
+    // handle of the next pass
+    pub fn next_handle(&self) -> BaseElement {
+        BaseElement::from(self.passes.len() as u64)
+    }
+
+    // the input becomes the ciphertext of the next handle
+    pub fn read(&mut self, ciphertext: &[BaseElement]) {
+        self.accesses.clear();
+
+        let pass = Pass {
+            read: true,
+            use_b: false,
+            operands: [0, 0],
+            scalar: ZERO,
+        };
+
+        self.push_pass(pass, ciphertext.to_vec());
+    }
+
+    // ct_add adds s0 to s1, ct_smul multiplies s1 by s0 and ct_mac adds the product to s2
+    // the stack state is the one before the operation
+    pub fn execute(&mut self, op: &Operation, stack: &[BaseElement], step: usize) -> Result<(), ChipletsError> {
+        self.accesses.clear();
+
+        let (use_b, operands, scalar) = match op.op_code() {
+            OpCode::CtAdd => (true, [stack[0], stack[1]], ONE),
+            OpCode::CtSMul => (false, [stack[1], ZERO], stack[0]),
+            OpCode::CtMac => (true, [stack[1], stack[2]], stack[0]),
+            _ => unreachable!(),
+        };
+
+        let handle_a = self
+            .handle(operands[0])
+            .ok_or_else(|| ChipletsError::invalid_handle(op, step))?;
+        let handle_b = match use_b {
+            true => self
+                .handle(operands[1])
+                .ok_or_else(|| ChipletsError::invalid_handle(op, step))?,
+            false => 0,
+        };
+
+        // the pass reads a at 3h and b at 3h + 1
+        let time = 3 * self.passes.len();
+
+        let mut ciphertext = Vec::with_capacity(self.ct_width);
+
+        for i in 0..self.ct_width {
+            let a = self.ciphertexts[handle_a][i];
+            self.accesses.push((self.addr(handle_a, i), time, false, a));
+
+            let b = match use_b {
+                true => self.ciphertexts[handle_b][i],
+                false => ZERO,
+            };
+            if use_b {
+                self.accesses.push((self.addr(handle_b, i), time + 1, false, b));
+            }
+
+            ciphertext.push(b + scalar * a);
+        }
+
+        let pass = Pass {
+            read: false,
+            use_b,
+            operands: [handle_a, handle_b],
+            scalar,
+        };
+
+        self.push_pass(pass, ciphertext);
+
+        Ok(())
+    }
+
+    // ciphertext of a handle, None when no pass computed it
+    pub fn ciphertext(&self, handle: BaseElement) -> Option<&[BaseElement]> {
+        self.handle(handle).map(|handle| self.ciphertexts[handle].as_slice())
+    }
+
+    // memory table accesses of the last pass, (addr, clk, write, value)
+    pub fn accesses(&self) -> &[(u128, usize, bool, BaseElement)] {
+        &self.accesses
+    }
+
+    // rows of the passes, at least one pass long
+    pub fn trace_length(&self) -> usize {
+        self.passes.len().max(1) * self.cycle_length() + 1
+    }
+
+    pub fn into_trace(self, trace_length: usize) -> Vec<Vec<BaseElement>> {
+        let mut trace = vec![vec![ZERO; trace_length]; FHE_TRACE_WIDTH];
+
+        for row in 0..trace_length {
+            for (column, value) in trace.iter_mut().zip(self.trace_row(row)) {
+                column[row] = value;
+            }
+        }
+
+        trace
+    }
+
+    // padding passes past the executed ones keep taking handles with all flags off
+    fn trace_row(&self, row: usize) -> [BaseElement; FHE_TRACE_WIDTH] {
+        let cycle = self.cycle_length();
+        let (handle, index) = (row / cycle, row % cycle);
+
+        let mut values = [ZERO; FHE_TRACE_WIDTH];
+
+        values[3] = BaseElement::from(index as u64);
+        values[4] = BaseElement::from(handle as u64);
+
+        let pass = match self.passes.get(handle) {
+            Some(pass) => pass,
+            None => return values,
+        };
+
+        values[0] = ONE;
+        values[1] = to_flag(!pass.read);
+        values[2] = to_flag(pass.use_b);
+        values[5] = BaseElement::from(pass.operands[0] as u64);
+        values[6] = BaseElement::from(pass.operands[1] as u64);
+        values[7] = pass.scalar;
+
+        if index < self.ct_width {
+            if !pass.read {
+                values[8] = self.ciphertexts[pass.operands[0]][index];
+            }
+            if pass.use_b {
+                values[9] = self.ciphertexts[pass.operands[1]][index];
+            }
+            values[10] = self.ciphertexts[handle][index];
+        }
+
+        values
+    }
+
+    // the pass writes its ciphertext at 3h + 2, after the reads of any earlier pass
+    fn push_pass(&mut self, pass: Pass, ciphertext: Vec<BaseElement>) {
+        let handle = self.passes.len();
+
+        for (i, &value) in ciphertext.iter().enumerate() {
+            self.accesses.push((self.addr(handle, i), 3 * handle + 2, true, value));
+        }
+
+        self.passes.push(pass);
+        self.ciphertexts.push(ciphertext);
+    }
+
+    fn handle(&self, value: BaseElement) -> Option<usize> {
+        usize::try_from(value.as_int())
+            .ok()
+            .filter(|&handle| handle < self.passes.len())
+    }
+
+    fn addr(&self, handle: usize, index: usize) -> u128 {
+        FHE_ADDR + (handle * self.ct_width + index) as u128
+    }
+
+    // rows of a pass, a power of two above the ciphertext width
+    fn cycle_length(&self) -> usize {
+        (self.ct_width + 1).next_power_of_two()
+    }
+}
+
+fn to_flag(value: bool) -> BaseElement {
+    match value {
+        true => ONE,
+        false => ZERO,
+    }
+}
@@ -0,0 +1,156 @@
+use super::{OpCode, Operation, ONE, ZERO};
+use std::collections::HashMap;
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+// a word holds a scalar in its first element or a ciphertext
+pub const MEM_WORD_WIDTH: usize = 16;
+
+// the address or clock delta between sorted accesses in base 4 limbs
+const NUM_DELTA_LIMBS: usize = 9;
+
+// addr | clk | write flag | element flag | access flag | new address flag | delta limbs | word
+const MEM_TRACE_WIDTH: usize = 6 + NUM_DELTA_LIMBS + MEM_WORD_WIDTH;
+
+type Word = [BaseElement; MEM_WORD_WIDTH];
+
+struct MemoryAccess {
+    addr: u128,
+    clk: usize,
+    write: bool,
+    element: bool,
+    word: Word,
+}
+
+pub struct Memory {
+    ct_width: usize,
+    words: HashMap<u128, Word>,
+    accesses: Vec<MemoryAccess>,
+}
+
+impl Memory {
+    pub fn new(ct_width: usize) -> Memory {
+        Memory {
+            ct_width,
+            words: HashMap::new(),
+            accesses: Vec::new(),
+        }
+    }
+
+    // the word at the op address, words never written are 0
+    // scalar loads read the first element, ciphertext loads the ciphertext width
+    pub fn read(&mut self, op: &Operation, clk: usize) -> Vec<BaseElement> {
+        let addr = op.value().as_int();
+        let word = self.words.get(&addr).copied().unwrap_or([ZERO; MEM_WORD_WIDTH]);
+        let element = op.op_code() == OpCode::MemLoad;
+
+        self.accesses.push(MemoryAccess {
+            addr,
+            clk,
+            write: false,
+            element,
+            word,
+        });
+
+        match element {
+            true => vec![word[0]],
+            false => word[..self.ct_width].to_vec(),
+        }
+    }
+
+    // scalar stores write [s0, 0, ..], ciphertext stores the top ciphertext
+    pub fn write(&mut self, op: &Operation, clk: usize, stack: &[BaseElement]) {
+        let addr = op.value().as_int();
+        let element = op.op_code() == OpCode::MemStore;
+        let width = if element { 1 } else { self.ct_width };
+
+        let mut word = [ZERO; MEM_WORD_WIDTH];
+        word[..width].copy_from_slice(&stack[..width]);
+
+        self.words.insert(addr, word);
+
+        self.accesses.push(MemoryAccess {
+            addr,
+            clk,
+            write: true,
+            element,
+            word,
+        });
+    }
+
+    // overflow table accesses of the stack move a single element
+    pub fn access_element(&mut self, addr: u128, clk: usize, write: bool, value: BaseElement) {
+        let mut word = [ZERO; MEM_WORD_WIDTH];
+        word[0] = value;
+
+        if write {
+            self.words.insert(addr, word);
+        }
+
+        self.accesses.push(MemoryAccess {
+            addr,
+            clk,
+            write,
+            element: true,
+            word,
+        });
+    }
+
+    // rows of the memory table
+    pub fn trace_length(&self) -> usize {
+        self.accesses.len() + 1
+    }
+
+    // the first row is a zero read, the accesses sorted by address then clock follow
+    // padding rows keep the last word with an increasing clock
+    pub fn into_trace(mut self, trace_length: usize) -> Vec<Vec<BaseElement>> {
+        self.accesses.sort_by_key(|access| (access.addr, access.clk));
+
+        let mut trace = vec![vec![ZERO; trace_length]; MEM_TRACE_WIDTH];
+
+        let (mut addr, mut clk, mut word) = (0, 0, [ZERO; MEM_WORD_WIDTH]);
+
+        for row in 1..trace_length {
+            let (next_addr, next_clk) = match self.accesses.get(row - 1) {
+                Some(access) => (access.addr, access.clk),
+                None => (addr, clk + 1),
+            };
+
+            let new_addr = next_addr != addr;
+
+            let delta = match new_addr {
+                true => next_addr - addr - 1,
+                false => (next_clk - clk - 1) as u128,
+            };
+
+            for i in 0..NUM_DELTA_LIMBS {
+                trace[6 + i][row - 1] = BaseElement::from((delta >> (2 * i) & 0b11) as u8);
+            }
+
+            if let Some(access) = self.accesses.get(row - 1) {
+                trace[2][row] = to_flag(access.write);
+                trace[3][row] = to_flag(access.element);
+                trace[4][row] = ONE;
+                word = access.word;
+            }
+
+            (addr, clk) = (next_addr, next_clk);
+
+            trace[0][row] = BaseElement::new(addr);
+            trace[1][row] = BaseElement::new(clk as u128);
+            trace[5][row] = to_flag(new_addr);
+
+            for (i, value) in word.iter().enumerate() {
+                trace[6 + NUM_DELTA_LIMBS + i][row] = *value;
+            }
+        }
+
+        trace
+    }
+}
+
+fn to_flag(value: bool) -> BaseElement {
+    match value {
+        true => ONE,
+        false => ZERO,
+    }
+}
@@ -1,6 +1,9 @@
-use crate::program::{Program, ProgramInputs};
+use crate::program::{CodeBlock, Program, ProgramInputs};
+
+use fhe::HomomorphicScheme;
 
 pub mod opcodes;
+use opcodes::STACK_CT_OPS;
 pub use opcodes::{HashOperation, OpCode, Operation};
 
 mod stack;
@@ -15,9 +18,22 @@ use system::System;
 mod chiplets;
 use chiplets::Chiplets;
 
+mod memory;
+use memory::Memory;
+
+mod fhe_chiplet;
+use fhe_chiplet::FheChiplet;
+
+mod range_chiplet;
+use range_chiplet::RangeChiplet;
+
+mod bitwise_chiplet;
+use bitwise_chiplet::BitwiseChiplet;
+
 mod errors;
+use errors::{ChipletsError, StackError};
 
-pub use errors::ProcessorError;
+pub use errors::{PolicyError, ProcessorError};
 
 use rand::Rng;
 
@@ -33,7 +49,20 @@ const ONE: BaseElement = BaseElement::ONE;
 // winterfell trace length must be at least 8 and multiple of 2
 // rescue-prime hash sponge requries at least 16 rounds
 const MIN_TRACE_LENGTH: usize = 16;
-const MAX_STACK_DEPTH: usize = 16;
+
+// the registers hold the top of the stack, deeper elements move to the overflow table
+pub const STACK_WIDTH: usize = 16;
+
+// a ciphertext on the stack has to fit the overflow slots of a single shift
+// wider ciphertexts only live in the fhe chiplet behind handles
+pub const MAX_STACK_CT_WIDTH: usize = 7;
+
+// overflow elements stay below the fhe chiplet elements of the memory table
+// the overflow addresses reach OVERFLOW_ADDR + 7 + the depth, below FHE_ADDR = 2 * OVERFLOW_ADDR
+pub const MAX_STACK_DEPTH: usize = (1 << 16) - 16;
+
+// bounds the execution of while loops
+pub const MAX_TRACE_LENGTH: usize = 1 << 16;
 
 // overwrite last trace row with random values
 // winterfell uses trace.length() - 1 to compute the column degree
@@ -41,37 +70,77 @@ const MAX_STACK_DEPTH: usize = 16;
 // add a random value to the last row and allow 2 transition exemptions
 const NUM_RAND_ROWS: usize = 1;
 
-pub struct Processor<'a> {
-    stack: Stack<'a>,
+pub struct Processor<'a, S: HomomorphicScheme> {
+    stack: Stack<'a, S>,
     decoder: Decoder,
     system: System,
     chiplets: Chiplets,
+    memory: Memory,
+    fhe: FheChiplet,
+    range: RangeChiplet,
+    bitwise: BitwiseChiplet,
 }
 
-impl<'a> Processor<'a> {
-    fn new(inputs: &'a ProgramInputs) -> Self {
+impl<'a, S: HomomorphicScheme> Processor<'a, S> {
+    fn new(inputs: &'a ProgramInputs<S>) -> Self {
         Processor {
             stack: Stack::new(inputs, MIN_TRACE_LENGTH),
             decoder: Decoder::new(MIN_TRACE_LENGTH),
             system: System::new(MIN_TRACE_LENGTH),
             chiplets: Chiplets::new(MIN_TRACE_LENGTH),
+            memory: Memory::new(inputs.scheme().ciphertext_width()),
+            fhe: FheChiplet::new(inputs.scheme().ciphertext_width()),
+            range: RangeChiplet::new(),
+            bitwise: BitwiseChiplet::new(),
         }
     }
 
-    pub fn run(program: &Program, inputs: &'a ProgramInputs) -> Result<Self, ProcessorError> {
-        let mut processor = Processor::new(inputs);
+    pub fn run(program: &Program, inputs: &'a ProgramInputs<S>) -> Result<Self, ProcessorError> {
+        if let Some(policy) = inputs.policy() {
+            if !policy.allows(&program.hash()) {
+                return Err(ProcessorError::Policy(PolicyError::program_not_allowed(
+                    &program.hash(),
+                )));
+            }
+            if policy.is_expired_now() {
+                return Err(ProcessorError::Policy(PolicyError::expired(policy.expiry().unwrap())));
+            }
+        }
 
-        for op in program.code().iter() {
-            processor.execute_op(op)?;
+        // ciphertexts wider than the overflow slots only live in the fhe chiplet, the program is rejected
+        // before it runs at the position of its first stack ciphertext operation in program order
+        let ct_width = inputs.scheme().ciphertext_width();
+        if ct_width > MAX_STACK_CT_WIDTH {
+            let code = program.code();
+            if let Some(pos) = code.iter().position(|op| STACK_CT_OPS.contains(&op.op_code())) {
+                return Err(ProcessorError::Stack(StackError::ciphertext_width(
+                    &code[pos],
+                    ct_width,
+                    pos + 1,
+                )));
+            }
         }
 
+        let mut processor = Processor::new(inputs);
+
+        processor.execute_blocks(program.blocks())?;
+
         Ok(processor)
     }
 
     pub fn trace(self) -> Result<Vec<Vec<BaseElement>>, ProcessorError> {
         let mut trace = Vec::new();
 
-        let trace_length = (self.chiplets.trace_length() + NUM_RAND_ROWS).next_power_of_two();
+        // overflow slots can add several memory table rows per step
+        let trace_length = (self
+            .chiplets
+            .trace_length()
+            .max(self.memory.trace_length())
+            .max(self.fhe.trace_length())
+            .max(self.range.trace_length())
+            .max(self.bitwise.trace_length())
+            + NUM_RAND_ROWS)
+            .next_power_of_two();
 
         trace.extend(self.system.into_trace(trace_length));
         trace.extend(self.decoder.into_trace(trace_length));
@@ -81,6 +150,14 @@ impl<'a> Processor<'a> {
             Err(err) => return Err(ProcessorError::Chiplets(err)),
         };
 
+        trace.extend(self.memory.into_trace(trace_length));
+
+        trace.extend(self.fhe.into_trace(trace_length));
+
+        trace.extend(self.range.into_trace(trace_length));
+
+        trace.extend(self.bitwise.into_trace(trace_length));
+
         trace.extend(self.stack.into_trace(trace_length));
 
         let mut rng = rand::thread_rng();
@@ -94,19 +171,131 @@ impl<'a> Processor<'a> {
         Ok(trace)
     }
 
-    pub fn output(&self) -> [BaseElement; MAX_STACK_DEPTH] {
+    pub fn output(&self) -> [BaseElement; STACK_WIDTH] {
         // trace computation does not change the clock value
         // clock value is always set to the last stack row
         self.stack.current_stack_state()
     }
 
+    // the ciphertext of the handle on top of the stack
+    pub fn ciphertext_output(&self) -> Result<Vec<BaseElement>, ProcessorError> {
+        match self.fhe.ciphertext(self.output()[0]) {
+            Some(ciphertext) => Ok(ciphertext.to_vec()),
+            None => Err(ProcessorError::Chiplets(ChipletsError::invalid_output_handle(
+                self.system.clk(),
+            ))),
+        }
+    }
+
+    fn execute_blocks(&mut self, blocks: &[CodeBlock]) -> Result<(), ProcessorError> {
+        for block in blocks {
+            match block {
+                CodeBlock::Span(ops) => {
+                    for op in ops {
+                        self.execute_op(op)?;
+                    }
+                }
+                // the branch guard checks the condition on top of the stack
+                CodeBlock::Switch(true_branch, false_branch) => match self.stack.current_stack_state()[0] {
+                    ONE => self.execute_blocks(true_branch)?,
+                    _ => self.execute_blocks(false_branch)?,
+                },
+                // each iteration and the exit start with a guard popping the condition
+                CodeBlock::Loop(body, exit) => {
+                    while self.stack.current_stack_state()[0] == ONE {
+                        self.execute_blocks(body)?;
+                    }
+                    self.execute_blocks(exit)?;
+                }
+                CodeBlock::Call(body) => self.execute_blocks(body)?,
+            }
+        }
+
+        Ok(())
+    }
+
     fn execute_op(&mut self, op: &Operation) -> Result<(), ProcessorError> {
         self.system.advance_step();
 
-        if let Err(err) = self.stack.execute_op(op) {
+        // stores write the stack state before the operation pops it
+        let stack_state = self.stack.current_stack_state();
+
+        // loads push the word read from memory
+        let result = match op.op_code() {
+            OpCode::MemLoad | OpCode::MemLoad2 => {
+                let values = self.memory.read(op, self.system.clk());
+                self.stack.execute_load(op, &values)
+            }
+            OpCode::CtRead | OpCode::CtAdd | OpCode::CtSMul | OpCode::CtMac => {
+                return self.execute_fhe_op(op, &stack_state);
+            }
+            _ => self.stack.execute_op(op),
+        };
+
+        if let Err(err) = result {
             return Err(ProcessorError::Stack(err));
         };
 
+        if let OpCode::MemStore | OpCode::MemStore2 = op.op_code() {
+            self.memory.write(op, self.system.clk(), &stack_state);
+        }
+
+        // hperm looks the permutation of the stack state up in the hash chiplet
+        if op.op_code() == OpCode::HPerm {
+            self.chiplets.permute(&stack_state);
+        }
+
+        // registers shifted past the bottom of the stack move through the memory table
+        for &(addr, write, value) in self.stack.overflow_accesses() {
+            self.memory.access_element(addr, self.system.clk(), write, value);
+        }
+
+        // u32 operations look their limbs up in the range chiplet
+        self.range.check(self.stack.range_checks());
+
+        // bitwise operations look their bytes up in the bitwise chiplet
+        self.bitwise.check(self.stack.bitwise_checks());
+
+        self.decoder.decode_op(op);
+
+        if let Err(err) = self.chiplets.hash_op(op) {
+            return Err(ProcessorError::Chiplets(err));
+        };
+
+        Ok(())
+    }
+
+    // the chiplet runs a pass for the op and the stack takes the handle of its result
+    // the pass moves the ciphertext elements through the memory table
+    fn execute_fhe_op(&mut self, op: &Operation, stack_state: &[BaseElement]) -> Result<(), ProcessorError> {
+        let handle = self.fhe.next_handle();
+
+        // the stack checks the operands before the chiplet looks their handles up
+        let input = match self.stack.execute_handle(op, handle) {
+            Ok(input) => input,
+            Err(err) => return Err(ProcessorError::Stack(err)),
+        };
+
+        let result = match input {
+            Some(input) => {
+                self.fhe.read(input.ciphertext());
+                Ok(())
+            }
+            None => self.fhe.execute(op, stack_state, self.system.clk()),
+        };
+
+        if let Err(err) = result {
+            return Err(ProcessorError::Chiplets(err));
+        };
+
+        for &(addr, clk, write, value) in self.fhe.accesses() {
+            self.memory.access_element(addr, clk, write, value);
+        }
+
+        for &(addr, write, value) in self.stack.overflow_accesses() {
+            self.memory.access_element(addr, self.system.clk(), write, value);
+        }
+
         self.decoder.decode_op(op);
 
         if let Err(err) = self.chiplets.hash_op(op) {
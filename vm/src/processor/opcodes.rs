@@ -1,3 +1,5 @@
+use winterfell::math::{fields::f128::BaseElement, FieldElement};
+
 #[rustfmt::skip]
 #[derive(Copy, Clone, PartialEq)]
 #[repr(u8)]
@@ -28,69 +30,280 @@ impl HashOperation {
 #[derive(Copy, Clone, PartialEq)]
 #[repr(u8)]
 pub enum OpCode {
-    Noop  = 0b00_000,
-
-    Push  = 0b10_000,    // shift-right: 1
-    Read  = 0b10_001,    // shift-right: 1
-
-    Read2 = 0b10_010,    // shift-right: 5
-
-    Add   = 0b01_000,    // shift-left: 1
-    Mul   = 0b01_001,    // shift-left: 1
-    SAdd  = 0b01_010,    // shift-left: 1
-    SMul  = 0b01_100,    // shift-left: 1
-    Add2  = 0b01_011,    // shift-left: 5
+    Noop    = 0b00_00000,
+    Swap    = 0b00_00001,
+    Swap2   = 0b00_00010,
+    Neg     = 0b00_00011,
+    Inv     = 0b00_00100,
+    Tend    = 0b00_00101,
+    Fend    = 0b00_00110,
+    Loop    = 0b00_01000,
+    Call    = 0b00_01100,
+    Repeat  = 0b00_10000,
+    Wend    = 0b00_11000,
+    U32Add  = 0b00_10001,
+    U32Sub  = 0b00_10010,
+    U32Mul  = 0b00_10100,
+    Shl     = 0b00_10111,
+    Shr     = 0b00_11110,
+    HPerm   = 0b00_01001,
+
+    Push    = 0b10_00000,   // shift-right: 1
+    Read    = 0b10_00001,   // shift-right: 1
+
+    Read2   = 0b10_00010,   // shift-right: 5
+    Dup2    = 0b10_00011,   // shift-right: 5
+    Push2   = 0b10_00100,   // shift-right: 5
+
+    MemLoad = 0b10_00111,   // shift-right: 1
+    MemLoad2 = 0b10_01110,   // shift-right: 5
+
+    CtRead  = 0b10_00101,   // shift-right: 1
+
+    Dup00   = 0b10_10000,   // shift-right: 1
+    Dup01   = 0b10_10001,   // shift-right: 1
+    Dup02   = 0b10_10010,   // shift-right: 1
+    Dup03   = 0b10_10011,   // shift-right: 1
+    Dup04   = 0b10_10100,   // shift-right: 1
+    Dup05   = 0b10_10101,   // shift-right: 1
+    Dup06   = 0b10_10110,   // shift-right: 1
+    Dup07   = 0b10_10111,   // shift-right: 1
+    Dup08   = 0b10_11000,   // shift-right: 1
+    Dup09   = 0b10_11001,   // shift-right: 1
+    Dup10   = 0b10_11010,   // shift-right: 1
+    Dup11   = 0b10_11011,   // shift-right: 1
+    Dup12   = 0b10_11100,   // shift-right: 1
+    Dup13   = 0b10_11101,   // shift-right: 1
+    Dup14   = 0b10_11110,   // shift-right: 1
+    Dup15   = 0b10_11111,   // shift-right: 1
+
+    Add     = 0b01_00000,   // shift-left: 1
+    Mul     = 0b01_00001,   // shift-left: 1
+    SAdd    = 0b01_00010,   // shift-left: 1
+    SMul    = 0b01_00100,   // shift-left: 1
+    Drop    = 0b01_00101,   // shift-left: 1
+    Sub     = 0b01_00111,   // shift-left: 1
+    CSwap   = 0b01_01000,   // shift-left: 1
+    CSel    = 0b01_01001,   // shift-left: 2
+    CSwap2  = 0b01_01010,   // shift-left: 1
+    CSel2   = 0b01_01011,   // shift-left: 6
+    True    = 0b01_01100,   // shift-left: 1
+    False   = 0b01_01101,   // shift-left: 1
+    MemStore = 0b01_01111,   // shift-left: 1
+    MemStore2 = 0b01_10111,   // shift-left: 5
+    CtAdd   = 0b01_01110,   // shift-left: 1
+    CtSMul  = 0b01_10000,   // shift-left: 1
+    CtMac   = 0b01_10001,   // shift-left: 2
+    Assert  = 0b01_10010,   // shift-left: 1
+    AssertZ = 0b01_10011,   // shift-left: 1
+    AssertEq = 0b01_10100,  // shift-left: 2
+    U32Div  = 0b01_11000,   // shift-left: 1
+    U32Mod  = 0b01_11011,   // shift-left: 1
+    And     = 0b01_10101,   // shift-left: 1
+    Or      = 0b01_10110,   // shift-left: 1
+    Xor     = 0b01_11101,   // shift-left: 1
+    Eq      = 0b01_11100,   // shift-left: 1
+    Neq     = 0b01_11110,   // shift-left: 1
+    Lt      = 0b01_11001,   // shift-left: 1
+    Lte     = 0b01_11010,   // shift-left: 1
+
+    Add2    = 0b01_00011,   // shift-left: 5
+    Drop2   = 0b01_00110,   // shift-left: 5
+
+    MovUp2  = 0b11_00010,   // no shift
+    MovUp3  = 0b11_00011,   // no shift
+    MovUp4  = 0b11_00100,   // no shift
+    MovUp5  = 0b11_00101,   // no shift
+    MovUp6  = 0b11_00110,   // no shift
+    MovUp7  = 0b11_00111,   // no shift
+    MovUp8  = 0b11_01000,   // no shift
+    MovUp9  = 0b11_01001,   // no shift
+    MovUp10 = 0b11_01010,   // no shift
+    MovUp11 = 0b11_01011,   // no shift
+    MovUp12 = 0b11_01100,   // no shift
+    MovUp13 = 0b11_01101,   // no shift
+    MovUp14 = 0b11_01110,   // no shift
+    MovUp15 = 0b11_01111,   // no shift
+
+    MovDn2  = 0b11_10010,   // no shift
+    MovDn3  = 0b11_10011,   // no shift
+    MovDn4  = 0b11_10100,   // no shift
+    MovDn5  = 0b11_10101,   // no shift
+    MovDn6  = 0b11_10110,   // no shift
+    MovDn7  = 0b11_10111,   // no shift
+    MovDn8  = 0b11_11000,   // no shift
+    MovDn9  = 0b11_11001,   // no shift
+    MovDn10 = 0b11_11010,   // no shift
+    MovDn11 = 0b11_11011,   // no shift
+    MovDn12 = 0b11_11100,   // no shift
+    MovDn13 = 0b11_11101,   // no shift
+    MovDn14 = 0b11_11110,   // no shift
+    MovDn15 = 0b11_11111,   // no shift
 }
 
+// dup, movup and movdn encode n in the lower 4 bits of the op code
+pub const DUP: [OpCode; 16] = [
+    OpCode::Dup00,
+    OpCode::Dup01,
+    OpCode::Dup02,
+    OpCode::Dup03,
+    OpCode::Dup04,
+    OpCode::Dup05,
+    OpCode::Dup06,
+    OpCode::Dup07,
+    OpCode::Dup08,
+    OpCode::Dup09,
+    OpCode::Dup10,
+    OpCode::Dup11,
+    OpCode::Dup12,
+    OpCode::Dup13,
+    OpCode::Dup14,
+    OpCode::Dup15,
+];
+
+pub const MOVUP: [OpCode; 14] = [
+    OpCode::MovUp2,
+    OpCode::MovUp3,
+    OpCode::MovUp4,
+    OpCode::MovUp5,
+    OpCode::MovUp6,
+    OpCode::MovUp7,
+    OpCode::MovUp8,
+    OpCode::MovUp9,
+    OpCode::MovUp10,
+    OpCode::MovUp11,
+    OpCode::MovUp12,
+    OpCode::MovUp13,
+    OpCode::MovUp14,
+    OpCode::MovUp15,
+];
+
+pub const MOVDN: [OpCode; 14] = [
+    OpCode::MovDn2,
+    OpCode::MovDn3,
+    OpCode::MovDn4,
+    OpCode::MovDn5,
+    OpCode::MovDn6,
+    OpCode::MovDn7,
+    OpCode::MovDn8,
+    OpCode::MovDn9,
+    OpCode::MovDn10,
+    OpCode::MovDn11,
+    OpCode::MovDn12,
+    OpCode::MovDn13,
+    OpCode::MovDn14,
+    OpCode::MovDn15,
+];
+
+// operations moving ciphertexts through the stack registers
+pub const STACK_CT_OPS: [OpCode; 12] = [
+    OpCode::Read2,
+    OpCode::Dup2,
+    OpCode::Push2,
+    OpCode::MemLoad2,
+    OpCode::SAdd,
+    OpCode::SMul,
+    OpCode::CSwap2,
+    OpCode::CSel2,
+    OpCode::MemStore2,
+    OpCode::Add2,
+    OpCode::Drop2,
+    OpCode::Swap2,
+];
+
 impl std::fmt::Display for OpCode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         #[rustfmt::skip]
         return match self {
             OpCode::Noop    => write!(f, "noop"),
+            OpCode::Swap    => write!(f, "swap"),
+            OpCode::Swap2   => write!(f, "swap2"),
+            OpCode::Neg     => write!(f, "neg"),
+            OpCode::Inv     => write!(f, "inv"),
+            OpCode::Tend    => write!(f, "tend"),
+            OpCode::Fend    => write!(f, "fend"),
+            OpCode::Loop    => write!(f, "loop"),
+            OpCode::Call    => write!(f, "call"),
+            OpCode::Repeat  => write!(f, "repeat"),
+            OpCode::Wend    => write!(f, "wend"),
 
             OpCode::Push    => write!(f, "push"),
             OpCode::Read    => write!(f, "read"),
             OpCode::Read2   => write!(f, "read2"),
+            OpCode::Dup2    => write!(f, "dup2"),
+            OpCode::Push2   => write!(f, "push2"),
 
             OpCode::Add     => write!(f, "add"),
             OpCode::Mul     => write!(f, "mul"),
             OpCode::SAdd    => write!(f, "sadd"),
             OpCode::SMul    => write!(f, "smul"),
+            OpCode::Drop    => write!(f, "drop"),
+            OpCode::Sub     => write!(f, "sub"),
+            OpCode::CSwap   => write!(f, "cswap"),
+            OpCode::CSel    => write!(f, "csel"),
+            OpCode::CSwap2  => write!(f, "cswap2"),
+            OpCode::CSel2   => write!(f, "csel2"),
+            OpCode::True    => write!(f, "true"),
+            OpCode::False   => write!(f, "false"),
             OpCode::Add2    => write!(f, "add2"),
+            OpCode::Drop2   => write!(f, "drop2"),
+            OpCode::MemLoad => write!(f, "mem_load"),
+            OpCode::MemLoad2 => write!(f, "mem_load2"),
+            OpCode::MemStore => write!(f, "mem_store"),
+            OpCode::MemStore2 => write!(f, "mem_store2"),
+            OpCode::CtRead  => write!(f, "ct_read"),
+            OpCode::CtAdd   => write!(f, "ct_add"),
+            OpCode::CtSMul  => write!(f, "ct_smul"),
+            OpCode::CtMac   => write!(f, "ct_mac"),
+            OpCode::Assert  => write!(f, "assert"),
+            OpCode::AssertZ => write!(f, "assertz"),
+            OpCode::AssertEq => write!(f, "assert_eq"),
+            OpCode::U32Add  => write!(f, "u32add"),
+            OpCode::U32Sub  => write!(f, "u32sub"),
+            OpCode::U32Mul  => write!(f, "u32mul"),
+            OpCode::U32Div  => write!(f, "u32div"),
+            OpCode::U32Mod  => write!(f, "u32mod"),
+            OpCode::And     => write!(f, "and"),
+            OpCode::Or      => write!(f, "or"),
+            OpCode::Xor     => write!(f, "xor"),
+            OpCode::Shl     => write!(f, "shl"),
+            OpCode::Shr     => write!(f, "shr"),
+            OpCode::HPerm   => write!(f, "hperm"),
+            OpCode::Eq      => write!(f, "eq"),
+            OpCode::Neq     => write!(f, "neq"),
+            OpCode::Lt      => write!(f, "lt"),
+            OpCode::Lte     => write!(f, "lte"),
+
+            op if DUP.contains(op)      => write!(f, "dup.{}", *op as u8 & 0b1111),
+            op if MOVUP.contains(op)    => write!(f, "movup.{}", *op as u8 & 0b1111),
+            op                          => write!(f, "movdn.{}", *op as u8 & 0b1111),
         };
     }
 }
 
 impl std::fmt::Debug for OpCode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        #[rustfmt::skip]
-        return match self {
-            OpCode::Noop    => write!(f, "noop"),
-
-            OpCode::Push    => write!(f, "push"),
-            OpCode::Read    => write!(f, "read"),
-            OpCode::Read2   => write!(f, "read2"),
-
-            OpCode::Add     => write!(f, "add"),
-            OpCode::Mul     => write!(f, "mul"),
-            OpCode::SAdd    => write!(f, "sadd"),
-            OpCode::SMul    => write!(f, "smul"),
-            OpCode::Add2    => write!(f, "add2"),
-        };
+        write!(f, "{self}")
     }
 }
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum OpValue {
-    Push(u8),
+    Push(BaseElement),
+    // sibling branch digest element
+    Digest(BaseElement),
+    // memory word address
+    Address(BaseElement),
+    // power of two of a shift amount
+    Shift(BaseElement),
     None,
 }
 
 impl OpValue {
-    pub fn value(&self) -> u8 {
+    pub fn value(&self) -> BaseElement {
         match self {
-            OpValue::Push(value) => *value,
-            _ => 0,
+            OpValue::Push(value) | OpValue::Digest(value) | OpValue::Address(value) | OpValue::Shift(value) => *value,
+            _ => BaseElement::ZERO,
         }
     }
 }
@@ -98,7 +311,9 @@ impl OpValue {
 impl std::fmt::Display for OpValue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            OpValue::Push(value) => write!(f, "({})", value),
+            OpValue::Push(value) | OpValue::Digest(value) | OpValue::Address(value) | OpValue::Shift(value) => {
+                write!(f, "({})", value)
+            }
             OpValue::None => Ok(()),
         }
     }
@@ -107,7 +322,9 @@ impl std::fmt::Display for OpValue {
 impl std::fmt::Debug for OpValue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            OpValue::Push(value) => write!(f, "({:?})", value),
+            OpValue::Push(value) | OpValue::Digest(value) | OpValue::Address(value) | OpValue::Shift(value) => {
+                write!(f, "({:?})", value)
+            }
             OpValue::None => Ok(()),
         }
     }
@@ -128,7 +345,7 @@ impl Operation {
         self.op_code as u8
     }
 
-    pub fn value(&self) -> u8 {
+    pub fn value(&self) -> BaseElement {
         self.op_value.value()
     }
 
@@ -144,10 +361,15 @@ impl Operation {
         Operation::new(OpCode::Noop, OpValue::None)
     }
 
-    pub fn push(value: u8) -> Operation {
+    pub fn push(value: BaseElement) -> Operation {
         Operation::new(OpCode::Push, OpValue::Push(value))
     }
 
+    // pushes a trivial encryption of value
+    pub fn push2(value: BaseElement) -> Operation {
+        Operation::new(OpCode::Push2, OpValue::Push(value))
+    }
+
     pub fn read() -> Operation {
         Operation::new(OpCode::Read, OpValue::None)
     }
@@ -175,6 +397,239 @@ impl Operation {
     pub fn add2() -> Operation {
         Operation::new(OpCode::Add2, OpValue::None)
     }
+
+    pub fn sub() -> Operation {
+        Operation::new(OpCode::Sub, OpValue::None)
+    }
+
+    pub fn neg() -> Operation {
+        Operation::new(OpCode::Neg, OpValue::None)
+    }
+
+    pub fn inv() -> Operation {
+        Operation::new(OpCode::Inv, OpValue::None)
+    }
+
+    // pops b and a, pushes (a + b) mod 2^32 and the carry on top
+    pub fn u32add() -> Operation {
+        Operation::new(OpCode::U32Add, OpValue::None)
+    }
+
+    // pops b and a, pushes (a - b) mod 2^32 and the borrow on top
+    pub fn u32sub() -> Operation {
+        Operation::new(OpCode::U32Sub, OpValue::None)
+    }
+
+    // pops b and a, pushes (a * b) mod 2^32 and the overflow flag on top
+    pub fn u32mul() -> Operation {
+        Operation::new(OpCode::U32Mul, OpValue::None)
+    }
+
+    // pops b and a, pushes a / b
+    pub fn u32div() -> Operation {
+        Operation::new(OpCode::U32Div, OpValue::None)
+    }
+
+    // pops b and a, pushes a mod b
+    pub fn u32mod() -> Operation {
+        Operation::new(OpCode::U32Mod, OpValue::None)
+    }
+
+    // pops b and a, pushes a and b
+    pub fn and() -> Operation {
+        Operation::new(OpCode::And, OpValue::None)
+    }
+
+    // pops b and a, pushes a or b
+    pub fn or() -> Operation {
+        Operation::new(OpCode::Or, OpValue::None)
+    }
+
+    // pops b and a, pushes a xor b
+    pub fn xor() -> Operation {
+        Operation::new(OpCode::Xor, OpValue::None)
+    }
+
+    // pops a, pushes (a << n) mod 2^32, the op value is 2^n
+    pub fn shl(n: u32) -> Operation {
+        Operation::new(OpCode::Shl, OpValue::Shift(BaseElement::from(1u32 << n)))
+    }
+
+    // pops a, pushes a >> n, the op value is 2^n
+    pub fn shr(n: u32) -> Operation {
+        Operation::new(OpCode::Shr, OpValue::Shift(BaseElement::from(1u32 << n)))
+    }
+
+    // pops b and a, pushes 1 when a = b, 0 otherwise
+    pub fn eq() -> Operation {
+        Operation::new(OpCode::Eq, OpValue::None)
+    }
+
+    // pops b and a, pushes 1 when a != b, 0 otherwise
+    pub fn neq() -> Operation {
+        Operation::new(OpCode::Neq, OpValue::None)
+    }
+
+    // pops b and a, pushes 1 when a < b, 0 otherwise
+    pub fn lt() -> Operation {
+        Operation::new(OpCode::Lt, OpValue::None)
+    }
+
+    // pops b and a, pushes 1 when a <= b, 0 otherwise
+    pub fn lte() -> Operation {
+        Operation::new(OpCode::Lte, OpValue::None)
+    }
+
+    // applies the rescue permutation to the top four stack elements
+    pub fn hperm() -> Operation {
+        Operation::new(OpCode::HPerm, OpValue::None)
+    }
+
+    pub fn cswap() -> Operation {
+        Operation::new(OpCode::CSwap, OpValue::None)
+    }
+
+    pub fn csel() -> Operation {
+        Operation::new(OpCode::CSel, OpValue::None)
+    }
+
+    pub fn cswap2() -> Operation {
+        Operation::new(OpCode::CSwap2, OpValue::None)
+    }
+
+    pub fn csel2() -> Operation {
+        Operation::new(OpCode::CSel2, OpValue::None)
+    }
+
+    // pops the condition of a true branch, it must be 1
+    pub fn true_branch() -> Operation {
+        Operation::new(OpCode::True, OpValue::None)
+    }
+
+    // pops the condition of a false branch, it must be 0
+    pub fn false_branch() -> Operation {
+        Operation::new(OpCode::False, OpValue::None)
+    }
+
+    // pops the top element, it must be 1
+    pub fn assert() -> Operation {
+        Operation::new(OpCode::Assert, OpValue::None)
+    }
+
+    // pops the top element, it must be 0
+    pub fn assertz() -> Operation {
+        Operation::new(OpCode::AssertZ, OpValue::None)
+    }
+
+    // pops the top two elements, they must be equal
+    pub fn assert_eq() -> Operation {
+        Operation::new(OpCode::AssertEq, OpValue::None)
+    }
+
+    // ends a true branch absorbing an element of the false branch digest
+    pub fn tend(value: BaseElement) -> Operation {
+        Operation::new(OpCode::Tend, OpValue::Digest(value))
+    }
+
+    // ends a false branch absorbing an element of the true branch digest
+    pub fn fend(value: BaseElement) -> Operation {
+        Operation::new(OpCode::Fend, OpValue::Digest(value))
+    }
+
+    // enters a loop loading an element of the loop body digest
+    pub fn enter_loop(value: BaseElement) -> Operation {
+        Operation::new(OpCode::Loop, OpValue::Digest(value))
+    }
+
+    // enters a procedure call loading an element of the procedure digest
+    pub fn call(value: BaseElement) -> Operation {
+        Operation::new(OpCode::Call, OpValue::Digest(value))
+    }
+
+    // ends a loop iteration, checks the body digest and restores the loop entry state
+    pub fn repeat() -> Operation {
+        Operation::new(OpCode::Repeat, OpValue::None)
+    }
+
+    // exits a loop absorbing the loop body digest
+    pub fn wend() -> Operation {
+        Operation::new(OpCode::Wend, OpValue::None)
+    }
+
+    // pushes the first element of the word at addr
+    pub fn mem_load(addr: BaseElement) -> Operation {
+        Operation::new(OpCode::MemLoad, OpValue::Address(addr))
+    }
+
+    // pushes the ciphertext at addr
+    pub fn mem_load2(addr: BaseElement) -> Operation {
+        Operation::new(OpCode::MemLoad2, OpValue::Address(addr))
+    }
+
+    // pops the top element into the word at addr
+    pub fn mem_store(addr: BaseElement) -> Operation {
+        Operation::new(OpCode::MemStore, OpValue::Address(addr))
+    }
+
+    // pops the top ciphertext into the word at addr
+    pub fn mem_store2(addr: BaseElement) -> Operation {
+        Operation::new(OpCode::MemStore2, OpValue::Address(addr))
+    }
+
+    // reads a ciphertext into the fhe chiplet, pushes its handle
+    pub fn ct_read() -> Operation {
+        Operation::new(OpCode::CtRead, OpValue::None)
+    }
+
+    // pops two handles, pushes the handle of the sum
+    pub fn ct_add() -> Operation {
+        Operation::new(OpCode::CtAdd, OpValue::None)
+    }
+
+    // pops a scalar and a handle, pushes the handle of the product
+    pub fn ct_smul() -> Operation {
+        Operation::new(OpCode::CtSMul, OpValue::None)
+    }
+
+    // pops a scalar, a handle and an accumulator handle, pushes the handle of acc + scalar * ct
+    pub fn ct_mac() -> Operation {
+        Operation::new(OpCode::CtMac, OpValue::None)
+    }
+
+    // duplicates the n-th stack element, n < 16
+    pub fn dup(n: usize) -> Operation {
+        Operation::new(DUP[n], OpValue::None)
+    }
+
+    pub fn dup2() -> Operation {
+        Operation::new(OpCode::Dup2, OpValue::None)
+    }
+
+    pub fn swap() -> Operation {
+        Operation::new(OpCode::Swap, OpValue::None)
+    }
+
+    pub fn swap2() -> Operation {
+        Operation::new(OpCode::Swap2, OpValue::None)
+    }
+
+    pub fn drop() -> Operation {
+        Operation::new(OpCode::Drop, OpValue::None)
+    }
+
+    pub fn drop2() -> Operation {
+        Operation::new(OpCode::Drop2, OpValue::None)
+    }
+
+    // moves the n-th stack element to the top, 2 <= n < 16
+    pub fn movup(n: usize) -> Operation {
+        Operation::new(MOVUP[n - 2], OpValue::None)
+    }
+
+    // moves the top stack element to the n-th position, 2 <= n < 16
+    pub fn movdn(n: usize) -> Operation {
+        Operation::new(MOVDN[n - 2], OpValue::None)
+    }
 }
 
 impl std::fmt::Display for Operation {
@@ -0,0 +1,65 @@
+use super::{ONE, ZERO};
+use winterfell::math::{fields::f128::BaseElement, StarkField};
+
+// the table runs through every byte value once traces are longer than it
+const NUM_VALUES: usize = 256;
+
+// value | lookup flag
+const RANGE_TRACE_WIDTH: usize = 2;
+
+// range checks of the byte limbs of the u32 operations
+// the table rows are sorted by value, each lookup gets its own row next to the filler row of its value
+pub struct RangeChiplet {
+    lookups: [usize; NUM_VALUES],
+    num_lookups: usize,
+}
+
+impl RangeChiplet {
+    pub fn new() -> RangeChiplet {
+        RangeChiplet {
+            lookups: [0; NUM_VALUES],
+            num_lookups: 0,
+        }
+    }
+
+    // the limbs come from the u32 helpers of the stack, they are bytes
+    pub fn check(&mut self, limbs: &[BaseElement]) {
+        for limb in limbs {
+            self.lookups[limb.as_int() as usize] += 1;
+        }
+        self.num_lookups += limbs.len();
+    }
+
+    // rows of the table and the last row, a single row without lookups
+    pub fn trace_length(&self) -> usize {
+        match self.num_lookups {
+            0 => 1,
+            n => NUM_VALUES + n + 1,
+        }
+    }
+
+    // traces up to 256 rows cannot step past 255, longer ones end the table at 255
+    pub fn into_trace(self, trace_length: usize) -> Vec<Vec<BaseElement>> {
+        let mut trace: Vec<Vec<BaseElement>> = (0..RANGE_TRACE_WIDTH)
+            .map(|_| Vec::with_capacity(trace_length))
+            .collect();
+
+        if trace_length > NUM_VALUES {
+            for (value, &count) in self.lookups.iter().enumerate() {
+                let value = BaseElement::from(value as u8);
+
+                trace[0].push(value);
+                trace[1].push(ZERO);
+
+                trace[0].extend((0..count).map(|_| value));
+                trace[1].extend((0..count).map(|_| ONE));
+            }
+        }
+
+        let last = trace[0].last().copied().unwrap_or(ZERO);
+        trace[0].resize(trace_length, last);
+        trace[1].resize(trace_length, ZERO);
+
+        trace
+    }
+}
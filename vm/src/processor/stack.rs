@@ -1,57 +1,139 @@
 use super::errors::StackError;
+use super::opcodes::{DUP, MOVDN, MOVUP};
 use super::ProgramInputs;
-use super::{OpCode, Operation, ZERO};
+use super::{OpCode, Operation, ONE, ZERO};
 
-use fhe::{FheUInt8, ServerKey};
+use crypto::rescue::{self, STATE_WIDTH};
+use fhe::{FheUInt8, HomomorphicScheme};
 
-use winterfell::math::fields::f128::BaseElement;
+use winterfell::math::{fields::f128::BaseElement, FieldElement, StarkField};
 
-use std::ops::{Add, Mul};
+use std::ops::{Add, Mul, Neg, Sub};
 
-use super::MAX_STACK_DEPTH;
+use super::{MAX_STACK_DEPTH, STACK_WIDTH};
 
-pub struct Stack<'a> {
+// overflow elements live in the memory table above the u16 addresses
+// the element p positions above the stack bottom is at OVERFLOW_ADDR + 16 + p
+const OVERFLOW_ADDR: u128 = 1 << 16;
+
+// a shift moves at most 8 registers, slot m flags register 15 - m
+const NUM_OVERFLOW_SLOTS: usize = 8;
+
+// empty registers of a stack up to 16 elements in base 4 limbs
+const NUM_FREE_LIMBS: usize = 2;
+
+// the maximum depth minus the depth in base 4 limbs, the limbs bound it to 16 bits
+const NUM_ROOM_LIMBS: usize = 8;
+
+// u32 operations check five values in byte limbs
+// u32 flag | limbs | inverse of the product high word | bitwise flag
+const NUM_U32_VALUES: usize = 5;
+const NUM_U32_LIMBS: usize = 4;
+const U32_INV_COL: usize = 1 + NUM_U32_VALUES * NUM_U32_LIMBS;
+const BITWISE_FLAG_COL: usize = U32_INV_COL + 1;
+const NUM_U32_HELPERS: usize = BITWISE_FLAG_COL + 1;
+
+const U32_MODULUS: u64 = 1 << 32;
+
+pub struct Stack<'a, S: HomomorphicScheme> {
     clk: usize,
     registers: Vec<Vec<BaseElement>>,
     helpers: Vec<Vec<BaseElement>>,
+    overflow: Vec<BaseElement>,
+    overflow_helpers: Vec<Vec<BaseElement>>,
+    overflow_accesses: Vec<(u128, bool, BaseElement)>,
+    u32_helpers: Vec<Vec<BaseElement>>,
+    range_checks: Vec<BaseElement>,
+    bitwise_checks: Vec<(u8, u8)>,
     tape_a: Vec<u8>,
     tape_b: Vec<FheUInt8>,
     depth: usize,
-    server_key: &'a ServerKey,
+    scheme: &'a S,
     trace_length: usize,
 }
 
-impl<'a> Stack<'a> {
-    pub fn new(inputs: &'a ProgramInputs, init_trace_length: usize) -> Self {
-        let registers: Vec<Vec<BaseElement>> = (0..MAX_STACK_DEPTH).map(|_| vec![ZERO; init_trace_length]).collect();
+impl<'a, S: HomomorphicScheme> Stack<'a, S> {
+    pub fn new(inputs: &'a ProgramInputs<S>, init_trace_length: usize) -> Self {
+        let registers: Vec<Vec<BaseElement>> = (0..STACK_WIDTH).map(|_| vec![ZERO; init_trace_length]).collect();
 
         let helpers: Vec<Vec<BaseElement>> = (0..1).map(|_| vec![ZERO; init_trace_length]).collect();
 
+        let overflow_helpers: Vec<Vec<BaseElement>> = (0..(NUM_FREE_LIMBS + NUM_ROOM_LIMBS + NUM_OVERFLOW_SLOTS))
+            .map(|_| vec![ZERO; init_trace_length])
+            .collect();
+
+        let u32_helpers: Vec<Vec<BaseElement>> = (0..NUM_U32_HELPERS).map(|_| vec![ZERO; init_trace_length]).collect();
+
         // reverse inputs to pop them in order
         let mut tape_a = inputs.public().to_vec();
         tape_a.reverse();
         let mut tape_b = inputs.secret().to_vec();
         tape_b.reverse();
 
-        Stack {
+        let mut stack = Stack {
             clk: 0,
             registers,
             helpers,
+            overflow: Vec::new(),
+            overflow_helpers,
+            overflow_accesses: Vec::new(),
+            u32_helpers,
+            range_checks: Vec::new(),
+            bitwise_checks: Vec::new(),
             tape_a,
             tape_b,
             depth: 0,
-            server_key: inputs.server_key(),
+            scheme: inputs.scheme(),
             trace_length: init_trace_length,
-        }
+        };
+
+        stack.set_helpers();
+
+        stack
     }
 
     pub fn execute_op(&mut self, op: &Operation) -> Result<(), StackError> {
         self.advance_clock();
         self.ensure_trace_capacity();
+        self.overflow_accesses.clear();
+        self.range_checks.clear();
+        self.bitwise_checks.clear();
 
         #[rustfmt::skip]
         match op.op_code() {
             OpCode::Noop    => self.op_noop(),
+            OpCode::Tend    => self.op_noop(),
+            OpCode::Fend    => self.op_noop(),
+            OpCode::Loop    => self.op_noop(),
+            OpCode::Call    => self.op_noop(),
+            OpCode::Repeat  => self.op_noop(),
+            OpCode::Wend    => self.op_noop(),
+
+            OpCode::True    => self.op_branch(op, ONE),
+            OpCode::False   => self.op_branch(op, ZERO),
+
+            OpCode::Assert  => self.op_assert(op, ONE),
+            OpCode::AssertZ => self.op_assert(op, ZERO),
+            OpCode::AssertEq => self.op_assert_eq(op),
+
+            OpCode::U32Add  => self.op_u32add(op),
+            OpCode::U32Sub  => self.op_u32sub(op),
+            OpCode::U32Mul  => self.op_u32mul(op),
+            OpCode::U32Div  => self.op_u32div(op),
+            OpCode::U32Mod  => self.op_u32div(op),
+
+            OpCode::And     => self.op_bitwise(op),
+            OpCode::Or      => self.op_bitwise(op),
+            OpCode::Xor     => self.op_bitwise(op),
+            OpCode::Shl     => self.op_shl(op),
+            OpCode::Shr     => self.op_shr(op),
+
+            OpCode::Eq      => self.op_eq(op),
+            OpCode::Neq     => self.op_eq(op),
+            OpCode::Lt      => self.op_lt(op),
+            OpCode::Lte     => self.op_lt(op),
+
+            OpCode::HPerm   => self.op_hperm(op),
 
             OpCode::Push    => self.op_push(op),
             OpCode::Read    => self.op_read(op),
@@ -62,16 +144,116 @@ impl<'a> Stack<'a> {
             OpCode::SAdd    => self.op_sadd(op),
             OpCode::SMul    => self.op_smul(op),
             OpCode::Add2    => self.op_add2(op),
+
+            OpCode::Sub     => self.op_sub(op),
+            OpCode::Neg     => self.op_neg(op),
+            OpCode::Inv     => self.op_inv(op),
+
+            OpCode::CSwap   => self.op_cswap(op),
+            OpCode::CSel    => self.op_csel(op),
+            OpCode::CSwap2  => self.op_cswap2(op),
+            OpCode::CSel2   => self.op_csel2(op),
+
+            OpCode::Swap    => self.op_swap(op),
+            OpCode::Drop    => self.op_drop(op),
+            OpCode::Dup2    => self.op_dup2(op),
+            OpCode::Push2   => self.op_push2(op),
+            OpCode::Swap2   => self.op_swap2(op),
+            OpCode::Drop2   => self.op_drop2(op),
+
+            OpCode::MemStore    => self.op_drop(op),
+            OpCode::MemStore2   => self.op_drop2(op),
+
+            code if DUP.contains(&code)     => self.op_dup(op, op.code() as usize & 0b1111),
+            code if MOVUP.contains(&code)   => self.op_movup(op, op.code() as usize & 0b1111),
+            code if MOVDN.contains(&code)   => self.op_movdn(op, op.code() as usize & 0b1111),
+
+            _ => unreachable!(),
         }?;
 
         self.set_helpers();
 
-        Ok(())
+        self.check_depth(op)
+    }
+
+    // pushes the values loaded from memory, the air binds them to the memory table
+    pub fn execute_load(&mut self, op: &Operation, values: &[BaseElement]) -> Result<(), StackError> {
+        self.advance_clock();
+        self.ensure_trace_capacity();
+        self.overflow_accesses.clear();
+        self.range_checks.clear();
+        self.bitwise_checks.clear();
+
+        self.shift_right(0, values.len());
+        for (i, value) in values.iter().enumerate() {
+            self.registers[i][self.clk] = *value;
+        }
+
+        self.set_helpers();
+
+        self.check_depth(op)
     }
 
-    pub fn current_stack_state(&self) -> [BaseElement; MAX_STACK_DEPTH] {
-        let mut state = Vec::with_capacity(MAX_STACK_DEPTH);
-        for i in 0..MAX_STACK_DEPTH {
+    // ciphertext ops replace their operands by the handle of the result in the fhe chiplet
+    // ct_read returns the secret input it hands over to the chiplet
+    pub fn execute_handle(&mut self, op: &Operation, handle: BaseElement) -> Result<Option<FheUInt8>, StackError> {
+        self.advance_clock();
+        self.ensure_trace_capacity();
+        self.overflow_accesses.clear();
+        self.range_checks.clear();
+        self.bitwise_checks.clear();
+
+        let input = match op.op_code() {
+            OpCode::CtRead => {
+                let input = match self.tape_b.pop() {
+                    Some(value) => value,
+                    None => return Err(StackError::empty_inputs(op, self.clk)),
+                };
+                self.shift_right(0, 1);
+                Some(input)
+            }
+            OpCode::CtAdd | OpCode::CtSMul => {
+                if self.depth < 2 {
+                    return Err(StackError::stack_underflow(op, self.clk));
+                }
+                self.shift_left(op, 2, 1)?;
+                None
+            }
+            OpCode::CtMac => {
+                if self.depth < 3 {
+                    return Err(StackError::stack_underflow(op, self.clk));
+                }
+                self.shift_left(op, 3, 2)?;
+                None
+            }
+            _ => unreachable!(),
+        };
+
+        self.registers[0][self.clk] = handle;
+
+        self.set_helpers();
+        self.check_depth(op)?;
+
+        Ok(input)
+    }
+
+    // memory table accesses of the overflow slots of the last operation, (addr, write, value)
+    pub fn overflow_accesses(&self) -> &[(u128, bool, BaseElement)] {
+        &self.overflow_accesses
+    }
+
+    // byte limbs the last operation checks through the range chiplet
+    pub fn range_checks(&self) -> &[BaseElement] {
+        &self.range_checks
+    }
+
+    pub fn bitwise_checks(&self) -> &[(u8, u8)] {
+        &self.bitwise_checks
+    }
+
+    pub fn current_stack_state(&self) -> [BaseElement; STACK_WIDTH] {
+        let mut state = Vec::with_capacity(STACK_WIDTH);
+        for i in 0..STACK_WIDTH {
             state.push(self.registers[i][self.clk]);
         }
         state.try_into().unwrap()
@@ -90,27 +272,49 @@ impl<'a> Stack<'a> {
             col.resize(trace_length, col[self.clk]);
         }
 
+        // the last row has no overflow slots flagged, the padding keeps it
+        for col in self.overflow_helpers.iter_mut() {
+            col.resize(self.clk + 1, ZERO);
+            col.resize(trace_length, col[self.clk]);
+        }
+
+        // padding rows run no u32 operation
+        for col in self.u32_helpers.iter_mut() {
+            col.resize(trace_length, ZERO);
+        }
+
         trace.append(&mut self.helpers);
         trace.append(&mut self.registers);
+        trace.append(&mut self.overflow_helpers);
+        trace.append(&mut self.u32_helpers);
 
         trace
     }
 
     fn op_noop(&mut self) -> Result<(), StackError> {
-        for i in 0..self.depth {
+        for i in 0..self.top() {
             self.registers[i][self.clk] = self.registers[i][self.clk - 1];
         }
         Ok(())
     }
 
     fn op_push(&mut self, op: &Operation) -> Result<(), StackError> {
-        self.shift_right(op, 0, 1)?;
-        self.registers[0][self.clk] = BaseElement::from(op.value());
+        self.shift_right(0, 1);
+        self.registers[0][self.clk] = op.value();
+        Ok(())
+    }
+
+    fn op_push2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct = self.scheme.encrypt_trivial(&op.value()).ciphertext().to_vec();
+        self.shift_right(0, ct.len());
+        for (i, value) in ct.iter().enumerate() {
+            self.registers[i][self.clk] = *value;
+        }
         Ok(())
     }
 
     fn op_read(&mut self, op: &Operation) -> Result<(), StackError> {
-        self.shift_right(op, 0, 1)?;
+        self.shift_right(0, 1);
         let value = match self.tape_a.pop() {
             Some(value) => value,
             None => return Err(StackError::empty_inputs(op, self.clk)),
@@ -124,7 +328,7 @@ impl<'a> Stack<'a> {
             Some(value) => value.ciphertext().to_vec(),
             None => return Err(StackError::empty_inputs(op, self.clk)),
         };
-        self.shift_right(op, 0, ct.len())?;
+        self.shift_right(0, ct.len());
         for (i, value) in ct.iter().enumerate() {
             self.registers[i][self.clk] = *value;
         }
@@ -152,69 +356,517 @@ impl<'a> Stack<'a> {
         self.shift_left(op, 2, 1)
     }
 
+    // pops a and b, pushes b - a
+    fn op_sub(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+        let x = self.registers[0][self.clk - 1];
+        let y = self.registers[1][self.clk - 1];
+        self.registers[0][self.clk] = y.sub(x);
+        self.shift_left(op, 2, 1)
+    }
+
+    fn op_neg(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+        self.registers[0][self.clk] = self.registers[0][self.clk - 1].neg();
+        self.copy(1);
+        Ok(())
+    }
+
+    // the inverse is a nondeterministic witness, the air checks x * inv = 1
+    fn op_inv(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+        let x = self.registers[0][self.clk - 1];
+        if x == ZERO {
+            return Err(StackError::division_by_zero(op, self.clk));
+        }
+        self.registers[0][self.clk] = x.inv();
+        self.copy(1);
+        Ok(())
+    }
+
+    // pops the branch condition, it must match the executed branch
+    fn op_branch(&mut self, op: &Operation, condition: BaseElement) -> Result<(), StackError> {
+        if self.depth < 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        if self.registers[0][self.clk - 1] != condition {
+            return Err(StackError::invalid_condition(op, self.clk));
+        }
+
+        self.shift_left(op, 1, 1)
+    }
+
+    // pops the top element, it must equal the expected value
+    fn op_assert(&mut self, op: &Operation, expected: BaseElement) -> Result<(), StackError> {
+        if self.depth < 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let value = self.registers[0][self.clk - 1];
+        if value != expected {
+            return Err(StackError::assertion_failed(op, expected, value, self.clk));
+        }
+
+        self.shift_left(op, 1, 1)
+    }
+
+    // pops the top two elements, s1 must equal s0
+    fn op_assert_eq(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let (a, b) = (self.registers[0][self.clk - 1], self.registers[1][self.clk - 1]);
+        if a != b {
+            return Err(StackError::assertion_failed(op, a, b, self.clk));
+        }
+
+        self.shift_left(op, 2, 2)
+    }
+
+    // pops b and a, pushes the carry over (a + b) mod 2^32
+    fn op_u32add(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        let sum = a + b;
+
+        self.set_u32_helpers([a, b, sum % U32_MODULUS, 0, 0], ZERO);
+
+        self.registers[0][self.clk] = BaseElement::from(sum / U32_MODULUS);
+        self.registers[1][self.clk] = BaseElement::from(sum % U32_MODULUS);
+        self.copy(2);
+        Ok(())
+    }
+
+    // pops b and a, pushes the borrow over (a - b) mod 2^32
+    fn op_u32sub(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        let borrow = (a < b) as u64;
+        let diff = a + borrow * U32_MODULUS - b;
+
+        self.set_u32_helpers([a, b, diff, 0, 0], ZERO);
+
+        self.registers[0][self.clk] = BaseElement::from(borrow);
+        self.registers[1][self.clk] = BaseElement::from(diff);
+        self.copy(2);
+        Ok(())
+    }
+
+    // pops b and a, pushes the overflow flag over (a * b) mod 2^32
+    // the inverse of the high word is a nondeterministic witness of the flag
+    fn op_u32mul(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        let product = a * b;
+        let (lo, hi) = (product % U32_MODULUS, product / U32_MODULUS);
+
+        let inv = match hi {
+            0 => ZERO,
+            hi => BaseElement::from(hi).inv(),
+        };
+        self.set_u32_helpers([a, b, lo, hi, 0], inv);
+
+        self.registers[0][self.clk] = BaseElement::from((hi != 0) as u64);
+        self.registers[1][self.clk] = BaseElement::from(lo);
+        self.copy(2);
+        Ok(())
+    }
+
+    // pops b and a, pushes a / b for u32div and a mod b for u32mod
+    // the quotient and remainder are nondeterministic witnesses, the air checks a = q * b + r and r < b
+    fn op_u32div(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        if b == 0 {
+            return Err(StackError::division_by_zero(op, self.clk));
+        }
+        let (q, r) = (a / b, a % b);
+
+        self.set_u32_helpers([a, b, q, r, b - r - 1], ZERO);
+
+        self.registers[0][self.clk] = match op.op_code() {
+            OpCode::U32Div => BaseElement::from(q),
+            _ => BaseElement::from(r),
+        };
+        self.shift_left(op, 2, 1)
+    }
+
+    // pops b and a, pushes a and b, a or b or a xor b
+    // the bitwise chiplet answers the bytes of [a, b, a and b, a xor b], a or b is their sum
+    fn op_bitwise(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        let (and, xor) = (a & b, a ^ b);
+
+        self.set_u32_helpers([a, b, and, xor, 0], ZERO);
+        self.u32_helpers[BITWISE_FLAG_COL][self.clk - 1] = ONE;
+        self.bitwise_checks
+            .extend((0..NUM_U32_LIMBS).map(|j| ((a >> (8 * j)) as u8, (b >> (8 * j)) as u8)));
+
+        self.registers[0][self.clk] = match op.op_code() {
+            OpCode::And => BaseElement::from(and),
+            OpCode::Or => BaseElement::from(a | b),
+            _ => BaseElement::from(xor),
+        };
+        self.shift_left(op, 2, 1)
+    }
+
+    // pops a, pushes (a * 2^n) mod 2^32, the op value is 2^n
+    fn op_shl(&mut self, op: &Operation) -> Result<(), StackError> {
+        let a = self.u32_operand(op)?;
+        let power = op.value().as_int() as u64;
+        let product = a * power;
+        let (lo, hi) = (product % U32_MODULUS, product / U32_MODULUS);
+
+        self.set_u32_helpers([a, power, lo, hi, 0], ZERO);
+
+        self.registers[0][self.clk] = BaseElement::from(lo);
+        self.copy(1);
+        Ok(())
+    }
+
+    // pops a, pushes a / 2^n, the op value is 2^n
+    // the air checks a = q * 2^n + r and r < 2^n as for the division
+    fn op_shr(&mut self, op: &Operation) -> Result<(), StackError> {
+        let a = self.u32_operand(op)?;
+        let power = op.value().as_int() as u64;
+        let (q, r) = (a / power, a % power);
+
+        self.set_u32_helpers([a, power, q, r, power - r - 1], ZERO);
+
+        self.registers[0][self.clk] = BaseElement::from(q);
+        self.copy(1);
+        Ok(())
+    }
+
+    // pops b and a, pushes a = b for eq and a != b for neq
+    // the inverse of a - b is a nondeterministic witness of the result
+    fn op_eq(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let (b, a) = (self.registers[0][self.clk - 1], self.registers[1][self.clk - 1]);
+        let inv = match a - b {
+            ZERO => ZERO,
+            diff => diff.inv(),
+        };
+        self.u32_helpers[U32_INV_COL][self.clk - 1] = inv;
+
+        self.registers[0][self.clk] = match (op.op_code(), a == b) {
+            (OpCode::Eq, true) | (OpCode::Neq, false) => ONE,
+            _ => ZERO,
+        };
+        self.shift_left(op, 2, 1)
+    }
+
+    // pops b and a, pushes a < b for lt and a <= b for lte
+    // lt is the borrow of a - b, lte the negated borrow of b - a, the air checks the u32 difference
+    fn op_lt(&mut self, op: &Operation) -> Result<(), StackError> {
+        let (b, a) = self.u32_operands(op)?;
+        let (x, y) = match op.op_code() {
+            OpCode::Lt => (a, b),
+            _ => (b, a),
+        };
+        let borrow = (x < y) as u64;
+
+        self.set_u32_helpers([a, b, x + borrow * U32_MODULUS - y, 0, 0], ZERO);
+
+        self.registers[0][self.clk] = match op.op_code() {
+            OpCode::Lt => BaseElement::from(borrow),
+            _ => BaseElement::from(1 - borrow),
+        };
+        self.shift_left(op, 2, 1)
+    }
+
+    // replaces the top four elements by their rescue permutation, the air looks it up in the hash chiplet
+    fn op_hperm(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < STATE_WIDTH {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let mut state: Vec<BaseElement> = (0..STATE_WIDTH).map(|i| self.registers[i][self.clk - 1]).collect();
+        rescue::apply_permutation(&mut state);
+
+        for (i, value) in state.into_iter().enumerate() {
+            self.registers[i][self.clk] = value;
+        }
+        self.copy(STATE_WIDTH);
+        Ok(())
+    }
+
+    // the top element, it must be a u32 value
+    fn u32_operand(&self, op: &Operation) -> Result<u64, StackError> {
+        if self.depth < 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        match u32::try_from(self.registers[0][self.clk - 1].as_int()) {
+            Ok(a) => Ok(a as u64),
+            _ => Err(StackError::not_u32(op, self.clk)),
+        }
+    }
+
+    // the two top elements, they must be u32 values
+    fn u32_operands(&self, op: &Operation) -> Result<(u64, u64), StackError> {
+        if self.depth < 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let (b, a) = (self.registers[0][self.clk - 1], self.registers[1][self.clk - 1]);
+        match (u32::try_from(b.as_int()), u32::try_from(a.as_int())) {
+            (Ok(b), Ok(a)) => Ok((b as u64, a as u64)),
+            _ => Err(StackError::not_u32(op, self.clk)),
+        }
+    }
+
+    // the helpers of the operation row, the values in little endian byte limbs
+    fn set_u32_helpers(&mut self, values: [u64; NUM_U32_VALUES], inv: BaseElement) {
+        let row = self.clk - 1;
+
+        self.u32_helpers[0][row] = ONE;
+        for (i, value) in values.iter().enumerate() {
+            for j in 0..NUM_U32_LIMBS {
+                let limb = BaseElement::from((value >> (8 * j)) as u8);
+                self.u32_helpers[1 + NUM_U32_LIMBS * i + j][row] = limb;
+                self.range_checks.push(limb);
+            }
+        }
+        self.u32_helpers[U32_INV_COL][row] = inv;
+    }
+
+    // pops c, swaps the next two elements when c = 1
+    fn op_cswap(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 3 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let (b, a) = (self.registers[1][self.clk - 1], self.registers[2][self.clk - 1]);
+        let (x, y) = if self.condition(op)? { (a, b) } else { (b, a) };
+
+        self.registers[0][self.clk] = x;
+        self.registers[1][self.clk] = y;
+        self.shift_left(op, 3, 1)
+    }
+
+    // pops c, b and a, pushes b when c = 1, a otherwise
+    fn op_csel(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 3 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let (b, a) = (self.registers[1][self.clk - 1], self.registers[2][self.clk - 1]);
+
+        self.registers[0][self.clk] = if self.condition(op)? { b } else { a };
+        self.shift_left(op, 3, 2)
+    }
+
+    fn op_cswap2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct_width = self.scheme.ciphertext_width();
+
+        if self.depth < ct_width * 2 + 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let swap = self.condition(op)?;
+
+        for i in 0..ct_width {
+            let b = self.registers[i + 1][self.clk - 1];
+            let a = self.registers[i + ct_width + 1][self.clk - 1];
+            let (x, y) = if swap { (a, b) } else { (b, a) };
+            self.registers[i][self.clk] = x;
+            self.registers[i + ct_width][self.clk] = y;
+        }
+
+        self.shift_left(op, ct_width * 2 + 1, 1)
+    }
+
+    fn op_csel2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct_width = self.scheme.ciphertext_width();
+
+        if self.depth < ct_width * 2 + 1 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let select = self.condition(op)?;
+
+        for i in 0..ct_width {
+            let b = self.registers[i + 1][self.clk - 1];
+            let a = self.registers[i + ct_width + 1][self.clk - 1];
+            self.registers[i][self.clk] = if select { b } else { a };
+        }
+
+        self.shift_left(op, ct_width * 2 + 1, ct_width + 1)
+    }
+
+    // the condition on top of the stack must be binary
+    fn condition(&self, op: &Operation) -> Result<bool, StackError> {
+        match self.registers[0][self.clk - 1] {
+            ZERO => Ok(false),
+            ONE => Ok(true),
+            _ => Err(StackError::invalid_condition(op, self.clk)),
+        }
+    }
+
     fn op_sadd(&mut self, op: &Operation) -> Result<(), StackError> {
-        let lwe_size = self.server_key.lwe_size();
+        let ct_width = self.scheme.ciphertext_width();
 
-        if self.depth < lwe_size + 1 {
+        if self.depth < ct_width + 1 {
             return Err(StackError::stack_underflow(op, self.clk));
         }
 
-        let ct: Vec<BaseElement> = (1..(lwe_size + 1))
+        let ct: Vec<BaseElement> = (1..(ct_width + 1))
             .map(|i: usize| self.registers[i][self.clk - 1])
             .collect();
 
         let scalar = self.registers[0][self.clk - 1];
 
-        let result_ct = self.server_key.scalar_add(&scalar, &FheUInt8::new(&ct));
+        let result_ct = self.scheme.scalar_add(&scalar, &FheUInt8::new(&ct));
 
         for (i, value) in result_ct.ciphertext().iter().enumerate() {
             self.registers[i][self.clk] = *value;
         }
 
-        self.shift_left(op, lwe_size + 1, 1)
+        self.shift_left(op, ct_width + 1, 1)
     }
 
     fn op_smul(&mut self, op: &Operation) -> Result<(), StackError> {
-        let lwe_size = self.server_key.lwe_size();
+        let ct_width = self.scheme.ciphertext_width();
 
-        if self.depth < lwe_size + 1 {
+        if self.depth < ct_width + 1 {
             return Err(StackError::stack_underflow(op, self.clk));
         }
 
-        let ct: Vec<BaseElement> = (1..(lwe_size + 1))
+        let ct: Vec<BaseElement> = (1..(ct_width + 1))
             .map(|i: usize| self.registers[i][self.clk - 1])
             .collect();
 
         let scalar = self.registers[0][self.clk - 1];
 
-        let result_ct = self.server_key.scalar_mul(&scalar, &FheUInt8::new(&ct));
+        let result_ct = self.scheme.scalar_mul(&scalar, &FheUInt8::new(&ct));
 
         for (i, value) in result_ct.ciphertext().iter().enumerate() {
             self.registers[i][self.clk] = *value;
         }
 
-        self.shift_left(op, lwe_size + 1, 1)
+        self.shift_left(op, ct_width + 1, 1)
     }
 
     fn op_add2(&mut self, op: &Operation) -> Result<(), StackError> {
-        let lwe_size = self.server_key.lwe_size();
+        let ct_width = self.scheme.ciphertext_width();
 
-        if self.depth < lwe_size * 2 {
+        if self.depth < ct_width * 2 {
             return Err(StackError::stack_underflow(op, self.clk));
         }
 
-        let ct0: Vec<BaseElement> = (0..lwe_size).map(|i: usize| self.registers[i][self.clk - 1]).collect();
-        let ct1: Vec<BaseElement> = (0..lwe_size)
-            .map(|i: usize| self.registers[i + lwe_size][self.clk - 1])
+        let ct0: Vec<BaseElement> = (0..ct_width).map(|i: usize| self.registers[i][self.clk - 1]).collect();
+        let ct1: Vec<BaseElement> = (0..ct_width)
+            .map(|i: usize| self.registers[i + ct_width][self.clk - 1])
             .collect();
 
-        let result_ct = self.server_key.add(&FheUInt8::new(&ct0), &FheUInt8::new(&ct1));
+        let result_ct = self.scheme.add(&FheUInt8::new(&ct0), &FheUInt8::new(&ct1));
 
         for (i, value) in result_ct.ciphertext().iter().enumerate() {
             self.registers[i][self.clk] = *value;
         }
 
-        self.shift_left(op, lwe_size * 2, lwe_size)
+        self.shift_left(op, ct_width * 2, ct_width)
+    }
+
+    fn op_dup(&mut self, op: &Operation, n: usize) -> Result<(), StackError> {
+        if self.depth <= n {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        let value = self.registers[n][self.clk - 1];
+        self.shift_right(0, 1);
+        self.registers[0][self.clk] = value;
+        Ok(())
+    }
+
+    fn op_swap(&mut self, op: &Operation) -> Result<(), StackError> {
+        if self.depth < 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        self.registers[0][self.clk] = self.registers[1][self.clk - 1];
+        self.registers[1][self.clk] = self.registers[0][self.clk - 1];
+        self.copy(2);
+        Ok(())
+    }
+
+    fn op_drop(&mut self, op: &Operation) -> Result<(), StackError> {
+        self.shift_left(op, 1, 1)
+    }
+
+    fn op_movup(&mut self, op: &Operation, n: usize) -> Result<(), StackError> {
+        if self.depth <= n {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        self.registers[0][self.clk] = self.registers[n][self.clk - 1];
+        for i in 0..n {
+            self.registers[i + 1][self.clk] = self.registers[i][self.clk - 1];
+        }
+        self.copy(n + 1);
+        Ok(())
+    }
+
+    fn op_movdn(&mut self, op: &Operation, n: usize) -> Result<(), StackError> {
+        if self.depth <= n {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        for i in 0..n {
+            self.registers[i][self.clk] = self.registers[i + 1][self.clk - 1];
+        }
+        self.registers[n][self.clk] = self.registers[0][self.clk - 1];
+        self.copy(n + 1);
+        Ok(())
+    }
+
+    fn op_dup2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct_width = self.scheme.ciphertext_width();
+
+        if self.depth < ct_width {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        self.shift_right(0, ct_width);
+        for i in 0..ct_width {
+            self.registers[i][self.clk] = self.registers[i][self.clk - 1];
+        }
+        Ok(())
+    }
+
+    fn op_swap2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct_width = self.scheme.ciphertext_width();
+
+        if self.depth < ct_width * 2 {
+            return Err(StackError::stack_underflow(op, self.clk));
+        }
+
+        for i in 0..ct_width {
+            self.registers[i][self.clk] = self.registers[i + ct_width][self.clk - 1];
+            self.registers[i + ct_width][self.clk] = self.registers[i][self.clk - 1];
+        }
+        self.copy(ct_width * 2);
+        Ok(())
+    }
+
+    fn op_drop2(&mut self, op: &Operation) -> Result<(), StackError> {
+        let ct_width = self.scheme.ciphertext_width();
+        self.shift_left(op, ct_width, ct_width)
+    }
+
+    // copy the stack values from start to the current depth
+    fn copy(&mut self, start: usize) {
+        for i in start..self.top() {
+            self.registers[i][self.clk] = self.registers[i][self.clk - 1];
+        }
     }
 
     fn shift_left(&mut self, op: &Operation, start: usize, pos_count: usize) -> Result<(), StackError> {
@@ -222,37 +874,63 @@ impl<'a> Stack<'a> {
             return Err(StackError::stack_underflow(op, self.clk));
         }
 
+        let top = self.top();
+
         // shift all values by pos_count to the left
-        for i in start..self.depth {
+        for i in start..top {
             self.registers[i - pos_count][self.clk] = self.registers[i][self.clk - 1];
         }
 
-        // set all "shifted-in" slots to 0
-        for i in (self.depth - pos_count)..self.depth {
-            self.registers[i][self.clk] = ZERO;
+        // refill the "shifted-in" slots from the overflow table, 0 once it is empty
+        for i in (top - pos_count)..top {
+            self.registers[i][self.clk] = self.overflow.pop().unwrap_or(ZERO);
         }
 
         // stack depth has been reduced by pos_count
         self.depth -= pos_count;
 
+        self.record_overflow(pos_count, false);
+
         Ok(())
     }
 
-    fn shift_right(&mut self, op: &Operation, start: usize, pos_count: usize) -> Result<(), StackError> {
+    fn shift_right(&mut self, start: usize, pos_count: usize) {
+        // set all "shifted-in" slots to clk' - 1, the registers past the bottom move to the overflow table
+        for i in (start..self.top()).rev() {
+            match i + pos_count < STACK_WIDTH {
+                true => self.registers[i + pos_count][self.clk] = self.registers[i][self.clk - 1],
+                false => self.overflow.push(self.registers[i][self.clk - 1]),
+            }
+        }
+
+        self.record_overflow(pos_count, true);
+
         // stack depth has been increased by pos_count
         self.depth += pos_count;
+    }
 
-        // allocate new registers to the stack and increase the stack mac depth
-        if self.depth > MAX_STACK_DEPTH {
-            return Err(StackError::stack_overflow(op, self.clk));
+    // a shift with more than 16 elements in the deeper state flags the slots of the moved registers
+    // slot m moves register 15 - m at OVERFLOW_ADDR + m + the depth of the shallower state
+    // the depth is the current one, right shifts write the previous registers and left shifts read the new ones
+    fn record_overflow(&mut self, pos_count: usize, write: bool) {
+        if self.depth + pos_count <= STACK_WIDTH {
+            return;
         }
 
-        // set all "shifted-in" slots to clk' - 1
-        for i in start..(self.depth - pos_count) {
-            self.registers[i + pos_count][self.clk] = self.registers[i][self.clk - 1];
+        let row = if write { self.clk - 1 } else { self.clk };
+
+        for m in 0..pos_count {
+            self.overflow_helpers[NUM_FREE_LIMBS + NUM_ROOM_LIMBS + m][self.clk - 1] = ONE;
+
+            let addr = OVERFLOW_ADDR + (self.depth + m) as u128;
+            let value = self.registers[STACK_WIDTH - 1 - m][row];
+            self.overflow_accesses.push((addr, write, value));
         }
+    }
 
-        Ok(())
+    // registers holding stack elements
+    fn top(&self) -> usize {
+        self.depth.min(STACK_WIDTH)
     }
 
     // Ensure there is enough memory allocated for the trace to accommodate a new row.
@@ -267,6 +945,12 @@ impl<'a> Stack<'a> {
             for col in self.helpers.iter_mut() {
                 col.resize(self.trace_length, ZERO);
             }
+            for col in self.overflow_helpers.iter_mut() {
+                col.resize(self.trace_length, ZERO);
+            }
+            for col in self.u32_helpers.iter_mut() {
+                col.resize(self.trace_length, ZERO);
+            }
         }
     }
 
@@ -275,7 +959,26 @@ impl<'a> Stack<'a> {
         self.clk += 1;
     }
 
+    // the free registers are capped to 15, an empty stack never is the deeper state of a shift
     fn set_helpers(&mut self) {
         self.helpers[0][self.clk] = BaseElement::from(self.depth as u32);
+
+        let free = STACK_WIDTH.saturating_sub(self.depth).min(15);
+        for i in 0..NUM_FREE_LIMBS {
+            self.overflow_helpers[i][self.clk] = BaseElement::from((free >> (2 * i) & 0b11) as u8);
+        }
+
+        let room = MAX_STACK_DEPTH.saturating_sub(self.depth);
+        for i in 0..NUM_ROOM_LIMBS {
+            self.overflow_helpers[NUM_FREE_LIMBS + i][self.clk] = BaseElement::from((room >> (2 * i) & 0b11) as u8);
+        }
+    }
+
+    // overflow elements past the maximum depth would reach the fhe chiplet elements of the memory table
+    fn check_depth(&self, op: &Operation) -> Result<(), StackError> {
+        match self.depth > MAX_STACK_DEPTH {
+            true => Err(StackError::stack_overflow(op, self.clk)),
+            false => Ok(()),
+        }
     }
 }
@@ -12,6 +12,10 @@ impl System {
         System { clk: 0, clk_trace }
     }
 
+    pub fn clk(&self) -> usize {
+        self.clk
+    }
+
     pub fn trace_length(&self) -> usize {
         self.clk_trace.len()
     }
@@ -0,0 +1,50 @@
+use super::*;
+
+#[test]
+fn test_trace_length() {
+    let mut bitwise = BitwiseChiplet::new();
+
+    assert_eq!(bitwise.trace_length(), 1);
+
+    bitwise.check(&[(0xa5, 0x0f), (0xff, 0x00)]);
+
+    // two lookups and the last row
+    assert_eq!(bitwise.trace_length(), 3);
+}
+
+#[test]
+fn test_trace() {
+    let mut bitwise = BitwiseChiplet::new();
+
+    bitwise.check(&[(0xa5, 0x0f)]);
+
+    let trace = bitwise.into_trace(4);
+
+    // the lookup flag, the bits of a and b least significant first
+    assert_eq!(trace[0], to_elements(&[1, 0, 0, 0]));
+    assert_eq!(
+        trace.iter().map(|col| col[0]).collect::<Vec<_>>(),
+        to_elements(&[1, 1, 0, 1, 0, 0, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0])
+    );
+
+    // padding rows answer no lookup
+    for col in &trace {
+        assert_eq!(col[1..], [ZERO; 3]);
+    }
+}
+
+#[test]
+fn test_execute_bitwise_ops() {
+    let source = "push.0x1234\npush.0xff\nand\npush.3\nxor";
+    let program = Program::compile(source).unwrap();
+
+    let server_key = server_key();
+    let inputs = empty_inputs(&server_key);
+
+    let processor = Processor::run(&program, &inputs).unwrap();
+
+    assert_eq!(processor.output()[0], to_element(0x37));
+
+    // four byte lookups per operation
+    assert_eq!(processor.bitwise.trace_length(), 9);
+}
@@ -1,4 +1,7 @@
-use crypto::{rescue::CYCLE_LENGTH, Rescue128};
+use crypto::{
+    rescue::{self, CYCLE_LENGTH, NUM_ROUNDS},
+    Rescue128,
+};
 use errors::ChipletsError;
 
 use super::*;
@@ -10,7 +13,7 @@ fn test_hash_operation() {
     let mut sponge = Rescue128::new();
 
     for _ in 0..14 {
-        chiplets.hash_op(&Operation::push(2)).unwrap();
+        chiplets.hash_op(&Operation::push(to_element(2))).unwrap();
     }
 
     for _ in 0..2 {
@@ -23,7 +26,7 @@ fn test_hash_operation() {
         let trace = trace_state(i, &chiplets_trace);
         assert_eq!(trace[0], ONE);
         assert_eq!(trace[1..5], sponge.state());
-        let op = Operation::push(2);
+        let op = Operation::push(to_element(2));
         sponge.update(op.code(), op.value());
     }
 
@@ -46,7 +49,7 @@ fn test_fill_trace_with_last_value() {
     let mut chiplets = Chiplets::new(8);
 
     for _ in 0..14 {
-        chiplets.hash_op(&Operation::push(2)).unwrap();
+        chiplets.hash_op(&Operation::push(to_element(2))).unwrap();
     }
 
     for _ in 0..2 {
@@ -68,10 +71,10 @@ fn test_invalid_operation_error() {
     let mut chiplets = Chiplets::new(8);
 
     for _ in 0..14 {
-        chiplets.hash_op(&Operation::push(2)).unwrap();
+        chiplets.hash_op(&Operation::push(to_element(2))).unwrap();
     }
 
-    let op = Operation::push(2);
+    let op = Operation::push(to_element(2));
 
     let error = chiplets.hash_op(&op).unwrap_err();
 
@@ -86,7 +89,7 @@ fn test_invalid_trace_length_error() {
     let mut chiplets = Chiplets::new(8);
 
     for _ in 0..8 {
-        chiplets.hash_op(&Operation::push(2)).unwrap();
+        chiplets.hash_op(&Operation::push(to_element(2))).unwrap();
     }
 
     let error = chiplets.into_trace(32).unwrap_err();
@@ -96,3 +99,44 @@ fn test_invalid_trace_length_error() {
         format!("{}", ChipletsError::invalid_trace_length(CYCLE_LENGTH, 8, 8))
     );
 }
+
+#[test]
+fn test_permutation() {
+    let mut chiplets = Chiplets::new(8);
+
+    let input = to_elements(&[1, 2, 3, 4]);
+
+    for _ in 0..16 {
+        chiplets.hash_op(&Operation::noop()).unwrap();
+    }
+
+    chiplets.permute(&input);
+    chiplets.permute(&input);
+
+    // two permutations take two hash cycles
+    assert_eq!(chiplets.trace_length(), 2 * CYCLE_LENGTH);
+
+    let chiplets_trace = chiplets.into_trace(64).unwrap();
+
+    let mut state = input.clone();
+
+    for i in 0..CYCLE_LENGTH {
+        let trace_row = trace_state(CYCLE_LENGTH + i, &chiplets_trace);
+
+        assert_eq!(trace_row[9], ONE);
+        assert_eq!(trace_row[10..14], state);
+        assert_eq!(trace_row[14..18], input);
+
+        if i < NUM_ROUNDS {
+            rescue::apply_round(&mut state, 0, ZERO, i);
+        }
+    }
+
+    let mut output = input.clone();
+    rescue::apply_permutation(&mut output);
+
+    assert_eq!(trace_state(NUM_ROUNDS, &chiplets_trace)[10..14], output);
+
+    // padding cycles are zeros
+    assert_eq!(trace_state(2 * CYCLE_LENGTH, &chiplets_trace)[9..18], [ZERO; 9]);
+}
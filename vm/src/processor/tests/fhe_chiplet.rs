@@ -0,0 +1,156 @@
+use super::*;
+
+#[test]
+fn test_passes() {
+    let mut fhe = FheChiplet::new(2);
+
+    fhe.read(&to_elements(&[1, 2]));
+    fhe.read(&to_elements(&[3, 4]));
+
+    // ct_add [h0, h1]
+    fhe.execute(&Operation::ct_add(), &to_elements(&[0, 1]), 3).unwrap();
+    assert_eq!(fhe.ciphertext(to_element(2)).unwrap(), to_elements(&[4, 6]));
+
+    // ct_smul [3, h2]
+    fhe.execute(&Operation::ct_smul(), &to_elements(&[3, 2]), 4).unwrap();
+    assert_eq!(fhe.ciphertext(to_element(3)).unwrap(), to_elements(&[12, 18]));
+
+    // ct_mac [2, h0, h3]
+    fhe.execute(&Operation::ct_mac(), &to_elements(&[2, 0, 3]), 5).unwrap();
+    assert_eq!(fhe.ciphertext(to_element(4)).unwrap(), to_elements(&[14, 22]));
+
+    assert_eq!(fhe.next_handle(), to_element(5));
+    assert!(fhe.ciphertext(to_element(5)).is_none());
+}
+
+#[test]
+fn test_accesses() {
+    let mut fhe = FheChiplet::new(2);
+
+    fhe.read(&to_elements(&[1, 2]));
+
+    // the read pass writes its elements at 3h + 2
+    let addr = 1 << 17;
+    assert_eq!(
+        fhe.accesses(),
+        [(addr, 2, true, to_element(1)), (addr + 1, 2, true, to_element(2))]
+    );
+
+    fhe.read(&to_elements(&[3, 4]));
+    fhe.execute(&Operation::ct_add(), &to_elements(&[0, 1]), 3).unwrap();
+
+    // a is read at 3h, b at 3h + 1 and c is written at 3h + 2
+    assert_eq!(
+        fhe.accesses(),
+        [
+            (addr, 6, false, to_element(1)),
+            (addr + 2, 7, false, to_element(3)),
+            (addr + 1, 6, false, to_element(2)),
+            (addr + 3, 7, false, to_element(4)),
+            (addr + 4, 8, true, to_element(4)),
+            (addr + 5, 8, true, to_element(6)),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_handle() {
+    let mut fhe = FheChiplet::new(2);
+
+    fhe.read(&to_elements(&[1, 2]));
+
+    assert!(fhe.execute(&Operation::ct_add(), &to_elements(&[0, 1]), 2).is_err());
+    assert!(fhe.execute(&Operation::ct_smul(), &to_elements(&[2, 1]), 2).is_err());
+
+    // ct_smul ignores s2
+    assert!(fhe.execute(&Operation::ct_smul(), &to_elements(&[2, 0, 7]), 2).is_ok());
+}
+
+#[test]
+fn test_trace() {
+    let mut fhe = FheChiplet::new(2);
+
+    fhe.read(&to_elements(&[1, 2]));
+    fhe.execute(&Operation::ct_smul(), &to_elements(&[3, 0]), 2).unwrap();
+
+    // 2 passes of 4 rows and the last row
+    assert_eq!(fhe.trace_length(), 9);
+
+    let trace = fhe.into_trace(16);
+
+    // active | mac | use b | index | handle | handle a | handle b | scalar | a | b | c
+    assert_eq!(trace_state(1, &trace), to_elements(&[1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 2]));
+    assert_eq!(trace_state(3, &trace), to_elements(&[1, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]));
+    assert_eq!(trace_state(4, &trace), to_elements(&[1, 1, 0, 0, 1, 0, 0, 3, 1, 0, 3]));
+    assert_eq!(trace_state(5, &trace), to_elements(&[1, 1, 0, 1, 1, 0, 0, 3, 2, 0, 6]));
+
+    // padding passes
+    assert_eq!(trace_state(13, &trace), to_elements(&[0, 0, 0, 1, 3, 0, 0, 0, 0, 0, 0]));
+}
+
+#[test]
+fn test_execute_handles() {
+    let source = "ct_read\nct_read\nct_add\npush.2\nct_smul";
+    let program = Program::compile(source).unwrap();
+
+    let server_key = server_key();
+    let values = values(&server_key);
+    let inputs = inputs(&values, &server_key);
+
+    let processor = Processor::run(&program, &inputs).unwrap();
+
+    // the handles replace the ciphertexts on the stack
+    assert_eq!(processor.output()[..2], to_elements(&[3, 0]));
+    assert_eq!(processor.stack.current_stack_state()[0], to_element(3));
+
+    let x = values.1[0].ciphertext();
+    let y = values.1[1].ciphertext();
+    let expected: Vec<BaseElement> = (0..5).map(|i| (x[i] + y[i]) * to_element(2)).collect();
+
+    assert_eq!(processor.ciphertext_output().unwrap(), expected);
+    assert_eq!(server_key.decrypt(&FheUInt8::new(&expected)), (33 + 7) * 2);
+}
+
+#[test]
+fn test_invalid_output_handle() {
+    let program = Program::compile("ct_read\npush.4").unwrap();
+
+    let server_key = server_key();
+    let values = values(&server_key);
+    let inputs = inputs(&values, &server_key);
+
+    let processor = Processor::run(&program, &inputs).unwrap();
+
+    assert!(processor.ciphertext_output().is_err());
+}
+
+#[test]
+fn test_wide_ciphertext_on_stack() {
+    let parameters = LweParameters::new(8u32, 128u32, 8, 2.412_390_240_121_573e-5);
+    let server_key = ServerKey::new(parameters);
+
+    let secret_inputs = [server_key.encrypt(1u8)];
+    let inputs = ProgramInputs::new(&[], &secret_inputs, &server_key);
+
+    // ciphertexts of 9 elements only live behind handles
+    assert!(Processor::run(&Program::compile("read2").unwrap(), &inputs).is_err());
+    assert!(Processor::run(&Program::compile("ct_read").unwrap(), &inputs).is_ok());
+
+    // the program is rejected before it runs, even when the branch with the stack operation is not taken
+    let program = Program::compile("push.1\nif.true\nct_read\nelse\nread2\nend").unwrap();
+    let error = Processor::run(&program, &inputs).err().unwrap();
+    let pos = program
+        .code()
+        .iter()
+        .position(|op| op.op_code() == OpCode::Read2)
+        .unwrap();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", StackError::ciphertext_width(&Operation::read2(), 9, pos + 1))
+    );
+
+    // the stack instructions compile to operations on handles
+    let program = Program::compile_with_handles("read2\ndup2", &[]).unwrap();
+    assert!(Processor::run(&program, &inputs).is_ok());
+}
@@ -0,0 +1,62 @@
+use super::*;
+
+#[test]
+fn test_read_unwritten_word() {
+    let mut memory = Memory::new(5);
+
+    assert_eq!(memory.read(&Operation::mem_load(to_element(3)), 1), [ZERO]);
+    assert_eq!(memory.read(&Operation::mem_load2(to_element(3)), 2), [ZERO; 5]);
+}
+
+#[test]
+fn test_write_read() {
+    let mut memory = Memory::new(5);
+
+    let stack = to_elements(&[1, 2, 3, 4, 5, 6]);
+
+    memory.write(&Operation::mem_store2(to_element(0)), 1, &stack);
+    memory.write(&Operation::mem_store(to_element(1)), 2, &stack);
+
+    assert_eq!(memory.read(&Operation::mem_load2(to_element(0)), 3), stack[..5]);
+    assert_eq!(memory.read(&Operation::mem_load(to_element(1)), 4), [to_element(1)]);
+
+    // scalar loads read the first element of a ciphertext word
+    assert_eq!(memory.read(&Operation::mem_load(to_element(0)), 5), [to_element(1)]);
+
+    // scalar stores clear the word but the first element
+    memory.write(&Operation::mem_store(to_element(0)), 6, &stack);
+    assert_eq!(
+        memory.read(&Operation::mem_load2(to_element(0)), 7),
+        to_elements(&[1, 0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn test_trace_sorted_by_address() {
+    let mut memory = Memory::new(5);
+
+    let stack = to_elements(&[7, 0, 0, 0, 0]);
+
+    memory.write(&Operation::mem_store(to_element(9)), 1, &stack);
+    memory.read(&Operation::mem_load(to_element(2)), 4);
+    memory.read(&Operation::mem_load(to_element(9)), 6);
+
+    let trace = memory.into_trace(8);
+
+    // addr | clk | write | element | access | new address
+    assert_eq!(trace_state(0, &trace)[..6], to_elements(&[0, 0, 0, 0, 0, 0]));
+    assert_eq!(trace_state(1, &trace)[..6], to_elements(&[2, 4, 0, 1, 1, 1]));
+    assert_eq!(trace_state(2, &trace)[..6], to_elements(&[9, 1, 1, 1, 1, 1]));
+    assert_eq!(trace_state(3, &trace)[..6], to_elements(&[9, 6, 0, 1, 1, 0]));
+    assert_eq!(trace_state(4, &trace)[..6], to_elements(&[9, 7, 0, 0, 0, 0]));
+
+    // address delta 2 - 0 - 1, address delta 9 - 2 - 1, clock delta 6 - 1 - 1 in base 4
+    assert_eq!(trace_state(0, &trace)[6..8], to_elements(&[1, 0]));
+    assert_eq!(trace_state(1, &trace)[6..8], to_elements(&[2, 1]));
+    assert_eq!(trace_state(2, &trace)[6..8], to_elements(&[0, 1]));
+
+    // the word is kept by the read and the padding
+    for row in 2..8 {
+        assert_eq!(trace_state(row, &trace)[15], to_element(7));
+    }
+}
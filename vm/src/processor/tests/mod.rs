@@ -16,6 +16,18 @@ mod system;
 #[cfg(test)]
 mod chiplets;
 
+#[cfg(test)]
+mod memory;
+
+#[cfg(test)]
+mod fhe_chiplet;
+
+#[cfg(test)]
+mod range_chiplet;
+
+#[cfg(test)]
+mod bitwise_chiplet;
+
 #[test]
 fn test_trace() {
     let source = "push.5\npush.3\nadd";
@@ -31,15 +43,43 @@ fn test_trace() {
 
     assert_eq!(trace_row31[0], to_element(31));
 
-    assert_eq!(trace_row31[1..6], to_elements(&[0, 0, 0, 0, 0]));
+    assert_eq!(trace_row31[1..9], to_elements(&[0, 0, 0, 0, 0, 0, 0, 0]));
+
+    assert_eq!(trace_row31[9], to_element(0));
+
+    assert_eq!(trace_row31[10..12], program.hash().to_elements());
+    assert_eq!(trace_row31[12..14], [ZERO, ZERO]);
+
+    assert_eq!(trace_row31[14..18], [ZERO, ZERO, ZERO, ZERO]);
+
+    // no hperm
+    assert_eq!(trace_row31[18..27], [ZERO; 9]);
+
+    // memory padding keeps address 0 with an increasing clock
+    assert_eq!(trace_row31[27..29], [ZERO, to_element(31)]);
+    assert_eq!(trace_row31[29..58], [ZERO; 29]);
+
+    // padding passes of 8 rows keep taking handles with all flags off
+    assert_eq!(trace_row31[58..61], [ZERO; 3]);
+    assert_eq!(trace_row31[61..63], to_elements(&[7, 3]));
+    assert_eq!(trace_row31[63..69], [ZERO; 6]);
+
+    // a trace without lookups keeps the range chiplet at 0
+    assert_eq!(trace_row31[69..71], [ZERO; 2]);
+
+    // no bitwise lookup
+    assert_eq!(trace_row31[71..88], [ZERO; 17]);
 
-    assert_eq!(trace_row31[6], to_element(0));
+    assert_eq!(trace_row31[88], to_element(1));
+    assert_eq!(trace_row31[89], to_element(8));
 
-    assert_eq!(trace_row31[7..9], program.hash().to_elements());
-    assert_eq!(trace_row31[9..11], [ZERO, ZERO]);
+    // 15 free registers, room for 65520 - 1 elements and no overflow slots
+    assert_eq!(trace_row31[105..107], to_elements(&[3, 3]));
+    assert_eq!(trace_row31[107..115], to_elements(&[3, 3, 2, 3, 3, 3, 3, 3]));
+    assert_eq!(trace_row31[115..123], [ZERO; 8]);
 
-    assert_eq!(trace_row31[11], to_element(1));
-    assert_eq!(trace_row31[12], to_element(8));
+    // no u32 operation
+    assert_eq!(trace_row31[123..146], [ZERO; 23]);
 }
 
 fn server_key() -> ServerKey {
@@ -68,7 +108,7 @@ fn inputs<'a>(inputs: &'a ([u8; 2], [FheUInt8; 2]), server_key: &'a ServerKey) -
     ProgramInputs::new(&inputs.0, &inputs.1, server_key)
 }
 
-fn empty_inputs(server_key: &ServerKey) -> ProgramInputs {
+fn empty_inputs(server_key: &ServerKey) -> ProgramInputs<'_> {
     ProgramInputs::new(&[], &[], server_key)
 }
 
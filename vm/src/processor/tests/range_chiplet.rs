@@ -0,0 +1,56 @@
+use super::*;
+
+#[test]
+fn test_trace_length() {
+    let mut range = RangeChiplet::new();
+
+    assert_eq!(range.trace_length(), 1);
+
+    range.check(&to_elements(&[3, 3, 255]));
+
+    // 256 filler rows, 3 lookups and the last row
+    assert_eq!(range.trace_length(), 260);
+}
+
+#[test]
+fn test_trace() {
+    let mut range = RangeChiplet::new();
+
+    range.check(&to_elements(&[3, 0, 3]));
+
+    let trace = range.into_trace(512);
+
+    // each lookup follows the filler row of its value
+    assert_eq!(trace[0][..7], to_elements(&[0, 0, 1, 2, 3, 3, 3]));
+    assert_eq!(trace[1][..7], to_elements(&[0, 1, 0, 0, 0, 1, 1]));
+
+    // the table ends at 255 and pads it
+    assert_eq!(trace[0][258], to_element(255));
+    assert_eq!(trace[0][511], to_element(255));
+    assert_eq!(trace[1][258..], [ZERO; 254]);
+}
+
+#[test]
+fn test_short_trace() {
+    let trace = RangeChiplet::new().into_trace(16);
+
+    assert_eq!(trace, [[ZERO; 16], [ZERO; 16]]);
+}
+
+#[test]
+fn test_execute_u32_ops() {
+    let source = "push.70000\npush.3\nu32mul\ndrop\npush.4\nu32mod";
+    let program = Program::compile(source).unwrap();
+
+    let server_key = server_key();
+    let inputs = empty_inputs(&server_key);
+
+    let processor = Processor::run(&program, &inputs).unwrap();
+
+    // 210000 mod 4
+    assert_eq!(processor.output()[0], to_element(0));
+
+    // two u32 rows of 20 limbs
+    assert_eq!(processor.range.trace_length(), 256 + 40 + 1);
+    assert_eq!(processor.trace().unwrap()[0].len(), 512);
+}
@@ -11,8 +11,8 @@ fn test_fill_trace_with_noop() {
     let mut stack = Stack::new(&inputs, 8);
 
     for _ in 0..4 {
-        stack.execute_op(&Operation::push(2)).unwrap();
-        stack.execute_op(&Operation::push(2)).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
         stack.execute_op(&Operation::add()).unwrap();
     }
 
@@ -35,8 +35,8 @@ mod mul {
 
         let mut stack = Stack::new(&inputs, 8);
 
-        stack.execute_op(&Operation::push(2)).unwrap();
-        stack.execute_op(&Operation::push(2)).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
         stack.execute_op(&Operation::mul()).unwrap();
 
         let stack_trace = stack.into_trace(8);
@@ -58,7 +58,7 @@ mod mul {
 
         let mut stack = Stack::new(&inputs, 8);
 
-        stack.execute_op(&Operation::push(2)).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
 
         let op = Operation::mul();
 
@@ -80,8 +80,8 @@ mod add {
 
         let mut stack = Stack::new(&inputs, 8);
 
-        stack.execute_op(&Operation::push(2)).unwrap();
-        stack.execute_op(&Operation::push(2)).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
         stack.execute_op(&Operation::add()).unwrap();
 
         let stack_trace = stack.into_trace(8);
@@ -103,7 +103,7 @@ mod add {
 
         let mut stack = Stack::new(&inputs, 8);
 
-        stack.execute_op(&Operation::push(2)).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
 
         let op = Operation::add();
 
@@ -148,7 +148,7 @@ mod push {
 
         let mut stack = Stack::new(&inputs, 8);
 
-        stack.execute_op(&Operation::push(5)).unwrap();
+        stack.execute_op(&Operation::push(to_element(5))).unwrap();
 
         let stack_trace = stack.into_trace(8);
 
@@ -160,6 +160,56 @@ mod push {
 
         assert_eq!(trace_row1[1], to_element(5));
     }
+
+    #[test]
+    fn test_stack_overflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        let op = Operation::push(to_element(5));
+
+        for _ in 0..MAX_STACK_DEPTH {
+            stack.execute_op(&op).unwrap();
+        }
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", StackError::stack_overflow(&op, MAX_STACK_DEPTH + 1))
+        );
+    }
+}
+
+mod push2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push2(to_element(5))).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row2 = trace_state(2, &stack_trace);
+
+        assert_eq!(trace_row2[0], to_element(6));
+
+        let result_ct = server_key.encrypt_trivial(&to_element(5)).ciphertext().to_vec();
+
+        assert_eq!(trace_row2[1..6], result_ct);
+        assert_eq!(trace_row2[6], to_element(1));
+    }
 }
 
 mod read {
@@ -270,7 +320,7 @@ mod sadd {
 
         let scalar = BaseElement::from(inputs.public()[0]);
 
-        let result = inputs.server_key().scalar_add(&scalar, &inputs.secret()[0]);
+        let result = inputs.scheme().scalar_add(&scalar, &inputs.secret()[0]);
         let result_ct = result.ciphertext().to_vec();
 
         assert_eq!(trace_row3[1..6], result_ct);
@@ -320,7 +370,7 @@ mod smul {
 
         let scalar = BaseElement::from(inputs.public()[0]);
 
-        let result = inputs.server_key().scalar_mul(&scalar, &inputs.secret()[0]);
+        let result = inputs.scheme().scalar_mul(&scalar, &inputs.secret()[0]);
         let result_ct = result.ciphertext().to_vec();
 
         assert_eq!(trace_row3[1..6], result_ct);
@@ -368,7 +418,7 @@ mod add2 {
         assert_eq!(trace_row2[0], to_element(10));
         assert_eq!(trace_row3[0], to_element(5));
 
-        let result = inputs.server_key().add(&inputs.secret()[0], &inputs.secret()[1]);
+        let result = inputs.scheme().add(&inputs.secret()[0], &inputs.secret()[1]);
         let result_ct = result.ciphertext().to_vec();
 
         assert_eq!(trace_row3[1..6], result_ct);
@@ -391,3 +441,1066 @@ mod add2 {
         assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
     }
 }
+
+mod dup {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(3))).unwrap();
+        stack.execute_op(&Operation::dup(1)).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[0], to_element(3));
+        assert_eq!(trace_row3[1..4], to_elements(&[2, 3, 2]));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+
+        let op = Operation::dup(1);
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        for i in 0..16 {
+            stack.execute_op(&Operation::push(to_element(i))).unwrap();
+        }
+
+        // the bottom register moves to the first overflow address
+        stack.execute_op(&Operation::dup(0)).unwrap();
+
+        assert_eq!(stack.overflow_accesses(), [((1 << 16) + 16, true, to_element(0))]);
+
+        let stack_trace = stack.into_trace(32);
+
+        let trace_row17 = trace_state(17, &stack_trace);
+
+        assert_eq!(trace_row17[0], to_element(17));
+        assert_eq!(trace_row17[1..3], to_elements(&[15, 15]));
+        assert_eq!(trace_row17[16], to_element(1));
+
+        // free register limbs, room limbs of 65520 - 15 and the flagged slot of the dup
+        assert_eq!(trace_state(15, &stack_trace)[17..19], to_elements(&[1, 0]));
+        assert_eq!(trace_state(16, &stack_trace)[17..19], to_elements(&[0, 0]));
+        assert_eq!(
+            trace_state(15, &stack_trace)[19..27],
+            to_elements(&[1, 0, 2, 3, 3, 3, 3, 3])
+        );
+        assert_eq!(trace_state(15, &stack_trace)[27..35], [ZERO; 8]);
+        assert_eq!(
+            trace_state(16, &stack_trace)[27..35],
+            to_elements(&[1, 0, 0, 0, 0, 0, 0, 0])
+        );
+    }
+}
+
+mod swap {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(3))).unwrap();
+        stack.execute_op(&Operation::swap()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(3));
+        assert_eq!(trace_row4[1..4], to_elements(&[2, 3, 1]));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+
+        let op = Operation::swap();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+mod drop {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::drop()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[0], to_element(1));
+        assert_eq!(trace_row3[1..3], to_elements(&[1, 0]));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        let op = Operation::drop();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 1)));
+    }
+}
+
+mod movup {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        for value in 1..5 {
+            stack.execute_op(&Operation::push(to_element(value))).unwrap();
+        }
+        stack.execute_op(&Operation::movup(3)).unwrap();
+        stack.execute_op(&Operation::movdn(2)).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row5 = trace_state(5, &stack_trace);
+        let trace_row6 = trace_state(6, &stack_trace);
+
+        assert_eq!(trace_row5[1..5], to_elements(&[1, 4, 3, 2]));
+        assert_eq!(trace_row6[1..5], to_elements(&[4, 3, 1, 2]));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+
+        let op = Operation::movdn(2);
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 3)));
+    }
+}
+
+mod dup2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::dup2()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row2 = trace_state(2, &stack_trace);
+
+        assert_eq!(trace_row2[0], to_element(10));
+
+        let input_ct = inputs.secret()[0].ciphertext().to_vec();
+
+        assert_eq!(trace_row2[1..6], input_ct);
+        assert_eq!(trace_row2[6..11], input_ct);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::dup2()).unwrap();
+        stack.execute_op(&Operation::dup2()).unwrap();
+
+        // the deepest ciphertext is in the overflow table, the two addresses below the stack bottom get zeros
+        let input_ct = inputs.secret()[0].ciphertext();
+        let accesses: Vec<_> = (0..5)
+            .map(|m| {
+                (
+                    (1 << 16) + 15 + m as u128,
+                    true,
+                    if m < 1 { ZERO } else { input_ct[5 - m] },
+                )
+            })
+            .collect();
+
+        assert_eq!(stack.overflow_accesses(), accesses);
+
+        // dropping them back reads the ciphertext from the overflow table
+        stack.execute_op(&Operation::drop2()).unwrap();
+
+        let accesses: Vec<_> = accesses
+            .into_iter()
+            .map(|(addr, _, value)| (addr, false, value))
+            .collect();
+
+        assert_eq!(stack.overflow_accesses(), accesses);
+
+        let stack_trace = stack.into_trace(8);
+
+        assert_eq!(trace_state(5, &stack_trace)[11..16], *input_ct);
+    }
+}
+
+mod swap2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::swap2()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[1..6], inputs.secret()[0].ciphertext().to_vec());
+        assert_eq!(trace_row3[6..11], inputs.secret()[1].ciphertext().to_vec());
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+
+        let op = Operation::swap2();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+mod drop2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::drop2()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[0], to_element(5));
+        assert_eq!(trace_row3[1..6], inputs.secret()[0].ciphertext().to_vec());
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::drop2();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+mod sub {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(7))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::sub()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[0], to_element(1));
+        assert_eq!(trace_row3[1], to_element(5));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+
+        let op = Operation::sub();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+mod neg {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(7))).unwrap();
+        stack.execute_op(&Operation::neg()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row3 = trace_state(3, &stack_trace);
+
+        assert_eq!(trace_row3[0], to_element(2));
+        assert_eq!(trace_row3[1], -to_element(7));
+        assert_eq!(trace_row3[2], to_element(2));
+    }
+}
+
+mod inv {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(4))).unwrap();
+        stack.execute_op(&Operation::inv()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row2 = trace_state(2, &stack_trace);
+
+        assert_eq!(trace_row2[1] * to_element(4), to_element(1));
+    }
+
+    #[test]
+    fn test_division_by_zero_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+
+        let op = Operation::inv();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::division_by_zero(&op, 2)));
+    }
+}
+
+mod cswap {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::cswap()).unwrap();
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+        stack.execute_op(&Operation::cswap()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+        let trace_row6 = trace_state(6, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(2));
+        assert_eq!(trace_row4[1..3], to_elements(&[1, 2]));
+        assert_eq!(trace_row6[1..3], to_elements(&[1, 2]));
+    }
+
+    #[test]
+    fn test_invalid_condition_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+
+        let op = Operation::cswap();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::invalid_condition(&op, 4)));
+    }
+}
+
+mod csel {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+        stack.execute_op(&Operation::csel()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(1));
+        assert_eq!(trace_row4[1], to_element(1));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::csel();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 3)));
+    }
+}
+
+mod cswap2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::cswap2()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(10));
+        assert_eq!(trace_row4[1..6], inputs.secret()[0].ciphertext().to_vec());
+        assert_eq!(trace_row4[6..11], inputs.secret()[1].ciphertext().to_vec());
+    }
+}
+
+mod csel2 {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::read2()).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::csel2()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(5));
+        assert_eq!(trace_row4[1..6], inputs.secret()[1].ciphertext().to_vec());
+    }
+}
+
+mod branch {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::true_branch()).unwrap();
+        stack.execute_op(&Operation::false_branch()).unwrap();
+
+        let stack_trace = stack.into_trace(8);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(0));
+    }
+
+    #[test]
+    fn test_invalid_condition_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::false_branch();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::invalid_condition(&op, 2)));
+    }
+}
+
+mod assert {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(7))).unwrap();
+        stack.execute_op(&Operation::push(to_element(3))).unwrap();
+        stack.execute_op(&Operation::push(to_element(3))).unwrap();
+        stack.execute_op(&Operation::assert_eq()).unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+        stack.execute_op(&Operation::assert()).unwrap();
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+        stack.execute_op(&Operation::assertz()).unwrap();
+
+        let stack_trace = stack.into_trace(16);
+
+        let trace_row4 = trace_state(4, &stack_trace);
+        let trace_row8 = trace_state(8, &stack_trace);
+
+        assert_eq!(trace_row4[0], to_element(1));
+        assert_eq!(trace_row4[1], to_element(7));
+        assert_eq!(trace_row8[..2], to_elements(&[1, 7]));
+    }
+
+    #[test]
+    fn test_assertion_failed_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        for (op, expected, top) in [(Operation::assert(), 1, 2), (Operation::assertz(), 0, 1)] {
+            let mut stack = Stack::new(&inputs, 8);
+
+            stack.execute_op(&Operation::push(to_element(top))).unwrap();
+
+            let error = stack.execute_op(&op).unwrap_err();
+            let expected = StackError::assertion_failed(&op, to_element(expected), to_element(top), 2);
+
+            assert_eq!(format!("{error}"), format!("{expected}"));
+        }
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(4))).unwrap();
+        stack.execute_op(&Operation::push(to_element(5))).unwrap();
+
+        let op = Operation::assert_eq();
+
+        let error = stack.execute_op(&op).unwrap_err();
+        let expected = StackError::assertion_failed(&op, to_element(5), to_element(4), 3);
+
+        assert_eq!(format!("{error}"), format!("{expected}"));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let values = values(&server_key);
+        let inputs = inputs(&values, &server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::assert_eq();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+mod u32_ops {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 16);
+
+        stack.execute_op(&Operation::push(BaseElement::from(u32::MAX))).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::u32add()).unwrap();
+
+        assert_eq!(stack.current_stack_state()[..2], to_elements(&[1, 1]));
+
+        // a, b and c in byte limbs
+        let mut limbs = vec![ZERO; 20];
+        limbs[..4].copy_from_slice(&to_elements(&[255, 255, 255, 255]));
+        limbs[4] = to_element(2);
+        limbs[8] = to_element(1);
+        assert_eq!(stack.range_checks(), limbs);
+
+        stack.execute_op(&Operation::u32sub()).unwrap();
+
+        // 1 - 1 without borrow
+        assert_eq!(stack.current_stack_state()[..2], to_elements(&[0, 0]));
+
+        stack.execute_op(&Operation::drop()).unwrap();
+        stack.execute_op(&Operation::push(to_element(2))).unwrap();
+        stack.execute_op(&Operation::u32sub()).unwrap();
+
+        // 0 - 2 borrows
+        assert_eq!(stack.current_stack_state()[0], to_element(1));
+        assert_eq!(stack.current_stack_state()[1], BaseElement::from(u32::MAX - 1));
+
+        stack.execute_op(&Operation::drop()).unwrap();
+        stack.execute_op(&Operation::push(to_element(3))).unwrap();
+        stack.execute_op(&Operation::u32mul()).unwrap();
+
+        // (2^32 - 2) * 3 = 2 * 2^32 + 2^32 - 6
+        assert_eq!(stack.current_stack_state()[0], to_element(1));
+        assert_eq!(stack.current_stack_state()[1], BaseElement::from(u32::MAX - 5));
+
+        stack.execute_op(&Operation::drop()).unwrap();
+        stack.execute_op(&Operation::push(to_element(7))).unwrap();
+        stack.execute_op(&Operation::u32div()).unwrap();
+
+        assert_eq!(stack.current_stack_state()[0], BaseElement::from((u32::MAX - 5) / 7));
+
+        let stack_trace = stack.into_trace(16);
+
+        // the u32 flag, the limbs of the remainder and the inverse of the high word
+        let trace_row9 = trace_state(9, &stack_trace);
+        let trace_row12 = trace_state(12, &stack_trace);
+
+        assert_eq!(trace_row9[35], ONE);
+        assert_eq!(trace_row9[56], to_element(2).inv());
+        assert_eq!(trace_row12[35], ONE);
+        assert_eq!(trace_row12[48..52], to_elements(&[((u32::MAX - 5) % 7) as u8, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_not_u32_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack
+            .execute_op(&Operation::push(BaseElement::from(1u64 << 32)))
+            .unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::u32add();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::not_u32(&op, 3)));
+    }
+
+    #[test]
+    fn test_division_by_zero_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(5))).unwrap();
+        stack.execute_op(&Operation::push(to_element(0))).unwrap();
+
+        let op = Operation::u32mod();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::division_by_zero(&op, 3)));
+    }
+}
+
+mod bitwise {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 16);
+
+        for op in [Operation::and(), Operation::or(), Operation::xor()] {
+            stack
+                .execute_op(&Operation::push(BaseElement::from(0x1234u32)))
+                .unwrap();
+            stack
+                .execute_op(&Operation::push(BaseElement::from(0xff0fu32)))
+                .unwrap();
+            stack.execute_op(&op).unwrap();
+        }
+
+        // the results of xor, or and and
+        assert_eq!(
+            stack.current_stack_state()[..3],
+            [0xed3bu32, 0xff3f, 0x1204].map(BaseElement::from)
+        );
+
+        // the byte pairs of the last operation
+        assert_eq!(stack.bitwise_checks(), [(0x34, 0x0f), (0x12, 0xff), (0, 0), (0, 0)]);
+
+        stack.execute_op(&Operation::shl(4)).unwrap();
+
+        assert_eq!(stack.current_stack_state()[0], BaseElement::from(0xed3b0u32));
+
+        stack.execute_op(&Operation::push(BaseElement::from(u32::MAX))).unwrap();
+        stack.execute_op(&Operation::shl(31)).unwrap();
+
+        // the high bits are dropped
+        assert_eq!(stack.current_stack_state()[0], BaseElement::from(1u32 << 31));
+
+        stack.execute_op(&Operation::shr(30)).unwrap();
+
+        assert_eq!(stack.current_stack_state()[0], to_element(2));
+        assert!(stack.bitwise_checks().is_empty());
+
+        let stack_trace = stack.into_trace(16);
+
+        // the bitwise flag, the op value of shr and the remainder bound
+        let trace_row8 = trace_state(8, &stack_trace);
+        let trace_row12 = trace_state(12, &stack_trace);
+
+        assert_eq!(trace_row8[35], ONE);
+        assert_eq!(trace_row8[57], ONE);
+        assert_eq!(trace_row12[35], ONE);
+        assert_eq!(trace_row12[57], ZERO);
+        assert_eq!(trace_row12[40..44], to_elements(&[0, 0, 0, 64]));
+        assert_eq!(trace_row12[52..56], to_elements(&[255, 255, 255, 63]));
+    }
+
+    #[test]
+    fn test_not_u32_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack
+            .execute_op(&Operation::push(BaseElement::from(1u64 << 32)))
+            .unwrap();
+
+        let op = Operation::shr(1);
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::not_u32(&op, 2)));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::xor();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+#[cfg(test)]
+mod compare {
+
+    use super::*;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 32);
+
+        let cases = [
+            (5, 7, Operation::lt()),
+            (7, 7, Operation::lte()),
+            (7, 7, Operation::lt()),
+            (9, 3, Operation::eq()),
+            (4, 4, Operation::eq()),
+            (4, 3, Operation::neq()),
+        ];
+        for (a, b, op) in cases {
+            stack.execute_op(&Operation::push(to_element(a))).unwrap();
+            stack.execute_op(&Operation::push(to_element(b))).unwrap();
+            stack.execute_op(&op).unwrap();
+        }
+
+        assert_eq!(stack.current_stack_state()[..6], to_elements(&[1, 1, 0, 0, 1, 1]));
+
+        let stack_trace = stack.into_trace(32);
+
+        // the borrowed difference of lt is range checked as u32 limbs
+        let trace_row2 = trace_state(2, &stack_trace);
+
+        assert_eq!(trace_row2[35], ONE);
+        assert_eq!(
+            trace_row2[36..48],
+            to_elements(&[5, 0, 0, 0, 7, 0, 0, 0, 254, 255, 255, 255])
+        );
+
+        // eq takes the inverse of the difference but no u32 operands
+        let trace_row11 = trace_state(11, &stack_trace);
+        let trace_row14 = trace_state(14, &stack_trace);
+
+        assert_eq!(trace_row11[35], ZERO);
+        assert_eq!(trace_row11[56], to_element(6).inv());
+        assert_eq!(trace_row14[56], ZERO);
+    }
+
+    #[test]
+    fn test_not_u32_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack
+            .execute_op(&Operation::push(BaseElement::from(1u64 << 32)))
+            .unwrap();
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::lt();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::not_u32(&op, 3)));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        stack.execute_op(&Operation::push(to_element(1))).unwrap();
+
+        let op = Operation::eq();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 2)));
+    }
+}
+
+#[cfg(test)]
+mod hash {
+
+    use super::*;
+    use crypto::rescue;
+
+    #[test]
+    fn test_operation_execution() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 16);
+
+        for value in [9, 4, 3, 2, 1] {
+            stack.execute_op(&Operation::push(to_element(value))).unwrap();
+        }
+
+        stack.execute_op(&Operation::hperm()).unwrap();
+
+        let mut state = to_elements(&[1, 2, 3, 4]);
+        rescue::apply_permutation(&mut state);
+
+        // the element below the state is kept
+        assert_eq!(stack.current_stack_state()[..4], state);
+        assert_eq!(stack.current_stack_state()[4], to_element(9));
+    }
+
+    #[test]
+    fn test_stack_underflow_error() {
+        let server_key = server_key();
+        let inputs = empty_inputs(&server_key);
+
+        let mut stack = Stack::new(&inputs, 8);
+
+        for value in [3, 2, 1] {
+            stack.execute_op(&Operation::push(to_element(value))).unwrap();
+        }
+
+        let op = Operation::hperm();
+
+        let error = stack.execute_op(&op).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", StackError::stack_underflow(&op, 4)));
+    }
+}
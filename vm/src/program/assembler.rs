@@ -0,0 +1,592 @@
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    mem,
+    rc::Rc,
+};
+
+use crypto::{
+    rescue::{CYCLE_LENGTH, NUM_ROUNDS},
+    Hash, Rescue128,
+};
+
+use super::{blocks::CodeBlock, errors::ProgramError, lower_to_handles, modules::ModuleLoader, parse_ops, parsers};
+use crate::processor::{OpCode, Operation};
+
+const PUSH_OP_ALIGNMENT: usize = 8;
+
+// procedures visible to a file by name, imported ones are named module::name
+pub type ProcMap = HashMap<String, Procedure>;
+
+// a procedure body is inlined at each exec, with the procedures visible to its definition
+// a called procedure does not depend on the call site, its body is compiled at the first call and shared
+#[derive(Clone)]
+pub struct Procedure {
+    tokens: Rc<Vec<String>>,
+    start: usize,
+    scope: Rc<ProcMap>,
+    call: Rc<OnceCell<CompiledCall>>,
+    // the module defining the procedure, none for the procedures of the program
+    file: Option<Rc<str>>,
+}
+
+impl Procedure {
+    // an error in the body of an imported procedure is at a step of its module
+    fn locate(&self, err: ProgramError) -> ProgramError {
+        match &self.file {
+            Some(file) => err.in_file(file),
+            None => err,
+        }
+    }
+}
+
+// the blocks of a called procedure with its digest, and the sponge and length of the body
+#[derive(Clone)]
+struct CompiledCall {
+    body: Rc<Vec<CodeBlock>>,
+    digest: Hash,
+    sponge: Rescue128,
+    step: usize,
+}
+
+// lays out the operations of every execution path and hashes them
+// both branches of a switch start from the same sponge state and end merged
+// into the same state, so the program hash does not depend on the executed branch
+// every loop iteration starts from the loop entry state, so the body is hashed once
+// a called procedure starts from the initial state, so its digest does not depend on the call site
+#[derive(Clone)]
+pub struct Assembler {
+    sponge: Rescue128,
+    step: usize,
+    in_loop: bool,
+    handles: bool,
+    procs: Rc<ProcMap>,
+    file: Option<Rc<str>>,
+}
+
+impl Assembler {
+    pub fn new() -> Assembler {
+        Assembler {
+            sponge: Rescue128::new(),
+            step: 0,
+            in_loop: false,
+            handles: false,
+            procs: Rc::new(HashMap::new()),
+            file: None,
+        }
+    }
+
+    // lowers the stack ciphertext instructions to ct_* operations on handles
+    pub fn with_handles(mut self) -> Assembler {
+        self.handles = true;
+        self
+    }
+
+    // the module being compiled, its procedures report errors in this file
+    pub fn with_file(mut self, file: &str) -> Assembler {
+        self.file = Some(Rc::from(file));
+        self
+    }
+
+    pub fn compile(
+        mut self,
+        tokens: &[&str],
+        modules: &mut ModuleLoader,
+    ) -> Result<(Vec<CodeBlock>, Hash), ProgramError> {
+        let mut pos = 0;
+
+        self.compile_imports(tokens, &mut pos, modules)?;
+        self.compile_procs(tokens, &mut pos)?;
+
+        let (mut blocks, close) = self.compile_block(tokens, &mut pos, Vec::new())?;
+
+        if let Some(step) = close {
+            let op: Vec<&str> = tokens[step - 1].split('.').collect();
+            return Err(ProgramError::unmatched_block(&op, step));
+        }
+
+        // pad the program length with NOOP codes to match the RescuePrime cycle length
+        let mut span = take_last_span(&mut blocks);
+
+        let padded_length = compute_padding(self.step);
+        while self.step < padded_length {
+            self.absorb(&mut span, Operation::noop());
+        }
+
+        blocks.push(CodeBlock::Span(span));
+
+        Ok((blocks, self.sponge.hash()))
+    }
+
+    // a module only contains imports and procedures, returns the procedures it defines
+    pub fn compile_module(mut self, tokens: &[&str], modules: &mut ModuleLoader) -> Result<Rc<ProcMap>, ProgramError> {
+        let mut pos = 0;
+
+        self.compile_imports(tokens, &mut pos, modules)?;
+        self.compile_procs(tokens, &mut pos)?;
+
+        if pos < tokens.len() {
+            let op: Vec<&str> = tokens[pos].split('.').collect();
+            return Err(ProgramError::module_body(&op, pos + 1));
+        }
+
+        let procs = self
+            .procs
+            .iter()
+            .filter(|(name, _)| !name.contains("::"))
+            .map(|(name, proc)| (name.clone(), proc.clone()))
+            .collect();
+
+        Ok(Rc::new(procs))
+    }
+
+    // imports come first, the procedures of use.dir::module are named module::name
+    fn compile_imports(
+        &mut self,
+        tokens: &[&str],
+        pos: &mut usize,
+        modules: &mut ModuleLoader,
+    ) -> Result<(), ProgramError> {
+        let mut aliases = HashSet::new();
+
+        while *pos < tokens.len() {
+            let step = *pos + 1;
+            let op: Vec<&str> = tokens[*pos].split('.').collect();
+
+            if op[0] != "use" {
+                break;
+            }
+
+            let path = parsers::parse_use(&op, step)?;
+            let alias = path[path.len() - 1];
+
+            if !aliases.insert(alias) {
+                return Err(ProgramError::duplicate_module(&op, step));
+            }
+
+            let module = modules.load(&op, &path, step)?;
+
+            let procs = Rc::make_mut(&mut self.procs);
+            for (name, proc) in module.iter() {
+                procs.insert(format!("{alias}::{name}"), proc.clone());
+            }
+
+            *pos += 1;
+        }
+
+        Ok(())
+    }
+
+    // procedures are defined before the program body, a procedure can only use the ones defined before it
+    fn compile_procs(&mut self, tokens: &[&str], pos: &mut usize) -> Result<(), ProgramError> {
+        let source: Rc<Vec<String>> = Rc::new(tokens.iter().map(|token| token.to_string()).collect());
+
+        while *pos < tokens.len() {
+            let step = *pos + 1;
+            let op: Vec<&str> = tokens[*pos].split('.').collect();
+
+            if op[0] != "proc" {
+                break;
+            }
+
+            let name = parsers::parse_proc_name(&op, step)?;
+
+            if self.procs.contains_key(name) {
+                return Err(ProgramError::duplicate_proc(&op, step));
+            }
+
+            *pos += 1;
+            let start = *pos;
+
+            // check the body once, it is compiled again at each exec and call
+            let mut body = Assembler::new();
+            body.procs = self.procs.clone();
+
+            let (_, close) = body.compile_block(tokens, pos, Vec::new())?;
+            expect_end(tokens, close, &op, step)?;
+
+            let proc = Procedure {
+                tokens: source.clone(),
+                start,
+                scope: self.procs.clone(),
+                call: Rc::new(OnceCell::new()),
+                file: self.file.clone(),
+            };
+
+            Rc::make_mut(&mut self.procs).insert(name.to_string(), proc);
+        }
+
+        Ok(())
+    }
+
+    // compiles tokens until an else or end token, returns its step
+    fn compile_block(
+        &mut self,
+        tokens: &[&str],
+        pos: &mut usize,
+        mut span: Vec<Operation>,
+    ) -> Result<(Vec<CodeBlock>, Option<usize>), ProgramError> {
+        let mut blocks = Vec::new();
+
+        while *pos < tokens.len() {
+            let step = *pos + 1;
+            let op: Vec<&str> = tokens[*pos].split('.').collect();
+
+            *pos += 1;
+
+            match op[0] {
+                "if" => {
+                    parsers::parse_condition(&op, step)?;
+
+                    if !span.is_empty() {
+                        blocks.push(CodeBlock::Span(mem::take(&mut span)));
+                    }
+
+                    blocks.push(self.compile_switch(tokens, pos, step)?);
+                }
+                "while" => {
+                    parsers::parse_condition(&op, step)?;
+
+                    if self.in_loop {
+                        return Err(ProgramError::nested_loop(&op, step));
+                    }
+
+                    // the loop entry closes the current span
+                    let block = self.compile_while(tokens, pos, step, &mut span)?;
+
+                    blocks.push(CodeBlock::Span(mem::take(&mut span)));
+                    blocks.push(block);
+                }
+                "repeat" => {
+                    let count = parsers::parse_repeat(&op, step)?;
+                    let start = *pos;
+
+                    // unroll the body
+                    for _ in 0..count {
+                        *pos = start;
+
+                        let (body, close) = self.compile_block(tokens, pos, mem::take(&mut span))?;
+                        expect_end(tokens, close, &op, step)?;
+
+                        blocks.extend(body);
+                        span = take_last_span(&mut blocks);
+                    }
+                }
+                "use" => {
+                    parsers::parse_use(&op, step)?;
+                    return Err(ProgramError::misplaced_import(&op, step));
+                }
+                "proc" => {
+                    parsers::parse_proc_name(&op, step)?;
+                    return Err(ProgramError::misplaced_proc(&op, step));
+                }
+                "exec" => {
+                    let proc = self.find_proc(&op, step)?;
+                    let body_tokens: Vec<&str> = proc.tokens.iter().map(String::as_str).collect();
+
+                    // inline the procedure body, it only sees the procedures of its definition
+                    let caller_procs = mem::replace(&mut self.procs, proc.scope.clone());
+                    let (body, _) = self
+                        .compile_block(&body_tokens, &mut proc.start.clone(), mem::take(&mut span))
+                        .map_err(|err| proc.locate(err))?;
+                    self.procs = caller_procs;
+
+                    blocks.extend(body);
+                    span = take_last_span(&mut blocks);
+                }
+                "call" => {
+                    let proc = self.find_proc(&op, step)?;
+
+                    if self.in_loop {
+                        return Err(ProgramError::nested_call(&op, step));
+                    }
+
+                    // the call sites share the blocks of the procedure, the caller continues in a new span
+                    let body = self.compile_call(&proc, &mut span)?;
+
+                    blocks.push(CodeBlock::Span(mem::take(&mut span)));
+                    blocks.push(CodeBlock::Call(body));
+                }
+                "else" | "end" => {
+                    parsers::parse_block_end(&op, step)?;
+
+                    if !span.is_empty() {
+                        blocks.push(CodeBlock::Span(span));
+                    }
+
+                    return Ok((blocks, Some(step)));
+                }
+                _ => {
+                    for mut op in parse_ops(step, tokens[step - 1])? {
+                        if self.handles {
+                            op = lower_to_handles(op, tokens[step - 1], step)?;
+                        }
+                        self.append(&mut span, op);
+                    }
+                }
+            }
+        }
+
+        if !span.is_empty() {
+            blocks.push(CodeBlock::Span(span));
+        }
+
+        Ok((blocks, None))
+    }
+
+    fn compile_switch(&mut self, tokens: &[&str], pos: &mut usize, step: usize) -> Result<CodeBlock, ProgramError> {
+        let if_op: Vec<&str> = tokens[step - 1].split('.').collect();
+
+        // each branch starts with a guard popping the condition
+        let mut true_path = self.clone();
+        let mut span = Vec::new();
+        true_path.append(&mut span, Operation::true_branch());
+
+        let mut false_path = self.clone();
+        let mut false_span = Vec::new();
+        false_path.append(&mut false_span, Operation::false_branch());
+
+        let (mut true_branch, close) = true_path.compile_block(tokens, pos, span)?;
+
+        let mut false_branch = match close.map(|close| tokens[close - 1]) {
+            Some("else") => {
+                let (false_branch, close) = false_path.compile_block(tokens, pos, false_span)?;
+                expect_end(tokens, close, &if_op, step)?;
+                false_branch
+            }
+            Some(_) => vec![CodeBlock::Span(false_span)],
+            None => return Err(ProgramError::unclosed_block(&if_op, step)),
+        };
+
+        let true_digest = true_path.close_branch(&mut true_branch);
+        let false_digest = false_path.close_branch(&mut false_branch);
+
+        // merge each branch with the digest of its sibling
+        let mut span = take_last_span(&mut true_branch);
+        for value in false_digest.to_elements() {
+            true_path.append(&mut span, Operation::tend(value));
+        }
+        true_branch.push(CodeBlock::Span(span));
+
+        let mut span = take_last_span(&mut false_branch);
+        for value in true_digest.to_elements() {
+            false_path.append(&mut span, Operation::fend(value));
+        }
+        false_branch.push(CodeBlock::Span(span));
+
+        debug_assert_eq!(true_path.sponge.state(), false_path.sponge.state());
+
+        *self = true_path;
+
+        Ok(CodeBlock::Switch(true_branch, false_branch))
+    }
+
+    fn compile_while(
+        &mut self,
+        tokens: &[&str],
+        pos: &mut usize,
+        step: usize,
+        span: &mut Vec<Operation>,
+    ) -> Result<CodeBlock, ProgramError> {
+        let while_op: Vec<&str> = tokens[step - 1].split('.').collect();
+
+        // the loop is entered on the copy steps of the current cycle
+        while self.step % CYCLE_LENGTH != NUM_ROUNDS {
+            self.absorb(span, Operation::noop());
+        }
+
+        // the entry operations only reset the capacity
+        let mut body_path = self.clone();
+        body_path.absorb(&mut Vec::new(), Operation::noop());
+        body_path.absorb(&mut Vec::new(), Operation::noop());
+
+        let mut exit_path = body_path.clone();
+
+        // each iteration starts with a guard popping the condition
+        body_path.in_loop = true;
+        let mut body_span = Vec::new();
+        body_path.append(&mut body_span, Operation::true_branch());
+
+        let (mut body, close) = body_path.compile_block(tokens, pos, body_span)?;
+        expect_end(tokens, close, &while_op, step)?;
+
+        let digest = body_path.close_branch(&mut body);
+
+        // the iteration end restores the loop entry state
+        let mut tail = take_last_span(&mut body);
+        tail.push(Operation::repeat());
+        tail.push(Operation::noop());
+        body.push(CodeBlock::Span(tail));
+
+        // the exit absorbs the body digest
+        let mut exit_span = Vec::new();
+        exit_path.append(&mut exit_span, Operation::false_branch());
+
+        let mut exit = vec![CodeBlock::Span(exit_span)];
+        exit_path.close_branch(&mut exit);
+
+        let mut tail = take_last_span(&mut exit);
+        exit_path.absorb(&mut tail, Operation::noop());
+        exit_path.sponge.merge_capacity(digest.to_elements());
+        exit_path.step += 1;
+        tail.push(Operation::wend());
+        exit.push(CodeBlock::Span(tail));
+
+        for value in digest.to_elements() {
+            self.absorb(span, Operation::enter_loop(value));
+        }
+
+        *self = exit_path;
+
+        Ok(CodeBlock::Loop(body, exit))
+    }
+
+    fn compile_call(
+        &mut self,
+        proc: &Procedure,
+        span: &mut Vec<Operation>,
+    ) -> Result<Rc<Vec<CodeBlock>>, ProgramError> {
+        // the call is entered on the copy steps of the current cycle
+        while self.step % CYCLE_LENGTH != NUM_ROUNDS {
+            self.absorb(span, Operation::noop());
+        }
+
+        let call = match proc.call.get() {
+            Some(call) => call.clone(),
+            None => {
+                let call = self.compile_proc(proc).map_err(|err| proc.locate(err))?;
+                proc.call.get_or_init(|| call).clone()
+            }
+        };
+
+        let [d0, d1] = call.digest.to_elements();
+
+        self.absorb(span, Operation::enter_loop(d0));
+        span.push(Operation::call(d1));
+
+        // the return restores the caller rate and absorbs the procedure digest
+        let state = self.sponge.state();
+        let mut sponge = call.sponge;
+        sponge.restore([state[0], state[1]]);
+        sponge.merge_capacity(call.digest.to_elements());
+
+        self.sponge = sponge;
+        self.step += 1 + call.step + 2;
+
+        Ok(call.body)
+    }
+
+    // compiles a called procedure from the initial sponge state
+    fn compile_proc(&self, proc: &Procedure) -> Result<CompiledCall, ProgramError> {
+        // the procedure uses the loop ctx, it cannot contain loops or calls
+        let mut callee = Assembler::new();
+        callee.in_loop = true;
+        callee.handles = self.handles;
+        callee.procs = proc.scope.clone();
+
+        let tokens: Vec<&str> = proc.tokens.iter().map(String::as_str).collect();
+        let (mut body, _) = callee.compile_block(&tokens, &mut proc.start.clone(), Vec::new())?;
+
+        let digest = callee.close_branch(&mut body);
+
+        let mut tail = take_last_span(&mut body);
+        tail.push(Operation::repeat());
+        tail.push(Operation::wend());
+        body.push(CodeBlock::Span(tail));
+
+        Ok(CompiledCall {
+            body: Rc::new(body),
+            digest,
+            sponge: callee.sponge,
+            step: callee.step,
+        })
+    }
+
+    fn find_proc(&self, op: &[&str], step: usize) -> Result<Procedure, ProgramError> {
+        let name = parsers::parse_proc_ref(op, step)?;
+
+        match self.procs.get(name) {
+            Some(proc) => Ok(proc.clone()),
+            None => Err(ProgramError::undefined_proc(op, step)),
+        }
+    }
+
+    // pads the branch up to the copy steps of its last cycle, returns the branch digest
+    fn close_branch(&mut self, blocks: &mut Vec<CodeBlock>) -> Hash {
+        let mut span = take_last_span(blocks);
+
+        while self.step % CYCLE_LENGTH != NUM_ROUNDS {
+            self.absorb(&mut span, Operation::noop());
+        }
+
+        blocks.push(CodeBlock::Span(span));
+
+        let state = self.sponge.state();
+        Hash::new(state[0], state[1])
+    }
+
+    fn append(&mut self, span: &mut Vec<Operation>, op: Operation) {
+        match op.op_code() {
+            // branch ends merge the sponge state on the copy steps
+            OpCode::Tend => self.sponge.merge_right(op.value()),
+            OpCode::Fend => self.sponge.merge_left(op.value()),
+            _ => {
+                if let OpCode::Push = op.op_code() {
+                    let alignment = self.step % PUSH_OP_ALIGNMENT;
+                    let pad_length = (PUSH_OP_ALIGNMENT - alignment) % PUSH_OP_ALIGNMENT;
+                    for _ in 0..pad_length {
+                        self.absorb(span, Operation::noop());
+                    }
+                }
+
+                // add NOOP codes when CYCLE_LENGTH >= NUM_ROUNDS
+                // to reset the capacity elements to 0
+                if self.step % CYCLE_LENGTH >= NUM_ROUNDS {
+                    let padded_length = compute_padding(self.step);
+                    while self.step < padded_length {
+                        self.absorb(span, Operation::noop());
+                    }
+                }
+
+                self.sponge.update(op.code(), op.value());
+            }
+        }
+
+        span.push(op);
+        self.step += 1;
+    }
+
+    fn absorb(&mut self, span: &mut Vec<Operation>, op: Operation) {
+        self.sponge.update(op.code(), op.value());
+        span.push(op);
+        self.step += 1;
+    }
+}
+
+// a block opened at step must be closed by an end token
+fn expect_end(tokens: &[&str], close: Option<usize>, op: &[&str], step: usize) -> Result<(), ProgramError> {
+    match close {
+        Some(close) if tokens[close - 1] == "end" => Ok(()),
+        Some(close) => {
+            let close_op: Vec<&str> = tokens[close - 1].split('.').collect();
+            Err(ProgramError::unmatched_block(&close_op, close))
+        }
+        None => Err(ProgramError::unclosed_block(op, step)),
+    }
+}
+
+// the branch tail span, a branch ending with a nested switch starts a new span
+fn take_last_span(blocks: &mut Vec<CodeBlock>) -> Vec<Operation> {
+    match blocks.pop() {
+        Some(CodeBlock::Span(ops)) => ops,
+        Some(block) => {
+            blocks.push(block);
+            Vec::new()
+        }
+        None => Vec::new(),
+    }
+}
+
+fn compute_padding(length: usize) -> usize {
+    length + (CYCLE_LENGTH - (length % CYCLE_LENGTH))
+}
@@ -0,0 +1,35 @@
+use std::rc::Rc;
+
+use crate::processor::Operation;
+
+// program code tree, the processor selects the executed branch of a switch
+#[derive(Clone, PartialEq, Debug)]
+pub enum CodeBlock {
+    Span(Vec<Operation>),
+    // true branch, false branch
+    Switch(Vec<CodeBlock>, Vec<CodeBlock>),
+    // loop body executed while the condition is 1, loop exit
+    Loop(Vec<CodeBlock>, Vec<CodeBlock>),
+    // body of a called procedure, shared by its call sites
+    Call(Rc<Vec<CodeBlock>>),
+}
+
+impl CodeBlock {
+    // operations in program order, the true branch precedes the false branch
+    pub fn flatten(blocks: &[CodeBlock]) -> Vec<Operation> {
+        let mut code = Vec::new();
+
+        for block in blocks {
+            match block {
+                CodeBlock::Span(ops) => code.extend(ops),
+                CodeBlock::Switch(first, second) | CodeBlock::Loop(first, second) => {
+                    code.extend(CodeBlock::flatten(first));
+                    code.extend(CodeBlock::flatten(second));
+                }
+                CodeBlock::Call(body) => code.extend(CodeBlock::flatten(body)),
+            }
+        }
+
+        code
+    }
+}
@@ -4,6 +4,8 @@ use std::error::Error;
 pub struct ProgramError {
     message: String,
     step: usize,
+    // source file of the instruction, none for a program compiled from a string
+    file: Option<String>,
 }
 
 impl Error for ProgramError {}
@@ -13,6 +15,7 @@ impl ProgramError {
         ProgramError {
             message: message.to_string(),
             step: 0,
+            file: None,
         }
     }
 
@@ -20,6 +23,7 @@ impl ProgramError {
         ProgramError {
             message: String::from("a program must contain at least one instruction"),
             step: 0,
+            file: None,
         }
     }
 
@@ -27,6 +31,7 @@ impl ProgramError {
         ProgramError {
             message: format!("instruction {} is invalid", op.join(".")),
             step,
+            file: None,
         }
     }
 
@@ -34,6 +39,7 @@ impl ProgramError {
         ProgramError {
             message: format!("malformed instruction {}, parameter is missing", op[0]),
             step,
+            file: None,
         }
     }
 
@@ -41,6 +47,7 @@ impl ProgramError {
         ProgramError {
             message: format!("malformed instruction {}, too many parameters provided", op[0]),
             step,
+            file: None,
         }
     }
 
@@ -48,12 +55,138 @@ impl ProgramError {
         ProgramError {
             message: format!("malformed instruction {}, parameter '{}' is invalid", op[0], op[1]),
             step,
+            file: None,
         }
     }
+
+    pub fn unmatched_block(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("instruction {} does not match an open block", op.join(".")),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn stack_only_op(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("instruction {} has no operation on ciphertext handles", op.join(".")),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn nested_call(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!(
+                "calls cannot be nested, instruction {} is in a while loop or a call",
+                op.join(".")
+            ),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn nested_loop(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!(
+                "while loops cannot be nested, instruction {} is in a while loop or a call",
+                op.join(".")
+            ),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn duplicate_proc(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("procedure {} is already defined", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn undefined_proc(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("procedure {} is not defined", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn misplaced_proc(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("procedure {} must be defined before the program body", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn duplicate_module(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("module {} is already imported", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn module_not_found(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("module {} was not found in the module search path", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn import_cycle(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("module {} is part of an import cycle", op[1]),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn misplaced_import(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!(
+                "module {} must be imported before the procedures and the program body",
+                op[1]
+            ),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn module_body(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!(
+                "instruction {} is invalid, a module only contains imports and procedures",
+                op.join(".")
+            ),
+            step,
+            file: None,
+        }
+    }
+
+    pub fn unclosed_block(op: &[&str], step: usize) -> ProgramError {
+        ProgramError {
+            message: format!("block {} is missing an end", op.join(".")),
+            step,
+            file: None,
+        }
+    }
+
+    // names the file the error occurred in, an error keeps the innermost file
+    pub fn in_file(mut self, file: &str) -> ProgramError {
+        self.file.get_or_insert_with(|| file.to_string());
+        self
+    }
 }
 
 impl std::fmt::Display for ProgramError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "program error at {}: {}", self.step, self.message)
+        match &self.file {
+            Some(file) => write!(f, "program error in {} at {}: {}", file, self.step, self.message),
+            None => write!(f, "program error at {}: {}", self.step, self.message),
+        }
     }
 }
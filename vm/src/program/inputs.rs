@@ -1,21 +1,30 @@
-use fhe::{FheUInt8, ServerKey};
+use fhe::{FheUInt8, HomomorphicScheme, ServerKey};
+
+use super::Policy;
 
 #[derive(Clone, Debug)]
-pub struct ProgramInputs<'a> {
+pub struct ProgramInputs<'a, S: HomomorphicScheme = ServerKey> {
     public: &'a [u8],
     secret: &'a [FheUInt8],
-    server_key: &'a ServerKey,
+    scheme: &'a S,
+    policy: Option<&'a Policy>,
 }
 
-impl<'a> ProgramInputs<'a> {
-    pub fn new(public: &'a [u8], secret: &'a [FheUInt8], server_key: &'a ServerKey) -> Self {
+impl<'a, S: HomomorphicScheme> ProgramInputs<'a, S> {
+    pub fn new(public: &'a [u8], secret: &'a [FheUInt8], scheme: &'a S) -> Self {
         ProgramInputs {
             public,
             secret,
-            server_key,
+            scheme,
+            policy: None,
         }
     }
 
+    pub fn with_policy(mut self, policy: &'a Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     pub fn public(&self) -> &[u8] {
         self.public
     }
@@ -24,7 +33,11 @@ impl<'a> ProgramInputs<'a> {
         self.secret
     }
 
-    pub fn server_key(&self) -> &ServerKey {
-        self.server_key
+    pub fn scheme(&self) -> &S {
+        self.scheme
+    }
+
+    pub fn policy(&self) -> Option<&Policy> {
+        self.policy
     }
 }
@@ -1,102 +1,100 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::processor::{OpCode, Operation};
 
 mod errors;
-use crypto::{
-    rescue::{CYCLE_LENGTH, NUM_ROUNDS},
-    Hash, Rescue128,
-};
+use crypto::Hash;
 use errors::ProgramError;
 
+mod assembler;
+use assembler::Assembler;
+
+pub mod blocks;
+pub use blocks::CodeBlock;
+
+mod modules;
+use modules::ModuleLoader;
+
 mod parsers;
 
 pub mod inputs;
 pub use inputs::ProgramInputs;
 
+pub mod policy;
+pub use policy::Policy;
+
 #[cfg(test)]
 mod tests;
 
-const PUSH_OP_ALIGNMENT: usize = 8;
-
 #[derive(Debug)]
 pub struct Program {
-    code: Vec<Operation>,
+    blocks: Vec<CodeBlock>,
     hash: Hash,
 }
 
 impl Program {
+    // modules are imported from the program directory
     pub fn load(path: &Path) -> Result<Program, ProgramError> {
+        let search_path: Vec<PathBuf> = path.parent().map(Path::to_path_buf).into_iter().collect();
+        Program::load_with_search_path(path, &search_path)
+    }
+
+    pub fn load_with_search_path(path: &Path, search_path: &[PathBuf]) -> Result<Program, ProgramError> {
         let source = match fs::read_to_string(path) {
             Ok(source) => source,
             Err(err) => return Err(ProgramError::read_error(&err.to_string().to_lowercase())),
         };
-        Program::compile(&source)
-    }
 
-    pub fn compile(source: &str) -> Result<Program, ProgramError> {
-        let mut code: Vec<Operation> = Vec::new();
-        let mut sponge = Rescue128::new();
+        let mut modules = ModuleLoader::new(search_path);
+        modules.enter(path.to_path_buf());
 
-        let comment_symbol = "#";
-        let mut tokens = Vec::new();
+        Program::assemble(&source, &mut modules, Assembler::new())
+            .map_err(|err| err.in_file(&path.display().to_string()))
+    }
 
-        for program_line in source.lines() {
-            let line = program_line.trim();
+    // without a module search path, imports are not found
+    pub fn compile(source: &str) -> Result<Program, ProgramError> {
+        Program::compile_with_search_path(source, &[])
+    }
 
-            if !line.is_empty() && !line.starts_with(comment_symbol) {
-                let mut code_line = match line.find(comment_symbol) {
-                    Some(pos) => &line[..pos],
-                    None => line,
-                };
+    pub fn compile_with_search_path(source: &str, search_path: &[PathBuf]) -> Result<Program, ProgramError> {
+        Program::assemble(source, &mut ModuleLoader::new(search_path), Assembler::new())
+    }
 
-                code_line = code_line.trim();
+    // the stack ciphertext instructions compile to ct_* operations on handles, so the program runs
+    // for any ciphertext width and returns its result ciphertext through prove_ciphertext
+    // dup.n, movup.n, movdn.n, cswap and csel keep their indices, which count a handle as one element
+    // instead of the width of a stack ciphertext
+    pub fn compile_with_handles(source: &str, search_path: &[PathBuf]) -> Result<Program, ProgramError> {
+        Program::assemble(
+            source,
+            &mut ModuleLoader::new(search_path),
+            Assembler::new().with_handles(),
+        )
+    }
 
-                if !code_line.is_empty() {
-                    tokens.push(code_line);
-                }
-            }
-        }
+    fn assemble(source: &str, modules: &mut ModuleLoader, assembler: Assembler) -> Result<Program, ProgramError> {
+        let tokens = tokenize(source);
 
         if tokens.is_empty() {
             return Err(ProgramError::empty_program());
         }
 
-        for (i, token) in tokens.iter().enumerate() {
-            let op = parse_op(i + 1, token)?;
-
-            if let OpCode::Push = op.op_code() {
-                let alignment = code.len() % PUSH_OP_ALIGNMENT;
-                let pad_length = (PUSH_OP_ALIGNMENT - alignment) % PUSH_OP_ALIGNMENT;
-                code.resize(code.len() + pad_length, Operation::noop());
-            }
-
-            // add NOOP codes when CYCLE_LENGTH >= NUM_ROUNDS
-            // to reset the capacity elements to 0
-            if code.len() % CYCLE_LENGTH >= NUM_ROUNDS {
-                let padded_length = compute_padding(code.len());
-                code.resize(padded_length, Operation::noop());
-            }
-
-            code.push(op);
-        }
-
-        // pad the program length with NOOP codes to match the RescuePrime cycle length
-        let padded_length = compute_padding(code.len());
-        code.resize(padded_length, Operation::noop());
+        let (blocks, hash) = assembler.compile(&tokens, modules)?;
 
-        for op in code.iter() {
-            sponge.update(op.code(), op.value());
-        }
+        Ok(Program { blocks, hash })
+    }
 
-        Ok(Program {
-            code,
-            hash: sponge.hash(),
-        })
+    pub fn blocks(&self) -> &[CodeBlock] {
+        &self.blocks
     }
 
-    pub fn code(&self) -> &[Operation] {
-        &self.code
+    // all operations in program order, branches included
+    pub fn code(&self) -> Vec<Operation> {
+        CodeBlock::flatten(&self.blocks)
     }
 
     pub fn hash(&self) -> Hash {
@@ -104,33 +102,134 @@ impl Program {
     }
 }
 
-fn parse_op(step: usize, line: &str) -> Result<Operation, ProgramError> {
+// one token per instruction, comments and blank lines are skipped
+fn tokenize(source: &str) -> Vec<&str> {
+    let comment_symbol = "#";
+    let mut tokens = Vec::new();
+
+    for program_line in source.lines() {
+        let line = program_line.trim();
+
+        if !line.is_empty() && !line.starts_with(comment_symbol) {
+            let mut code_line = match line.find(comment_symbol) {
+                Some(pos) => &line[..pos],
+                None => line,
+            };
+
+            code_line = code_line.trim();
+
+            if !code_line.is_empty() {
+                tokens.push(code_line);
+            }
+        }
+    }
+
+    tokens
+}
+
+// instructions expanded by the assembler into several operations
+fn parse_ops(step: usize, line: &str) -> Result<Vec<Operation>, ProgramError> {
     let op: Vec<&str> = line.split('.').collect();
 
+    match op[0] {
+        "pow" => parsers::parse_pow(&op, step),
+        "ct_dot" => parsers::parse_ct_dot(&op, step),
+        "gt" => parsers::parse_gt(&op, step),
+        "gte" => parsers::parse_gte(&op, step),
+        "hash" => parsers::parse_hash(&op, step),
+        "mtree_verify" => parsers::parse_mtree_verify(&op, step),
+        _ => Ok(vec![parse_op(&op, step)?]),
+    }
+}
+
+// a ciphertext handle takes the place of the stack ciphertext, scalars stay on the stack
+// sadd and push2 have no ct_* operation
+fn lower_to_handles(op: Operation, line: &str, step: usize) -> Result<Operation, ProgramError> {
     #[rustfmt::skip]
-    return match op[0] {
-        "push"  => parsers::parse_push(&op, step),
-        "read"  => parsers::parse_read(&op, step),
-        "read2" => parsers::parse_read2(&op, step),
-        "add"   => parsers::parse_add(&op, step),
-        "mul"   => parsers::parse_mul(&op, step),
-        "sadd"  => parsers::parse_sadd(&op, step),
-        "smul"  => parsers::parse_smul(&op, step),
-        "add2"  => parsers::parse_add2(&op, step),
-        _       => Err(ProgramError::invalid_op(&op, step)),
+    return match op.op_code() {
+        OpCode::Read2     => Ok(Operation::ct_read()),
+        OpCode::Add2      => Ok(Operation::ct_add()),
+        OpCode::SMul      => Ok(Operation::ct_smul()),
+        OpCode::Dup2      => Ok(Operation::dup(0)),
+        OpCode::Swap2     => Ok(Operation::swap()),
+        OpCode::Drop2     => Ok(Operation::drop()),
+        OpCode::CSwap2    => Ok(Operation::cswap()),
+        OpCode::CSel2     => Ok(Operation::csel()),
+        OpCode::MemLoad2  => Ok(Operation::mem_load(op.value())),
+        OpCode::MemStore2 => Ok(Operation::mem_store(op.value())),
+        OpCode::SAdd | OpCode::Push2 => {
+            let op: Vec<&str> = line.split('.').collect();
+            Err(ProgramError::stack_only_op(&op, step))
+        }
+        _ => Ok(op),
     };
 }
 
-fn compute_padding(length: usize) -> usize {
-    length + (CYCLE_LENGTH - (length % CYCLE_LENGTH))
+fn parse_op(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    #[rustfmt::skip]
+    return match op[0] {
+        "push"  => parsers::parse_push(op, step),
+        "push2" => parsers::parse_push2(op, step),
+        "read"  => parsers::parse_read(op, step),
+        "read2" => parsers::parse_read2(op, step),
+        "mem_load"  => parsers::parse_mem_load(op, step),
+        "mem_load2" => parsers::parse_mem_load2(op, step),
+        "mem_store" => parsers::parse_mem_store(op, step),
+        "mem_store2"=> parsers::parse_mem_store2(op, step),
+        "add"   => parsers::parse_add(op, step),
+        "sub"   => parsers::parse_sub(op, step),
+        "mul"   => parsers::parse_mul(op, step),
+        "neg"   => parsers::parse_neg(op, step),
+        "inv"   => parsers::parse_inv(op, step),
+        "sadd"  => parsers::parse_sadd(op, step),
+        "smul"  => parsers::parse_smul(op, step),
+        "add2"  => parsers::parse_add2(op, step),
+        "dup"   => parsers::parse_dup(op, step),
+        "dup2"  => parsers::parse_dup2(op, step),
+        "swap"  => parsers::parse_swap(op, step),
+        "swap2" => parsers::parse_swap2(op, step),
+        "drop"  => parsers::parse_drop(op, step),
+        "drop2" => parsers::parse_drop2(op, step),
+        "movup" => parsers::parse_movup(op, step),
+        "movdn" => parsers::parse_movdn(op, step),
+        "cswap" => parsers::parse_cswap(op, step),
+        "csel"  => parsers::parse_csel(op, step),
+        "cswap2"=> parsers::parse_cswap2(op, step),
+        "csel2" => parsers::parse_csel2(op, step),
+        "assert"    => parsers::parse_assert(op, step),
+        "assertz"   => parsers::parse_assertz(op, step),
+        "assert_eq" => parsers::parse_assert_eq(op, step),
+        "u32add"    => parsers::parse_u32add(op, step),
+        "u32sub"    => parsers::parse_u32sub(op, step),
+        "u32mul"    => parsers::parse_u32mul(op, step),
+        "u32div"    => parsers::parse_u32div(op, step),
+        "u32mod"    => parsers::parse_u32mod(op, step),
+        "and"       => parsers::parse_and(op, step),
+        "or"        => parsers::parse_or(op, step),
+        "xor"       => parsers::parse_xor(op, step),
+        "shl"       => parsers::parse_shl(op, step),
+        "shr"       => parsers::parse_shr(op, step),
+        "eq"        => parsers::parse_eq(op, step),
+        "neq"       => parsers::parse_neq(op, step),
+        "lt"        => parsers::parse_lt(op, step),
+        "lte"       => parsers::parse_lte(op, step),
+        "hperm"     => parsers::parse_hperm(op, step),
+        "ct_read"   => parsers::parse_ct_read(op, step),
+        "ct_add"    => parsers::parse_ct_add(op, step),
+        "ct_smul"   => parsers::parse_ct_smul(op, step),
+        "ct_mac"    => parsers::parse_ct_mac(op, step),
+        _       => Err(ProgramError::invalid_op(op, step)),
+    };
 }
 
 impl std::fmt::Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}{}", self.code[0].op_code(), self.code[0].op_value())?;
+        let code = self.code();
+
+        write!(f, "{}{}", code[0].op_code(), code[0].op_value())?;
 
-        for i in 1..self.code.len() {
-            write!(f, " {}{}", self.code[i].op_code(), self.code[i].op_value())?;
+        for op in code.iter().skip(1) {
+            write!(f, " {}{}", op.op_code(), op.op_value())?;
         }
 
         Ok(())
@@ -0,0 +1,112 @@
+use std::{collections::HashMap, fs, path::PathBuf, rc::Rc};
+
+use super::{
+    assembler::{Assembler, ProcMap},
+    errors::ProgramError,
+    tokenize,
+};
+
+// use.dir::module imports dir/module.txt from the module search path
+const MODULE_EXTENSION: &str = "txt";
+
+// modules bundled with the vm, use.std::module imports them whatever the search path
+const STDLIB_NAMESPACE: &str = "std";
+const STDLIB: [(&str, &str); 3] = [
+    ("vector", include_str!("../../stdlib/vector.txt")),
+    ("poly", include_str!("../../stdlib/poly.txt")),
+    ("stats", include_str!("../../stdlib/stats.txt")),
+];
+
+// resolves imports against the module search path, each module is compiled once
+pub struct ModuleLoader {
+    search_path: Vec<PathBuf>,
+    modules: HashMap<PathBuf, Rc<ProcMap>>,
+    // files being compiled, from the program to the innermost import
+    loading: Vec<PathBuf>,
+}
+
+impl ModuleLoader {
+    pub fn new(search_path: &[PathBuf]) -> ModuleLoader {
+        ModuleLoader {
+            search_path: search_path.to_vec(),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+
+    // the program file, a module importing it closes a cycle
+    pub fn enter(&mut self, file: PathBuf) {
+        self.loading.push(fs::canonicalize(&file).unwrap_or(file));
+    }
+
+    // procedures defined by the module imported with op
+    pub fn load(&mut self, op: &[&str], path: &[&str], step: usize) -> Result<Rc<ProcMap>, ProgramError> {
+        if path[0] == STDLIB_NAMESPACE {
+            return self.load_stdlib(op, path, step);
+        }
+
+        let relative = path.iter().collect::<PathBuf>().with_extension(MODULE_EXTENSION);
+
+        let file = match self
+            .search_path
+            .iter()
+            .map(|dir| dir.join(&relative))
+            .find(|file| file.is_file())
+        {
+            Some(file) => fs::canonicalize(&file).unwrap_or(file),
+            None => return Err(ProgramError::module_not_found(op, step)),
+        };
+
+        if self.loading.contains(&file) {
+            return Err(ProgramError::import_cycle(op, step));
+        }
+
+        if let Some(procs) = self.modules.get(&file) {
+            return Ok(procs.clone());
+        }
+
+        let name = file.display().to_string();
+
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(err) => return Err(ProgramError::read_error(&err.to_string().to_lowercase()).in_file(&name)),
+        };
+
+        self.compile(file, &name, &source)
+    }
+
+    fn load_stdlib(&mut self, op: &[&str], path: &[&str], step: usize) -> Result<Rc<ProcMap>, ProgramError> {
+        let source = match STDLIB.iter().find(|(module, _)| path[1..] == [*module]) {
+            Some((_, source)) => source,
+            None => return Err(ProgramError::module_not_found(op, step)),
+        };
+
+        // bundled modules are keyed by their relative path, files by their absolute one
+        let name = path.join("::");
+        let file = PathBuf::from(&name);
+
+        if self.loading.contains(&file) {
+            return Err(ProgramError::import_cycle(op, step));
+        }
+
+        if let Some(procs) = self.modules.get(&file) {
+            return Ok(procs.clone());
+        }
+
+        self.compile(file, &name, source)
+    }
+
+    fn compile(&mut self, file: PathBuf, name: &str, source: &str) -> Result<Rc<ProcMap>, ProgramError> {
+        self.loading.push(file.clone());
+
+        let procs = Assembler::new()
+            .with_file(name)
+            .compile_module(&tokenize(source), self)
+            .map_err(|err| err.in_file(name))?;
+
+        self.loading.pop();
+        self.modules.insert(file, procs.clone());
+
+        Ok(procs)
+    }
+}
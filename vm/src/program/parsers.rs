@@ -1,5 +1,8 @@
 use super::errors::ProgramError;
 use super::Operation;
+use crate::processor::STACK_WIDTH;
+
+use winterfell::math::{fields::f128::BaseElement, FieldElement, StarkField};
 
 pub fn parse_push(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     if op.len() == 1 {
@@ -8,14 +11,29 @@ pub fn parse_push(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
         return Err(ProgramError::extra_param(op, step));
     }
 
-    let value = match op[1].parse::<u8>() {
-        Ok(i) => i,
-        Err(_) => return Err(ProgramError::invalid_param(op, step)),
+    let value = match parse_element(op[1]) {
+        Some(value) => value,
+        None => return Err(ProgramError::invalid_param(op, step)),
     };
 
     Ok(Operation::push(value))
 }
 
+pub fn parse_push2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let value = match parse_element(op[1]) {
+        Some(value) => value,
+        None => return Err(ProgramError::invalid_param(op, step)),
+    };
+
+    Ok(Operation::push2(value))
+}
+
 pub fn parse_read(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     if op.len() > 1 {
         return Err(ProgramError::extra_param(op, step));
@@ -30,6 +48,26 @@ pub fn parse_read2(op: &[&str], step: usize) -> Result<Operation, ProgramError>
     Ok(Operation::read2())
 }
 
+pub fn parse_mem_load(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let addr = parse_address(op, step)?;
+    Ok(Operation::mem_load(addr))
+}
+
+pub fn parse_mem_load2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let addr = parse_address(op, step)?;
+    Ok(Operation::mem_load2(addr))
+}
+
+pub fn parse_mem_store(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let addr = parse_address(op, step)?;
+    Ok(Operation::mem_store(addr))
+}
+
+pub fn parse_mem_store2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let addr = parse_address(op, step)?;
+    Ok(Operation::mem_store2(addr))
+}
+
 pub fn parse_add(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     if op.len() > 1 {
         return Err(ProgramError::extra_param(op, step));
@@ -37,6 +75,13 @@ pub fn parse_add(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     Ok(Operation::add())
 }
 
+pub fn parse_sub(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::sub())
+}
+
 pub fn parse_mul(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     if op.len() > 1 {
         return Err(ProgramError::extra_param(op, step));
@@ -44,6 +89,297 @@ pub fn parse_mul(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     Ok(Operation::mul())
 }
 
+pub fn parse_neg(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::neg())
+}
+
+pub fn parse_inv(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::inv())
+}
+
+// the constraint degree can't grow with the exponent
+// pow.n expands to square-and-multiply with the base kept below the accumulator
+pub fn parse_pow(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let exponent = match op[1].parse::<u32>() {
+        Ok(i) => i,
+        Err(_) => return Err(ProgramError::invalid_param(op, step)),
+    };
+
+    match exponent {
+        0 => return Ok(vec![Operation::drop(), Operation::push(BaseElement::ONE)]),
+        1 => return Ok(Vec::new()),
+        _ => (),
+    }
+
+    let mut code = vec![Operation::dup(0)];
+
+    // skip the most significant bit, the accumulator starts at the base
+    for i in (0..(u32::BITS - exponent.leading_zeros() - 1)).rev() {
+        code.extend([Operation::dup(0), Operation::mul()]);
+        if exponent >> i & 1 == 1 {
+            code.extend([Operation::dup(1), Operation::mul()]);
+        }
+    }
+
+    code.extend([Operation::swap(), Operation::drop()]);
+
+    Ok(code)
+}
+
+pub fn parse_condition(op: &[&str], step: usize) -> Result<(), ProgramError> {
+    match op.len() {
+        0 | 1 => Err(ProgramError::missing_param(op, step)),
+        2 if op[1] == "true" => Ok(()),
+        2 => Err(ProgramError::invalid_param(op, step)),
+        _ => Err(ProgramError::extra_param(op, step)),
+    }
+}
+
+// the body of repeat.n is unrolled n times
+pub fn parse_repeat(op: &[&str], step: usize) -> Result<usize, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    match op[1].parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(ProgramError::invalid_param(op, step)),
+    }
+}
+
+// proc takes a procedure name
+pub fn parse_proc_name<'a>(op: &[&'a str], step: usize) -> Result<&'a str, ProgramError> {
+    let path = parse_path(op, step)?;
+
+    if path.len() > 1 {
+        return Err(ProgramError::invalid_param(op, step));
+    }
+
+    Ok(op[1])
+}
+
+// exec and call take a procedure name or module::name for an imported procedure
+pub fn parse_proc_ref<'a>(op: &[&'a str], step: usize) -> Result<&'a str, ProgramError> {
+    let path = parse_path(op, step)?;
+
+    if path.len() > 2 {
+        return Err(ProgramError::invalid_param(op, step));
+    }
+
+    Ok(op[1])
+}
+
+// use takes a module path dir::module
+pub fn parse_use<'a>(op: &[&'a str], step: usize) -> Result<Vec<&'a str>, ProgramError> {
+    parse_path(op, step)
+}
+
+// names separated by ::, a name is a letter or underscore followed by letters, digits or underscores
+fn parse_path<'a>(op: &[&'a str], step: usize) -> Result<Vec<&'a str>, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let path: Vec<&str> = op[1].split("::").collect();
+
+    let valid = path.iter().all(|name| {
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            _ => false,
+        }
+    });
+
+    if !valid {
+        return Err(ProgramError::invalid_param(op, step));
+    }
+
+    Ok(path)
+}
+
+// else and end take no parameters
+pub fn parse_block_end(op: &[&str], step: usize) -> Result<(), ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(())
+}
+
+pub fn parse_cswap(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::cswap())
+}
+
+pub fn parse_csel(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::csel())
+}
+
+pub fn parse_cswap2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::cswap2())
+}
+
+pub fn parse_csel2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::csel2())
+}
+
+pub fn parse_assert(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::assert())
+}
+
+pub fn parse_assertz(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::assertz())
+}
+
+pub fn parse_assert_eq(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::assert_eq())
+}
+
+pub fn parse_u32add(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::u32add())
+}
+
+pub fn parse_u32sub(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::u32sub())
+}
+
+pub fn parse_u32mul(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::u32mul())
+}
+
+pub fn parse_u32div(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::u32div())
+}
+
+pub fn parse_u32mod(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::u32mod())
+}
+
+pub fn parse_and(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::and())
+}
+
+pub fn parse_or(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::or())
+}
+
+pub fn parse_xor(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::xor())
+}
+
+pub fn parse_shl(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let n = parse_shift(op, step)?;
+    Ok(Operation::shl(n))
+}
+
+pub fn parse_shr(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let n = parse_shift(op, step)?;
+    Ok(Operation::shr(n))
+}
+
+pub fn parse_eq(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::eq())
+}
+
+pub fn parse_neq(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::neq())
+}
+
+pub fn parse_lt(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::lt())
+}
+
+pub fn parse_lte(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::lte())
+}
+
+// a > b is b < a, the operands are swapped before lt
+pub fn parse_gt(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(vec![Operation::swap(), Operation::lt()])
+}
+
+// a >= b is b <= a, the operands are swapped before lte
+pub fn parse_gte(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(vec![Operation::swap(), Operation::lte()])
+}
+
 pub fn parse_sadd(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     if op.len() > 1 {
         return Err(ProgramError::extra_param(op, step));
@@ -64,3 +400,290 @@ pub fn parse_add2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
     }
     Ok(Operation::add2())
 }
+
+pub fn parse_dup(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    // dup copies the top element
+    if op.len() == 1 {
+        return Ok(Operation::dup(0));
+    }
+
+    let n = parse_stack_index(op, step, 0)?;
+    Ok(Operation::dup(n))
+}
+
+pub fn parse_dup2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::dup2())
+}
+
+pub fn parse_swap(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::swap())
+}
+
+pub fn parse_swap2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::swap2())
+}
+
+pub fn parse_drop(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::drop())
+}
+
+pub fn parse_drop2(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::drop2())
+}
+
+pub fn parse_movup(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let n = parse_stack_index(op, step, 2)?;
+    Ok(Operation::movup(n))
+}
+
+pub fn parse_movdn(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    let n = parse_stack_index(op, step, 2)?;
+    Ok(Operation::movdn(n))
+}
+
+pub fn parse_ct_read(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::ct_read())
+}
+
+pub fn parse_ct_add(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::ct_add())
+}
+
+pub fn parse_ct_smul(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::ct_smul())
+}
+
+pub fn parse_ct_mac(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::ct_mac())
+}
+
+// ct_dot.n takes n scalar and handle pairs [w1, h1, w2, h2, ..]
+// the first product starts the accumulator, each next pair moves above it for a ct_mac
+pub fn parse_ct_dot(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let count = match op[1].parse::<usize>() {
+        Ok(count) if count > 0 => count,
+        _ => return Err(ProgramError::invalid_param(op, step)),
+    };
+
+    let mut code = vec![Operation::ct_smul()];
+
+    for _ in 1..count {
+        code.extend([Operation::movdn(2), Operation::ct_mac()]);
+    }
+
+    Ok(code)
+}
+
+pub fn parse_hperm(op: &[&str], step: usize) -> Result<Operation, ProgramError> {
+    if op.len() > 1 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+    Ok(Operation::hperm())
+}
+
+// hashes the top n elements into the digest [d0, d1] like crypto::rescue::hash_elements
+pub fn parse_hash(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let count = match op[1].parse::<u32>() {
+        Ok(count) if count > 0 => count,
+        _ => return Err(ProgramError::invalid_param(op, step)),
+    };
+
+    Ok(hash_ops(count))
+}
+
+// the state [r0, r1, n, 0] is pushed over the elements, they are absorbed two at a time before each hperm
+fn hash_ops(count: u32) -> Vec<Operation> {
+    let mut code = vec![
+        Operation::push(BaseElement::ZERO),
+        Operation::push(BaseElement::from(count)),
+        Operation::push(BaseElement::ZERO),
+        Operation::push(BaseElement::ZERO),
+    ];
+
+    // the next element sits below the state, r0 += e0 then r1 += e1
+    for chunk in 0..count.div_ceil(2) {
+        code.extend([Operation::movup(4), Operation::add()]);
+        if 2 * chunk + 1 < count {
+            code.extend([
+                Operation::swap(),
+                Operation::movup(4),
+                Operation::add(),
+                Operation::swap(),
+            ]);
+        }
+        code.push(Operation::hperm());
+    }
+
+    // drop the capacity
+    code.extend([
+        Operation::movup(2),
+        Operation::drop(),
+        Operation::movup(2),
+        Operation::drop(),
+    ]);
+
+    code
+}
+
+// checks the path of depth d from a leaf to a root like crypto::merkle::compute_root
+// the stack holds [index, leaf, sibling_0, ..., sibling_d-1, root], two elements per digest, and is popped
+// bit i of the index orders the node and the sibling i before hashing them, the index must be below 2^d
+pub fn parse_mtree_verify(op: &[&str], step: usize) -> Result<Vec<Operation>, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    let depth = match op[1].parse::<u32>() {
+        Ok(depth) if depth > 0 && depth <= u32::BITS => depth,
+        _ => return Err(ProgramError::invalid_param(op, step)),
+    };
+
+    let mut code = Vec::new();
+
+    for _ in 0..depth {
+        // [i, n, s] -> [b, q, n, s] with q = i >> 1 and b = i - 2q
+        code.extend([
+            Operation::dup(0),
+            Operation::shr(1),
+            Operation::dup(0),
+            Operation::dup(0),
+            Operation::add(),
+            Operation::movup(2),
+            Operation::swap(),
+            Operation::sub(),
+        ]);
+
+        // [b, q, n0, n1, s0, s1] -> [n0, s0, n1, s1, b, q]
+        code.extend([
+            Operation::swap(),
+            Operation::movdn(5),
+            Operation::movdn(4),
+            Operation::movup(2),
+            Operation::swap(),
+        ]);
+
+        // swaps the node and the sibling when b is 1, the left child comes first
+        code.extend([
+            Operation::dup(4),
+            Operation::cswap(),
+            Operation::movup(2),
+            Operation::movup(3),
+            Operation::swap(),
+            Operation::movup(4),
+            Operation::cswap(),
+            Operation::movup(2),
+            Operation::movup(3),
+            Operation::movdn(2),
+        ]);
+
+        // [left, right, q] -> [q, parent]
+        code.extend(hash_ops(4));
+        code.push(Operation::movup(2));
+    }
+
+    // no index bits are left and the root matches
+    code.extend([
+        Operation::assertz(),
+        Operation::movup(2),
+        Operation::assert_eq(),
+        Operation::assert_eq(),
+    ]);
+
+    Ok(code)
+}
+
+// parses a field element in decimal or 0x prefixed hex notation
+fn parse_element(param: &str) -> Option<BaseElement> {
+    let value = match param.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => param.parse::<u128>(),
+    };
+
+    match value {
+        Ok(value) if value < BaseElement::MODULUS => Some(BaseElement::new(value)),
+        _ => None,
+    }
+}
+
+// parses a memory address in [0, 2^16), the air range checks the deltas between sorted addresses
+fn parse_address(op: &[&str], step: usize) -> Result<BaseElement, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    match op[1].parse::<u16>() {
+        Ok(addr) => Ok(BaseElement::from(addr)),
+        Err(_) => Err(ProgramError::invalid_param(op, step)),
+    }
+}
+
+// parses a shift amount of a u32 value in [0, 32)
+fn parse_shift(op: &[&str], step: usize) -> Result<u32, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    match op[1].parse::<u32>() {
+        Ok(n) if n < u32::BITS => Ok(n),
+        _ => Err(ProgramError::invalid_param(op, step)),
+    }
+}
+
+// parses a stack position in [min, STACK_WIDTH)
+fn parse_stack_index(op: &[&str], step: usize, min: usize) -> Result<usize, ProgramError> {
+    if op.len() == 1 {
+        return Err(ProgramError::missing_param(op, step));
+    } else if op.len() > 2 {
+        return Err(ProgramError::extra_param(op, step));
+    }
+
+    match op[1].parse::<usize>() {
+        Ok(n) if n >= min && n < STACK_WIDTH => Ok(n),
+        _ => Err(ProgramError::invalid_param(op, step)),
+    }
+}
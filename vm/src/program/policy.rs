@@ -0,0 +1,107 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crypto::{rescue, Hash};
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement},
+    ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable,
+};
+
+pub const MAX_POLICY_PROGRAMS: usize = 1 << 10;
+
+// restricts the programs allowed to consume an input bundle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Policy {
+    programs: Vec<Hash>,
+    expiry: Option<u64>,
+}
+
+impl Policy {
+    pub fn new(programs: &[Hash]) -> Policy {
+        Policy {
+            programs: programs.to_vec(),
+            expiry: None,
+        }
+    }
+
+    // expiry is a unix timestamp in seconds
+    pub fn with_expiry(mut self, expiry: u64) -> Policy {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    pub fn programs(&self) -> &[Hash] {
+        &self.programs
+    }
+
+    pub fn expiry(&self) -> Option<u64> {
+        self.expiry
+    }
+
+    pub fn allows(&self, program_hash: &Hash) -> bool {
+        self.programs.contains(program_hash)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expiry, Some(expiry) if now >= expiry)
+    }
+
+    pub fn is_expired_now(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(u64::MAX);
+        self.is_expired(now)
+    }
+
+    // [0, program hashes...] without an expiry, [1, expiry, program hashes...] otherwise
+    pub fn digest(&self) -> Hash {
+        let mut elements = match self.expiry {
+            Some(expiry) => vec![BaseElement::ONE, BaseElement::from(expiry)],
+            None => vec![BaseElement::ZERO],
+        };
+        for hash in self.programs.iter() {
+            elements.extend(hash.to_elements());
+        }
+        rescue::hash_elements(&elements)
+    }
+}
+
+impl Serializable for Policy {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_usize(self.programs.len());
+        for hash in self.programs.iter() {
+            hash.write_into(target);
+        }
+
+        // the expiry only follows its presence flag
+        target.write_bool(self.expiry.is_some());
+        if let Some(expiry) = self.expiry {
+            target.write_u64(expiry);
+        }
+    }
+}
+
+impl Deserializable for Policy {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let num_programs = source.read_usize()?;
+
+        if num_programs > MAX_POLICY_PROGRAMS {
+            return Err(DeserializationError::InvalidValue(format!(
+                "policy program count {num_programs} exceeds the limit of {MAX_POLICY_PROGRAMS}"
+            )));
+        }
+
+        let mut programs = Vec::new();
+
+        for _ in 0..num_programs {
+            programs.push(Hash::read_from(source)?);
+        }
+
+        let expiry = match source.read_bool()? {
+            true => Some(source.read_u64()?),
+            false => None,
+        };
+
+        Ok(Policy { programs, expiry })
+    }
+}
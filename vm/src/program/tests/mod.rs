@@ -1,10 +1,20 @@
-use std::io::Write;
+use std::{io::Write, rc::Rc};
 use tempfile::NamedTempFile;
 
+use winterfell::{
+    math::{fields::f128::BaseElement, FieldElement, StarkField},
+    Deserializable, Serializable,
+};
+
+use super::CodeBlock;
 use super::Operation;
+use super::Policy;
 use super::Program;
 use super::ProgramError;
+use crate::processor::OpCode;
 
+#[cfg(test)]
+mod modules;
 #[cfg(test)]
 mod parsers;
 
@@ -88,11 +98,19 @@ fn test_program_padding() {
     let code = program.code();
 
     assert_eq!(code.len() as u8 % 16, 0);
-    assert_eq!(code[8], Operation::push(2));
+    assert_eq!(code[8], Operation::push(BaseElement::from(2u8)));
     assert_eq!(code[14], Operation::noop());
     assert_eq!(code[15], Operation::noop());
 }
 
+#[test]
+fn test_program_hash_commits_to_immediates() {
+    let program0 = Program::compile("push.1").unwrap();
+    let program1 = Program::compile("push.257").unwrap();
+
+    assert_ne!(program0.hash(), program1.hash());
+}
+
 #[test]
 fn test_empty_program() {
     let source = "";
@@ -100,3 +118,347 @@ fn test_empty_program() {
 
     assert_eq!(format!("{error}"), format!("{}", ProgramError::empty_program()));
 }
+
+#[test]
+fn test_policy() {
+    let program = Program::compile("push.1\npush.2\nadd").unwrap();
+    let other = Program::compile("push.1\npush.2\nmul").unwrap();
+
+    let policy = Policy::new(&[program.hash()]).with_expiry(100);
+
+    assert!(policy.allows(&program.hash()));
+    assert!(!policy.allows(&other.hash()));
+
+    assert!(!policy.is_expired(99));
+    assert!(policy.is_expired(100));
+
+    assert_ne!(policy.digest(), Policy::new(&[program.hash()]).digest());
+    assert_ne!(policy.digest(), Policy::new(&[other.hash()]).with_expiry(100).digest());
+
+    // no expiry is not an expiry at 0
+    let never = Policy::new(&[program.hash()]);
+    let expired = Policy::new(&[program.hash()]).with_expiry(0);
+
+    assert_ne!(never.digest(), expired.digest());
+    assert_ne!(never.to_bytes(), expired.to_bytes());
+}
+
+#[test]
+fn test_serialize_and_deserialize_policy() {
+    let program = Program::compile("push.1\npush.2\nadd").unwrap();
+
+    for policy in [
+        Policy::new(&[program.hash()]),
+        Policy::new(&[program.hash()]).with_expiry(100),
+        Policy::new(&[program.hash()]).with_expiry(0),
+    ] {
+        let read_policy = Policy::read_from_bytes(&policy.to_bytes()).unwrap();
+
+        assert_eq!(policy, read_policy);
+        assert_eq!(policy.digest(), read_policy.digest());
+    }
+}
+
+#[test]
+fn test_compile_if_else() {
+    let source = "read\nif.true\npush.1\nelse\npush.2\nend\nadd";
+    let program = Program::compile(source).unwrap();
+
+    match program.blocks() {
+        [CodeBlock::Span(head), CodeBlock::Switch(true_branch, false_branch), CodeBlock::Span(tail)] => {
+            assert_eq!(head[..], [Operation::read()]);
+            assert_eq!(tail[0], Operation::add());
+
+            // both branches start with a guard and end on the cycle copy steps
+            for (branch, guard) in [
+                (true_branch, Operation::true_branch()),
+                (false_branch, Operation::false_branch()),
+            ] {
+                let code = CodeBlock::flatten(branch);
+
+                assert_eq!(code[0], guard);
+                assert_eq!(code.len(), 15);
+            }
+        }
+        blocks => panic!("unexpected blocks {blocks:?}"),
+    }
+}
+
+#[test]
+fn test_if_else_hash_commits_to_branches() {
+    let program = Program::compile("read\nif.true\npush.1\nelse\npush.2\nend").unwrap();
+
+    let swapped = Program::compile("read\nif.true\npush.2\nelse\npush.1\nend").unwrap();
+    let without_else = Program::compile("read\nif.true\npush.1\nend").unwrap();
+
+    assert_ne!(program.hash(), swapped.hash());
+    assert_ne!(program.hash(), without_else.hash());
+}
+
+#[test]
+fn test_unclosed_block_error() {
+    let source = "read\nif.true\npush.1\nelse\npush.2";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::unclosed_block(&["if", "true"], 2))
+    );
+}
+
+#[test]
+fn test_unmatched_block_error() {
+    let source = "read\nif.true\npush.1\nend\nelse";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::unmatched_block(&["else"], 5))
+    );
+}
+
+#[test]
+fn test_compile_while() {
+    let source = "read\nwhile.true\npush.1\nadd\nread\nend\nadd";
+    let program = Program::compile(source).unwrap();
+
+    match program.blocks() {
+        [CodeBlock::Span(head), CodeBlock::Loop(body, exit), CodeBlock::Span(tail)] => {
+            // the loop is entered on the copy steps with the body digest
+            assert_eq!(head.len(), 16);
+            assert_eq!(head[14].op_code(), OpCode::Loop);
+            assert_eq!(head[15].op_code(), OpCode::Loop);
+            assert_eq!(tail[0], Operation::add());
+
+            let body = CodeBlock::flatten(body);
+            assert_eq!(body[0], Operation::true_branch());
+            assert_eq!(body[14..], [Operation::repeat(), Operation::noop()]);
+
+            let exit = CodeBlock::flatten(exit);
+            assert_eq!(exit[0], Operation::false_branch());
+            assert_eq!(exit[14..], [Operation::noop(), Operation::wend()]);
+        }
+        blocks => panic!("unexpected blocks {blocks:?}"),
+    }
+}
+
+#[test]
+fn test_while_hash_commits_to_body() {
+    let program = Program::compile("read\nwhile.true\npush.1\nread\nend").unwrap();
+    let other = Program::compile("read\nwhile.true\npush.2\nread\nend").unwrap();
+
+    assert_ne!(program.hash(), other.hash());
+}
+
+#[test]
+fn test_repeat_unrolls_body() {
+    let program = Program::compile("push.1\nrepeat.3\npush.2\nmul\nend").unwrap();
+    let unrolled = Program::compile("push.1\npush.2\nmul\npush.2\nmul\npush.2\nmul").unwrap();
+
+    assert_eq!(program.code(), unrolled.code());
+    assert_eq!(program.hash(), unrolled.hash());
+}
+
+#[test]
+fn test_nested_loop_error() {
+    let source = "read\nwhile.true\nread\nwhile.true\nread\nend\nend";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::nested_loop(&["while", "true"], 4))
+    );
+}
+
+#[test]
+fn test_nested_loop_through_exec_error() {
+    let source = "proc.drain\nread\nwhile.true\nread\nend\nend\nread\nwhile.true\nexec.drain\nread\nend";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::nested_loop(&["while", "true"], 3))
+    );
+}
+
+#[test]
+fn test_while_else_error() {
+    let source = "read\nwhile.true\nread\nelse\nend";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::unmatched_block(&["else"], 4))
+    );
+}
+
+#[test]
+fn test_exec_inlines_procedure() {
+    let program = Program::compile("proc.double\npush.2\nmul\nend\npush.3\nexec.double\nexec.double").unwrap();
+    let inlined = Program::compile("push.3\npush.2\nmul\npush.2\nmul").unwrap();
+
+    assert_eq!(program.code(), inlined.code());
+    assert_eq!(program.hash(), inlined.hash());
+}
+
+#[test]
+fn test_call_reuses_procedure_digest() {
+    let program = Program::compile("proc.double\npush.2\nmul\nend\npush.3\ncall.double\ncall.double").unwrap();
+    let code = program.code();
+
+    // both calls load the same digest whatever the caller state
+    let digests: Vec<_> = code
+        .iter()
+        .filter(|op| op.op_code() == OpCode::Loop || op.op_code() == OpCode::Call)
+        .map(|op| op.value())
+        .collect();
+
+    assert_eq!(digests.len(), 4);
+    assert_eq!(digests[..2], digests[2..]);
+
+    let other = Program::compile("proc.double\npush.2\nadd\nend\npush.3\ncall.double\ncall.double").unwrap();
+    assert_ne!(program.hash(), other.hash());
+}
+
+#[test]
+fn test_compile_with_handles() {
+    // called procedures are lowered too
+    let source = "proc.scale\nread\nsmul\nend\nread2\nread2\nadd2\ncall.scale\ndup2\nswap2\ndrop2\nmem_store2.1\nmem_load2.1\npush.1\ncswap2\npush.0\ncsel2";
+    let lowered = "proc.scale\nread\nct_smul\nend\nct_read\nct_read\nct_add\ncall.scale\ndup.0\nswap\ndrop\nmem_store.1\nmem_load.1\npush.1\ncswap\npush.0\ncsel";
+
+    let program = Program::compile_with_handles(source, &[]).unwrap();
+    let expected = Program::compile(lowered).unwrap();
+
+    assert_eq!(program.code(), expected.code());
+    assert_eq!(program.hash(), expected.hash());
+}
+
+#[test]
+fn test_compile_with_handles_index_ops() {
+    // the indices are kept, a handle is a single stack element
+    let source = "read2\nread2\nread\nmovup.2\ndup.1\nmovdn.2\npush.1\ncswap\npush.0\ncsel";
+    let lowered = "ct_read\nct_read\nread\nmovup.2\ndup.1\nmovdn.2\npush.1\ncswap\npush.0\ncsel";
+
+    let program = Program::compile_with_handles(source, &[]).unwrap();
+    let expected = Program::compile(lowered).unwrap();
+
+    assert_eq!(program.code(), expected.code());
+}
+
+#[test]
+fn test_stack_only_op_error() {
+    let cases: [(&str, &[&str], usize); 2] = [("read2\npush.1\nsadd", &["sadd"], 3), ("push2.3", &["push2", "3"], 1)];
+
+    for (source, op, step) in cases {
+        let error = Program::compile_with_handles(source, &[]).unwrap_err();
+
+        assert_eq!(format!("{error}"), format!("{}", ProgramError::stack_only_op(op, step)));
+    }
+}
+
+#[test]
+fn test_undefined_proc_error() {
+    // a procedure cannot use itself
+    let source = "proc.double\nexec.double\nend\npush.1";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::undefined_proc(&["exec", "double"], 2))
+    );
+}
+
+#[test]
+fn test_duplicate_proc_error() {
+    let source = "proc.double\npush.2\nmul\nend\nproc.double\npush.2\nend\npush.1";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::duplicate_proc(&["proc", "double"], 5))
+    );
+}
+
+#[test]
+fn test_misplaced_proc_error() {
+    let source = "push.1\nproc.double\npush.2\nmul\nend";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::misplaced_proc(&["proc", "double"], 2))
+    );
+}
+
+#[test]
+fn test_call_in_loop_error() {
+    let source = "proc.double\npush.2\nmul\nend\nread\nwhile.true\ncall.double\nread\nend";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::nested_call(&["call", "double"], 7))
+    );
+}
+
+#[test]
+fn test_loop_in_call_error() {
+    let source = "proc.drain
+read
+while.true
+read
+end
+end
+call.drain";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::nested_loop(&["while", "true"], 3))
+    );
+}
+
+#[test]
+fn test_call_in_call_error() {
+    let source = "proc.double
+push.2
+mul
+end
+proc.quad
+call.double
+end
+push.3
+call.quad";
+    let error = Program::compile(source).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::nested_call(&["call", "double"], 6))
+    );
+}
+
+#[test]
+fn test_call_shares_procedure_block() {
+    let program = Program::compile(
+        "proc.double
+push.2
+mul
+end
+push.3
+call.double
+call.double",
+    )
+    .unwrap();
+
+    let calls: Vec<_> = program
+        .blocks()
+        .iter()
+        .filter_map(|block| match block {
+            CodeBlock::Call(body) => Some(body),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(calls.len(), 2);
+    assert!(Rc::ptr_eq(calls[0], calls[1]));
+}
@@ -0,0 +1,192 @@
+use std::{fs, path::Path};
+
+use tempfile::TempDir;
+
+use super::Program;
+use super::ProgramError;
+
+// lib/scaling.txt defines double, lib/affine.txt imports it
+fn library() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+
+    write_module(dir.path(), "lib/scaling.txt", "proc.double\npush.2\nmul\nend");
+    write_module(
+        dir.path(),
+        "lib/affine.txt",
+        "use.lib::scaling\nproc.apply\nexec.scaling::double\npush.1\nadd\nend",
+    );
+
+    dir
+}
+
+fn write_module(dir: &Path, name: &str, source: &str) {
+    let path = dir.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, source).unwrap();
+}
+
+fn file_name(dir: &Path, name: &str) -> String {
+    fs::canonicalize(dir.join(name)).unwrap().display().to_string()
+}
+
+#[test]
+fn test_import_module() {
+    let dir = library();
+    let search_path = [dir.path().to_path_buf()];
+
+    let program =
+        Program::compile_with_search_path("use.lib::affine\npush.3\nexec.affine::apply", &search_path).unwrap();
+    let inlined = Program::compile("push.3\npush.2\nmul\npush.1\nadd").unwrap();
+
+    assert_eq!(program.code(), inlined.code());
+    assert_eq!(program.hash(), inlined.hash());
+
+    // imported procedures can be called
+    Program::compile_with_search_path("use.lib::affine\npush.3\ncall.affine::apply", &search_path).unwrap();
+}
+
+#[test]
+fn test_load_imports_from_program_directory() {
+    let dir = library();
+    write_module(dir.path(), "main.txt", "use.lib::scaling\npush.3\nexec.scaling::double");
+
+    let program = Program::load(&dir.path().join("main.txt")).unwrap();
+    let inlined = Program::compile("push.3\npush.2\nmul").unwrap();
+
+    assert_eq!(program.hash(), inlined.hash());
+}
+
+#[test]
+fn test_imports_are_namespaced() {
+    let dir = library();
+    let search_path = [dir.path().to_path_buf()];
+
+    // the procedures imported by a module are not visible to the importer
+    for source in [
+        "use.lib::affine\npush.3\nexec.scaling::double",
+        "use.lib::scaling\npush.3\nexec.double",
+    ] {
+        let error = Program::compile_with_search_path(source, &search_path).unwrap_err();
+        let op: Vec<&str> = source.lines().nth(2).unwrap().split('.').collect();
+
+        assert_eq!(format!("{error}"), format!("{}", ProgramError::undefined_proc(&op, 3)));
+    }
+}
+
+#[test]
+fn test_module_not_found_error() {
+    let dir = library();
+    write_module(dir.path(), "main.txt", "use.lib::missing\npush.1");
+
+    let path = dir.path().join("main.txt");
+    let error = Program::load(&path).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!(
+            "{}",
+            ProgramError::module_not_found(&["use", "lib::missing"], 1).in_file(&path.display().to_string())
+        )
+    );
+}
+
+#[test]
+fn test_import_cycle_error() {
+    let dir = tempfile::tempdir().unwrap();
+    write_module(dir.path(), "a.txt", "use.b\nproc.x\npush.1\nend");
+    write_module(dir.path(), "b.txt", "use.a\nproc.y\npush.2\nend");
+
+    let error = Program::compile_with_search_path("use.a\npush.1", &[dir.path().to_path_buf()]).unwrap_err();
+
+    // the error names the importing file
+    assert_eq!(
+        format!("{error}"),
+        format!(
+            "{}",
+            ProgramError::import_cycle(&["use", "a"], 1).in_file(&file_name(dir.path(), "b.txt"))
+        )
+    );
+}
+
+#[test]
+fn test_module_body_error() {
+    let dir = tempfile::tempdir().unwrap();
+    write_module(dir.path(), "a.txt", "proc.x\npush.1\nend\npush.2");
+
+    let error = Program::compile_with_search_path("use.a\npush.1", &[dir.path().to_path_buf()]).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!(
+            "{}",
+            ProgramError::module_body(&["push", "2"], 4).in_file(&file_name(dir.path(), "a.txt"))
+        )
+    );
+}
+
+#[test]
+fn test_imported_proc_error() {
+    let dir = tempfile::tempdir().unwrap();
+    write_module(
+        dir.path(),
+        "lib/m.txt",
+        "proc.double\npush.2\nmul\nend\nproc.loopy\npush.1\nwhile.true\npush.0\nend\nend\nproc.caller\ncall.double\nend",
+    );
+
+    // a procedure body rejected inside the loop of the importer is reported at its step in the module
+    for (proc, op, step) in [("loopy", ["while", "true"], 7), ("caller", ["call", "double"], 12)] {
+        let source = format!("use.lib::m\npush.1\nwhile.true\nexec.m::{proc}\npush.0\nend");
+        let error = Program::compile_with_search_path(&source, &[dir.path().to_path_buf()]).unwrap_err();
+
+        let expected = match op[0] {
+            "while" => ProgramError::nested_loop(&op, step),
+            _ => ProgramError::nested_call(&op, step),
+        };
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", expected.in_file(&file_name(dir.path(), "lib/m.txt")))
+        );
+    }
+}
+
+#[test]
+fn test_duplicate_module_error() {
+    let dir = library();
+    let source = "use.lib::scaling\nuse.lib::scaling\npush.1";
+
+    let error = Program::compile_with_search_path(source, &[dir.path().to_path_buf()]).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::duplicate_module(&["use", "lib::scaling"], 2))
+    );
+}
+
+#[test]
+fn test_misplaced_import_error() {
+    let dir = library();
+    let source = "push.1\nuse.lib::scaling";
+
+    let error = Program::compile_with_search_path(source, &[dir.path().to_path_buf()]).unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::misplaced_import(&["use", "lib::scaling"], 2))
+    );
+}
+
+#[test]
+fn test_import_stdlib() {
+    // the bundled modules do not depend on the search path
+    for module in ["vector", "poly", "stats"] {
+        Program::compile(&format!("use.std::{module}\npush.1")).unwrap();
+    }
+
+    let error = Program::compile("use.std::missing\npush.1").unwrap_err();
+
+    assert_eq!(
+        format!("{error}"),
+        format!("{}", ProgramError::module_not_found(&["use", "std::missing"], 1))
+    );
+}
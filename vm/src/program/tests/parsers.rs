@@ -185,7 +185,30 @@ mod push {
         let program = Program::compile(source).unwrap();
         let code = program.code();
 
-        assert_eq!(code[0], Operation::push(1));
+        assert_eq!(code[0], Operation::push(BaseElement::from(1u8)));
+    }
+
+    #[test]
+    fn test_parse_field_element() {
+        let source = "push.0xff\npush.340282366920938463463374557953744961536";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::push(BaseElement::from(255u8)));
+        assert_eq!(code[8], Operation::push(-BaseElement::ONE));
+    }
+
+    #[test]
+    fn test_modulus_error() {
+        let source = format!("push.{}", BaseElement::MODULUS);
+        let error = Program::compile(&source).unwrap_err();
+
+        let modulus = BaseElement::MODULUS.to_string();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["push", &modulus], 1))
+        );
     }
 
     #[test]
@@ -210,3 +233,725 @@ mod push {
         );
     }
 }
+
+#[cfg(test)]
+mod mem {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "mem_store.1\nmem_store2.2\nmem_load.1\nmem_load2.65535";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::mem_store(BaseElement::from(1u8)));
+        assert_eq!(code[1], Operation::mem_store2(BaseElement::from(2u8)));
+        assert_eq!(code[2], Operation::mem_load(BaseElement::from(1u8)));
+        assert_eq!(code[3], Operation::mem_load2(BaseElement::from(65535u16)));
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "mem_load.65536";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["mem_load", "65536"], 1))
+        );
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "mem_store2";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["mem_store2"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod push2 {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "push2.0x3";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::push2(BaseElement::from(3u8)));
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "push2";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["push2"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod dup {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "dup.3\ndup2";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::dup(3));
+        assert_eq!(code[1], Operation::dup2());
+    }
+
+    #[test]
+    fn test_parse_default() {
+        let source = "dup";
+        let program = Program::compile(source).unwrap();
+
+        assert_eq!(program.code()[0], Operation::dup(0));
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "dup.16";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["dup", "16"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod swap {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "swap\nswap2";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::swap());
+        assert_eq!(code[1], Operation::swap2());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "swap.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["swap"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod drop {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "drop\ndrop2";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::drop());
+        assert_eq!(code[1], Operation::drop2());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "drop2.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["drop2"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod movup {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "movup.2\nmovdn.15";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::movup(2));
+        assert_eq!(code[1], Operation::movdn(15));
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "movup.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["movup", "1"], 1))
+        );
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "movdn";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["movdn"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod sub {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "sub\nneg\ninv";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::sub());
+        assert_eq!(code[1], Operation::neg());
+        assert_eq!(code[2], Operation::inv());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "inv.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["inv"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod pow {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        // 5 = 0b101: square, square and multiply
+        let source = "pow.5";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(
+            code[..9],
+            [
+                Operation::dup(0),
+                Operation::dup(0),
+                Operation::mul(),
+                Operation::dup(0),
+                Operation::mul(),
+                Operation::dup(1),
+                Operation::mul(),
+                Operation::swap(),
+                Operation::drop(),
+            ]
+        );
+        assert_eq!(code[9], Operation::noop());
+    }
+
+    #[test]
+    fn test_parse_zero() {
+        let source = "pow.0";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::drop());
+        assert_eq!(code[8], Operation::push(BaseElement::from(1u8)));
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "pow.x";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["pow", "x"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod cswap {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "cswap\ncsel\ncswap2\ncsel2";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::cswap());
+        assert_eq!(code[1], Operation::csel());
+        assert_eq!(code[2], Operation::cswap2());
+        assert_eq!(code[3], Operation::csel2());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "csel.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["csel"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod assert {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "assert\nassertz\nassert_eq";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::assert());
+        assert_eq!(code[1], Operation::assertz());
+        assert_eq!(code[2], Operation::assert_eq());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "assert_eq.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["assert_eq"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod u32_ops {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "u32add\nu32sub\nu32mul\nu32div\nu32mod";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::u32add());
+        assert_eq!(code[1], Operation::u32sub());
+        assert_eq!(code[2], Operation::u32mul());
+        assert_eq!(code[3], Operation::u32div());
+        assert_eq!(code[4], Operation::u32mod());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "u32div.2";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["u32div"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod bitwise {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "and\nor\nxor\nshl.0\nshr.31";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::and());
+        assert_eq!(code[1], Operation::or());
+        assert_eq!(code[2], Operation::xor());
+        assert_eq!(code[3], Operation::shl(0));
+        assert_eq!(code[4], Operation::shr(31));
+
+        // the op value is the power of two of the shift
+        assert_eq!(code[4].value(), BaseElement::from(1u32 << 31));
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "xor.2";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["xor"], 1))
+        );
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "shl";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["shl"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "shr.32";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["shr", "32"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod compare {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "eq\nneq\nlt\nlte";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::eq());
+        assert_eq!(code[1], Operation::neq());
+        assert_eq!(code[2], Operation::lt());
+        assert_eq!(code[3], Operation::lte());
+    }
+
+    #[test]
+    fn test_parse_gt() {
+        let source = "gt\ngte";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        // the operands are swapped before lt and lte
+        assert_eq!(code[0], Operation::swap());
+        assert_eq!(code[1], Operation::lt());
+        assert_eq!(code[2], Operation::swap());
+        assert_eq!(code[3], Operation::lte());
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "gte.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["gte", "1"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod hash {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "hperm\nhash.3";
+        let program = Program::compile(source).unwrap();
+
+        // pushes are aligned with noops
+        let code: Vec<Operation> = program
+            .code()
+            .iter()
+            .filter(|&&op| op != Operation::noop())
+            .copied()
+            .collect();
+
+        assert_eq!(code[0], Operation::hperm());
+
+        // the initial state of three elements, two chunks absorbed and the capacity dropped
+        assert_eq!(
+            code[1..5],
+            [0u8, 3, 0, 0].map(|value| Operation::push(BaseElement::from(value)))
+        );
+        assert_eq!(
+            code[5..],
+            [
+                Operation::movup(4),
+                Operation::add(),
+                Operation::swap(),
+                Operation::movup(4),
+                Operation::add(),
+                Operation::swap(),
+                Operation::hperm(),
+                Operation::movup(4),
+                Operation::add(),
+                Operation::hperm(),
+                Operation::movup(2),
+                Operation::drop(),
+                Operation::movup(2),
+                Operation::drop(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "hash";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["hash"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "hash.0";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["hash", "0"], 1))
+        );
+    }
+}
+
+mod mtree {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "mtree_verify.3";
+        let program = Program::compile(source).unwrap();
+
+        let code: Vec<Operation> = program
+            .code()
+            .iter()
+            .filter(|&&op| op != Operation::noop())
+            .copied()
+            .collect();
+
+        // two permutations to hash the two nodes of each level
+        assert_eq!(code.iter().filter(|&&op| op == Operation::hperm()).count(), 6);
+        assert_eq!(
+            code[code.len() - 4..],
+            [
+                Operation::assertz(),
+                Operation::movup(2),
+                Operation::assert_eq(),
+                Operation::assert_eq(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "mtree_verify";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["mtree_verify"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        for depth in ["0", "33"] {
+            let source = format!("mtree_verify.{depth}");
+            let error = Program::compile(&source).unwrap_err();
+
+            assert_eq!(
+                format!("{error}"),
+                format!("{}", ProgramError::invalid_param(&["mtree_verify", depth], 1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "mtree_verify.2.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["mtree_verify", "2", "1"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod ct {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "ct_read\nct_add\nct_smul\nct_mac";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(code[0], Operation::ct_read());
+        assert_eq!(code[1], Operation::ct_add());
+        assert_eq!(code[2], Operation::ct_smul());
+        assert_eq!(code[3], Operation::ct_mac());
+    }
+
+    #[test]
+    fn test_parse_dot() {
+        let source = "ct_dot.3";
+        let program = Program::compile(source).unwrap();
+        let code = program.code();
+
+        assert_eq!(
+            code[..5],
+            [
+                Operation::ct_smul(),
+                Operation::movdn(2),
+                Operation::ct_mac(),
+                Operation::movdn(2),
+                Operation::ct_mac(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "ct_add.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["ct_add", "1"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "ct_dot.0";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["ct_dot", "0"], 1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod if_else {
+    use super::*;
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "if.false\npush.1\nend";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["if", "false"], 1))
+        );
+    }
+
+    #[test]
+    fn test_extra_param_error() {
+        let source = "if.true\npush.1\nend.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::extra_param(&["end"], 3))
+        );
+    }
+}
+
+mod repeat {
+    use super::*;
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "repeat\npush.1\nend";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["repeat"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "repeat.0\npush.1\nend";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["repeat", "0"], 1))
+        );
+    }
+
+    #[test]
+    fn test_unclosed_block_error() {
+        let source = "repeat.2\npush.1";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::unclosed_block(&["repeat", "2"], 1))
+        );
+    }
+}
+
+mod proc {
+    use super::*;
+
+    #[test]
+    fn test_missing_param_error() {
+        let source = "proc\npush.1\nend";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::missing_param(&["proc"], 1))
+        );
+    }
+
+    #[test]
+    fn test_invalid_param_error() {
+        let source = "proc.1st\npush.1\nend\ncall.1st";
+        let error = Program::compile(source).unwrap_err();
+
+        assert_eq!(
+            format!("{error}"),
+            format!("{}", ProgramError::invalid_param(&["proc", "1st"], 1))
+        );
+    }
+}